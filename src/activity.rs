@@ -0,0 +1,186 @@
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use crate::process;
+
+/// Opt-in sampling of the active window during a session. Only the coarse
+/// category of the window is kept, never its title.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct ActivitySampling {
+    pub every_minutes: i64,
+    /// Extra words per category to look for in window titles, such as
+    /// `{"editor": ["rider"]}`. A category may also be a new one.
+    pub categories: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for ActivitySampling {
+    fn default() -> ActivitySampling {
+        ActivitySampling {
+            every_minutes: 10,
+            categories: BTreeMap::new(),
+        }
+    }
+}
+
+/// Words in window titles that tell the category. Browsers are checked
+/// first, as their titles contain the title of any page, which may well
+/// mention an editor or a terminal.
+const CATEGORIES: [(&str, &[&str]); 3] = [
+    (
+        "browser",
+        &[
+            "firefox",
+            "chrome",
+            "chromium",
+            "safari",
+            "microsoft edge",
+            "brave",
+            "vivaldi",
+        ],
+    ),
+    (
+        "editor",
+        &[
+            "visual studio",
+            "vscode",
+            "vim",
+            "emacs",
+            "intellij",
+            "pycharm",
+            "clion",
+            "rustrover",
+            "sublime text",
+            "xcode",
+            "android studio",
+            "helix",
+        ],
+    ),
+    (
+        "terminal",
+        &[
+            "terminal",
+            "iterm",
+            "alacritty",
+            "kitty",
+            "konsole",
+            "wezterm",
+            "tmux",
+            "xterm",
+            "powershell",
+            "cmd.exe",
+            "bash",
+            "zsh",
+        ],
+    ),
+];
+
+/// The category of a window with `title`, or "other".
+pub fn categorize(config: &ActivitySampling, title: &str) -> String {
+    let title = title.to_lowercase();
+    let configured = config.categories.iter().map(|(category, words)| {
+        (
+            category.as_str(),
+            words.iter().map(String::as_str).collect(),
+        )
+    });
+    let built_in = CATEGORIES
+        .iter()
+        .map(|(category, words)| (*category, words.to_vec()));
+    configured
+        .chain(built_in)
+        .find(|(_, words): &(&str, Vec<&str>)| {
+            words
+                .iter()
+                .any(|word| title.contains(&word.to_lowercase()))
+        })
+        .map_or("other".to_string(), |(category, _)| category.to_string())
+}
+
+/// The title of the active window, from `xdotool` on Linux, the name of the
+/// frontmost application on macOS, or PowerShell on Windows.
+pub fn active_window() -> Result<String, String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(
+            "tell application \"System Events\" to get name of first application process whose frontmost is true",
+        );
+        command
+    } else if cfg!(windows) {
+        let mut command = Command::new("powershell");
+        command.args([
+            "-NoProfile",
+            "-Command",
+            "$w = Add-Type -Name W -PassThru -MemberDefinition '[DllImport(\"user32.dll\")] public static extern IntPtr GetForegroundWindow();'; \
+             (Get-Process | Where-Object { $_.MainWindowHandle -eq $w::GetForegroundWindow() }).MainWindowTitle",
+        ]);
+        command
+    } else {
+        let mut command = Command::new("xdotool");
+        command.args(["getactivewindow", "getwindowname"]);
+        command
+    };
+    match process::output(&mut command) {
+        Ok(output) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        }
+        Ok(output) => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        Err(e) => Err(format!("Cannot read the active window: {}", e)),
+    }
+}
+
+/// The sampled categories with their share of the samples, most common
+/// first, such as "editor 60%, browser 30%, terminal 10%".
+pub fn hints(samples: &BTreeMap<String, u32>) -> Option<String> {
+    let total: u32 = samples.values().sum();
+    if total == 0 {
+        return None;
+    }
+    let mut samples = samples.iter().collect::<Vec<(&String, &u32)>>();
+    samples.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+    Some(
+        samples
+            .iter()
+            .map(|(category, count)| {
+                format!("{} {:.0}%", category, **count as f64 * 100.0 / total as f64)
+            })
+            .collect::<Vec<String>>()
+            .join(", "),
+    )
+}
+
+/// Adds the samples of `from` to `to`.
+pub fn add(to: &mut BTreeMap<String, u32>, from: &BTreeMap<String, u32>) {
+    for (category, count) in from {
+        *to.entry(category.clone()).or_insert(0) += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_categorize() {
+        let mut config = ActivitySampling::default();
+        assert_eq!(
+            categorize(&config, "main.rs - jobclock - Visual Studio Code"),
+            "editor"
+        );
+        assert_eq!(categorize(&config, "Vim tips - Mozilla Firefox"), "browser");
+        assert_eq!(categorize(&config, "Alacritty"), "terminal");
+        assert_eq!(categorize(&config, "Slack | general"), "other");
+        config
+            .categories
+            .insert("chat".to_string(), vec!["Slack".to_string()]);
+        assert_eq!(categorize(&config, "Slack | general"), "chat");
+
+        let mut samples = BTreeMap::new();
+        assert_eq!(hints(&samples), None);
+        add(
+            &mut samples,
+            &BTreeMap::from([("editor".to_string(), 3), ("browser".to_string(), 1)]),
+        );
+        assert_eq!(hints(&samples).as_deref(), Some("editor 75%, browser 25%"));
+    }
+}
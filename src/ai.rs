@@ -0,0 +1,95 @@
+/// Settings for `end --ai-summary`, which sends the task titles of the
+/// session to an OpenAI-compatible chat completions endpoint. Requires a
+/// build with the `ai-summary` feature.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct AiSummaryConfig {
+    pub endpoint: String,
+    pub model: String,
+    /// Environment variable holding the API key, so it is not stored in the
+    /// config file.
+    pub api_key_env: String,
+    pub prompt: String,
+}
+
+impl Default for AiSummaryConfig {
+    fn default() -> AiSummaryConfig {
+        AiSummaryConfig {
+            endpoint: "https://api.openai.com/v1/chat/completions".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            api_key_env: "OPENAI_API_KEY".to_string(),
+            prompt: "Write a short summary in prose of a work session with the tasks below. \
+                     Answer with the summary only."
+                .to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "ai-summary")]
+fn request_body(config: &AiSummaryConfig, tasks: &[String]) -> String {
+    let tasks = tasks
+        .iter()
+        .map(|task| format!("- {}", task))
+        .collect::<Vec<String>>()
+        .join("\n");
+    serde_json::json!({
+        "model": config.model,
+        "messages": [
+            { "role": "system", "content": config.prompt },
+            { "role": "user", "content": tasks },
+        ],
+    })
+    .to_string()
+}
+
+#[cfg(feature = "ai-summary")]
+fn parse_response(body: &str) -> Result<String, String> {
+    let response: serde_json::Value =
+        serde_json::from_str(body).map_err(|e| format!("Invalid response: {}", e))?;
+    if let Some(message) = response["error"]["message"].as_str() {
+        return Err(message.to_string());
+    }
+    response["choices"][0]["message"]["content"]
+        .as_str()
+        .map(|content| content.trim().to_string())
+        .ok_or_else(|| "The response contains no summary".to_string())
+}
+
+/// Generates a summary of `tasks` with the configured endpoint. The request
+/// is made with `curl`, reading its options from standard input so the API
+/// key does not show up in the process list.
+#[cfg(feature = "ai-summary")]
+pub fn generate(config: &AiSummaryConfig, tasks: &[String]) -> Result<String, String> {
+    let api_key = std::env::var(&config.api_key_env).map_err(|_| {
+        format!(
+            "No API key in the environment variable {}",
+            config.api_key_env
+        )
+    })?;
+    let (_, body) = crate::http::request(
+        "POST",
+        &config.endpoint,
+        &[format!("Authorization: Bearer {}", api_key)],
+        Some(&request_body(config, tasks)),
+    )?;
+    parse_response(&body)
+}
+
+#[cfg(all(test, feature = "ai-summary"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        let body = request_body(&AiSummaryConfig::default(), &["Fix \"parser\"".to_string()]);
+        assert!(body.contains(r#""content":"- Fix \"parser\"""#));
+        assert_eq!(
+            parse_response(r#"{"choices":[{"message":{"content":" Fixed the parser. "}}]}"#),
+            Ok("Fixed the parser.".to_string())
+        );
+        assert_eq!(
+            parse_response(r#"{"error":{"message":"Invalid key"}}"#),
+            Err("Invalid key".to_string())
+        );
+    }
+}
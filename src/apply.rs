@@ -0,0 +1,311 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    cli,
+    config::Config,
+    error::{self, ErrorCode},
+    log,
+    log::LogEntry,
+    rules, Task,
+};
+
+/// One item of a transaction file: its keys and their values as text.
+type Item = BTreeMap<String, String>;
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return value[1..value.len() - 1].to_string();
+        }
+    }
+    value.to_string()
+}
+
+/// Parses the subset of YAML used by transaction files: a list of items with
+/// one `key: value` per line, such as
+///
+/// ```yaml
+/// - begin: 2024-03-11 09:00
+/// - task: Fix parser
+///   at: "10:30"
+/// ```
+///
+/// Values are plain or quoted text, and lists such as `[a, b]` are kept as
+/// text. Full-line comments and blank lines are skipped.
+fn parse_yaml(data: &str) -> Result<Vec<Item>, String> {
+    let mut items: Vec<Item> = vec![];
+    for (number, line) in data.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "---" {
+            continue;
+        }
+        let pair = match trimmed.strip_prefix("- ") {
+            Some(pair) => {
+                items.push(Item::new());
+                pair
+            }
+            None if line.starts_with([' ', '\t']) && !items.is_empty() => trimmed,
+            None => return Err(format!("Line {}: expected '- key: value'", number + 1)),
+        };
+        let Some((key, value)) = pair.split_once(':') else {
+            return Err(format!("Line {}: expected 'key: value'", number + 1));
+        };
+        items
+            .last_mut()
+            .unwrap()
+            .insert(key.trim().to_string(), unquote(value));
+    }
+    Ok(items)
+}
+
+/// Parses a JSON transaction file: a list of objects with the same keys as
+/// the YAML form.
+fn parse_json(data: &str) -> Result<Vec<Item>, String> {
+    let items: Vec<BTreeMap<String, serde_json::Value>> =
+        serde_json::from_str(data).map_err(|e| format!("Invalid transaction file: {}", e))?;
+    Ok(items
+        .into_iter()
+        .map(|item| {
+            item.into_iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        serde_json::Value::String(value) => value,
+                        serde_json::Value::Array(values) => values
+                            .iter()
+                            .map(|value| value.as_str().map_or(value.to_string(), str::to_string))
+                            .collect::<Vec<String>>()
+                            .join(","),
+                        value => value.to_string(),
+                    };
+                    (key, value)
+                })
+                .collect()
+        })
+        .collect())
+}
+
+fn parse_tags(tags: &str) -> Vec<String> {
+    tags.trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(unquote)
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// A task described by `item`, added at `at`.
+fn task(config: &Config, item: &Item, name: &str, at: chrono::DateTime<chrono::Local>) -> Task {
+    let mut task = Task::new(name, at);
+    rules::categorize(&config.rules, &mut task);
+    if let Some(project) = item.get("project") {
+        task.project = Some(project.clone());
+    }
+    if let Some(tags) = item.get("tags") {
+        task.tags = parse_tags(tags);
+    }
+    if let Some(billable) = item.get("billable") {
+        task.billable = billable != "false";
+    }
+    task
+}
+
+/// Turns the items of a transaction file into the sessions they describe,
+/// numbered from `next_id`. Times are `YYYY-MM-DD HH:MM`, or `HH:MM` on the
+/// day of the session being built.
+fn plan(config: &Config, items: &[Item], next_id: u64) -> Result<Vec<LogEntry>, String> {
+    let mut entries = vec![];
+    let mut open: Option<LogEntry> = None;
+    for (index, item) in items.iter().enumerate() {
+        let error = |message: String| format!("Item {}: {}", index + 1, message);
+        let today = open
+            .as_ref()
+            .map(|entry| entry.start_time.date_naive())
+            .unwrap_or_else(|| chrono::Local::now().date_naive());
+        let time = |key: &str| {
+            let value = item
+                .get(key)
+                .ok_or_else(|| error(format!("missing '{}'", key)))?;
+            cli::parse_time(value, today).ok_or_else(|| {
+                error(format!(
+                    "invalid time '{}' (expected YYYY-MM-DD HH:MM)",
+                    value
+                ))
+            })
+        };
+
+        if item.contains_key("begin") {
+            if open.is_some() {
+                return Err(error("a job session is already started".to_string()));
+            }
+            open = Some(LogEntry {
+                start_time: time("begin")?,
+                goal: item.get("goal").cloned(),
+                ..Default::default()
+            });
+        } else if let Some(name) = item.get("task") {
+            let Some(entry) = &mut open else {
+                return Err(error("no job session started".to_string()));
+            };
+            let at = match item.get("at") {
+                Some(_) => time("at")?,
+                None => entry
+                    .tasks
+                    .last()
+                    .map_or(entry.start_time, |task| task.created_at),
+            };
+            if at < entry.start_time {
+                return Err(error(
+                    "the task is before the start of the session".to_string(),
+                ));
+            }
+            entry.tasks.push(task(config, item, name, at));
+        } else if item.contains_key("end") {
+            let Some(mut entry) = open.take() else {
+                return Err(error("no job session started".to_string()));
+            };
+            entry.end_time = time("end")?;
+            if entry.end_time <= entry.start_time
+                || entry
+                    .tasks
+                    .iter()
+                    .any(|task| task.created_at > entry.end_time)
+            {
+                return Err(error(
+                    "the session ends before its start or its tasks".to_string(),
+                ));
+            }
+            entries.push(entry);
+        } else if let Some(name) = item.get("add") {
+            if open.is_some() {
+                return Err(error("'add' inside a job session".to_string()));
+            }
+            let (start_time, end_time) = (time("from")?, time("to")?);
+            if end_time <= start_time {
+                return Err(error("'to' is not after 'from'".to_string()));
+            }
+            entries.push(LogEntry {
+                start_time,
+                end_time,
+                tasks: vec![task(config, item, name, end_time)],
+                ..Default::default()
+            });
+        } else {
+            return Err(error("expected begin, task, end or add".to_string()));
+        }
+    }
+    if open.is_some() {
+        return Err("The last job session is not ended".to_string());
+    }
+    for (id, entry) in (next_id..).zip(&mut entries) {
+        entry.id = id;
+        entry.tasks.sort_by_key(|task| task.created_at);
+    }
+    Ok(entries)
+}
+
+/// `apply <file>`: logs the sessions described by a YAML or JSON transaction
+/// file. Nothing is logged unless every operation in the file is valid and
+/// no session overlaps another.
+pub fn apply(file: &str) {
+    if file.is_empty() {
+        error::print(ErrorCode::MissingArgument, "Usage: apply <file.yaml>");
+        return;
+    }
+    let data = match std::fs::read_to_string(file) {
+        Ok(data) => data,
+        Err(e) => {
+            error::print(
+                ErrorCode::IoError,
+                &format!("ERROR: Cannot read {}: {}", file, e),
+            );
+            return;
+        }
+    };
+    let items = if file.ends_with(".json") {
+        parse_json(&data)
+    } else {
+        parse_yaml(&data)
+    };
+    let mut entries = log::load();
+    let next_id = entries.iter().map(|entry| entry.id).max().unwrap_or(0) + 1;
+    let planned = match items.and_then(|items| plan(&Config::current(), &items, next_id)) {
+        Ok(planned) => planned,
+        Err(e) => {
+            error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+            return;
+        }
+    };
+
+    let count = planned.len();
+    for entry in planned {
+        if let Some(existing) = entries.iter().find(|existing| {
+            existing.start_time < entry.end_time && entry.start_time < existing.end_time
+        }) {
+            error::print(
+                ErrorCode::CommandFailed,
+                &format!(
+                    "ERROR: The session starting {} overlaps session {}",
+                    entry.start_time.format("%d-%m-%Y %H:%M:%S"),
+                    existing.id
+                ),
+            );
+            return;
+        }
+        entries.push(entry);
+    }
+    entries.sort_by_key(|entry| entry.start_time);
+    match log::save(&entries) {
+        Ok(()) => cli::info(&format!("{} job sessions logged from {}", count, file)),
+        Err(e) => error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let yaml = "# Paper notes, week 11\n\
+                    - begin: 2024-03-11 09:00\n\
+                    \x20 goal: Ship the parser\n\
+                    - task: Fix parser\n\
+                    \x20 at: \"10:30\"\n\
+                    \x20 project: jobclock\n\
+                    \x20 tags: [bug, 'urgent']\n\
+                    - end: 12:00\n\
+                    \n\
+                    - add: Invoice\n\
+                    \x20 from: 2024-03-12 13:00\n\
+                    \x20 to: 2024-03-12 14:00\n\
+                    \x20 billable: false\n";
+        let items = parse_yaml(yaml).unwrap();
+        assert_eq!(items.len(), 4);
+        assert_eq!(items[1]["at"], "10:30");
+
+        let entries = plan(&Config::default(), &items, 7).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, 7);
+        assert_eq!(entries[0].goal.as_deref(), Some("Ship the parser"));
+        assert_eq!(
+            entries[0].duration(),
+            chrono::Duration::try_hours(3).unwrap()
+        );
+        assert_eq!(entries[0].tasks[0].tags, vec!["bug", "urgent"]);
+        assert!(!entries[1].tasks[0].billable);
+
+        let json = r#"[{"begin": "2024-03-11 09:00"}, {"task": "Review", "tags": ["a", "b"]}]"#;
+        let items = parse_json(json).unwrap();
+        assert_eq!(items[1]["tags"], "a,b");
+        assert_eq!(
+            plan(&Config::default(), &items, 1).err().as_deref(),
+            Some("The last job session is not ended")
+        );
+        assert_eq!(
+            plan(&Config::default(), &items[1..], 1).err().as_deref(),
+            Some("Item 1: no job session started")
+        );
+        assert!(parse_yaml("begin: 09:00").is_err());
+    }
+}
@@ -0,0 +1,315 @@
+use std::process::Command;
+
+use crate::{
+    cli::Args,
+    config::Config,
+    error::{self, ErrorCode},
+    format_duration, log,
+    log::LogEntry,
+    process,
+    report::{self, Day},
+    timesheet,
+    week::Weeks,
+};
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ApprovalState {
+    Submitted,
+    Approved,
+}
+
+/// The review state of a logged session, recorded by `submit` and `approve`.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Approval {
+    pub state: ApprovalState,
+    /// Hash of the bundle the session was submitted or approved in.
+    pub bundle: String,
+    pub by: Option<String>,
+    pub at: chrono::DateTime<chrono::Local>,
+}
+
+/// A week of sessions handed to a reviewer. The hash covers the timesheet
+/// contents of the entries, so any change to them is detected on approval.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Bundle {
+    week: String,
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+    submitted_by: Option<String>,
+    submitted_at: chrono::DateTime<chrono::Local>,
+    entries: Vec<LogEntry>,
+    hash: String,
+}
+
+impl Bundle {
+    fn verify(&self) -> Result<(), String> {
+        if timesheet::digest(self.entries.iter().collect()) == self.hash {
+            Ok(())
+        } else {
+            Err("The sessions in the bundle do not match its hash".to_string())
+        }
+    }
+}
+
+/// The name to record for the person running the command: `--by`, or the
+/// login name.
+fn user(args: &mut Args) -> Option<String> {
+    args.value("--by")
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .filter(|name| !name.is_empty())
+}
+
+fn same_session(a: &LogEntry, b: &LogEntry) -> bool {
+    a.start_time == b.start_time && a.end_time == b.end_time
+}
+
+fn gpg(args: &[&str]) -> Result<(), String> {
+    match process::output(Command::new("gpg").args(args)) {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        Err(e) => Err(format!("Failed to execute gpg: {}", e)),
+    }
+}
+
+/// `submit --week [YYYY-MM-DD]`: writes the sessions of the week (the current
+/// one by default) to a bundle for a reviewer, optionally signed with GPG,
+/// and marks them as submitted.
+pub fn submit(args: &mut Args) {
+    let sign = args.flag("--sign");
+    let output = args.value("--output");
+    let submitted_by = user(args);
+    if !args.flag("--week") {
+        error::print(
+            ErrorCode::MissingArgument,
+            "Usage: submit --week [YYYY-MM-DD] [--sign] [--output <file>]",
+        );
+        return;
+    }
+    let date = args.text();
+    let date = if date.is_empty() {
+        chrono::Local::now().date_naive()
+    } else {
+        match report::parse_date(&date) {
+            Some(date) => date,
+            None => {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("Invalid date: {} (expected YYYY-MM-DD)", date),
+                );
+                return;
+            }
+        }
+    };
+    let weeks = Weeks::from_config(&Config::current());
+    let from = weeks.first_day(date);
+    let to = from + chrono::Days::new(6);
+
+    let mut entries = log::load();
+    let in_week = |entry: &LogEntry| {
+        let day = entry.start_time.date_naive();
+        day >= from && day <= to
+    };
+    let mut bundle = Bundle {
+        week: weeks.label(from),
+        from,
+        to,
+        submitted_by: submitted_by.clone(),
+        submitted_at: chrono::Local::now(),
+        entries: entries
+            .iter()
+            .filter(|entry| in_week(entry))
+            .cloned()
+            .map(|entry| LogEntry {
+                approval: None,
+                ..entry
+            })
+            .collect(),
+        hash: String::new(),
+    };
+    if bundle.entries.is_empty() {
+        println!("No job sessions logged in week {}", bundle.week);
+        return;
+    }
+    bundle.hash = timesheet::digest(bundle.entries.iter().collect());
+
+    let file = output.unwrap_or(format!("jobclock-{}.json", bundle.week));
+    if let Err(e) = std::fs::write(&file, serde_json::to_string_pretty(&bundle).unwrap()) {
+        error::print(
+            ErrorCode::IoError,
+            &format!("ERROR: Cannot write {}: {}", file, e),
+        );
+        return;
+    }
+    if sign {
+        if let Err(e) = gpg(&["--yes", "--armor", "--detach-sign", &file]) {
+            error::print(
+                ErrorCode::CommandFailed,
+                &format!("ERROR: Cannot sign {}: {}", file, e),
+            );
+            return;
+        }
+    }
+
+    for entry in entries.iter_mut().filter(|entry| in_week(entry)) {
+        entry.approval = Some(Approval {
+            state: ApprovalState::Submitted,
+            bundle: bundle.hash.clone(),
+            by: submitted_by.clone(),
+            at: bundle.submitted_at,
+        });
+    }
+    if let Err(e) = log::save(&entries) {
+        error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+        return;
+    }
+
+    let days = report::days(&bundle.entries);
+    println!(
+        "Week {} submitted: {} sessions, {}",
+        bundle.week,
+        bundle.entries.len(),
+        format_duration(days.iter().map(Day::worked).sum())
+    );
+    println!(
+        "Bundle written to {}{}",
+        file,
+        if sign { " (signed)" } else { "" }
+    );
+    println!("Hash: {}", bundle.hash);
+}
+
+/// `approve <bundle>`: checks the hash (and the GPG signature, if there is a
+/// `.asc` file next to the bundle) and records the sessions as approved.
+/// Sessions not in the log yet are added to it, so a reviewer keeps the
+/// approved timesheets; use a profile per contractor to keep them apart.
+pub fn approve(args: &mut Args) {
+    let approved_by = user(args);
+    let file = args.text();
+    if file.is_empty() {
+        error::print(ErrorCode::MissingArgument, "Bundle file is required");
+        return;
+    }
+    let bundle: Bundle = match std::fs::read_to_string(&file)
+        .map_err(|e| e.to_string())
+        .and_then(|data| serde_json::from_str(&data).map_err(|e| e.to_string()))
+    {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            error::print(
+                ErrorCode::IoError,
+                &format!("ERROR: Cannot read bundle {}: {}", file, e),
+            );
+            return;
+        }
+    };
+    if let Err(e) = bundle.verify() {
+        error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+        return;
+    }
+    let signature = format!("{}.asc", file);
+    let signed = std::path::Path::new(&signature).exists();
+    if signed {
+        if let Err(e) = gpg(&["--verify", &signature, &file]) {
+            error::print(
+                ErrorCode::CommandFailed,
+                &format!("ERROR: Bad signature {}: {}", signature, e),
+            );
+            return;
+        }
+    }
+
+    let approval = Approval {
+        state: ApprovalState::Approved,
+        bundle: bundle.hash.clone(),
+        by: approved_by,
+        at: chrono::Local::now(),
+    };
+    let mut entries = log::load();
+    let mut last_id = entries.iter().map(|entry| entry.id).max().unwrap_or(0);
+    let mut added = 0;
+    for submitted in &bundle.entries {
+        match entries
+            .iter_mut()
+            .find(|entry| same_session(entry, submitted))
+        {
+            Some(entry) => entry.approval = Some(approval.clone()),
+            None => {
+                last_id += 1;
+                entries.push(LogEntry {
+                    id: last_id,
+                    approval: Some(approval.clone()),
+                    ..submitted.clone()
+                });
+                added += 1;
+            }
+        }
+    }
+    if let Err(e) = log::save(&entries) {
+        error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+        return;
+    }
+
+    let days = report::days(&bundle.entries);
+    println!(
+        "Week {} approved: {} sessions, {}{}",
+        bundle.week,
+        bundle.entries.len(),
+        format_duration(days.iter().map(Day::worked).sum()),
+        if signed { " (signature verified)" } else { "" }
+    );
+    if let Some(by) = &bundle.submitted_by {
+        println!("Submitted by {}", by);
+    }
+    if added > 0 {
+        println!("{} sessions added to the log", added);
+    }
+}
+
+/// Counts of approved and submitted sessions among `days`, for reports.
+pub fn counts(days: &[Day]) -> (usize, usize, usize) {
+    let mut counts = (0, 0, 0);
+    for session in days.iter().flat_map(|day| &day.sessions) {
+        match session.approval.as_ref().map(|approval| approval.state) {
+            Some(ApprovalState::Approved) => counts.0 += 1,
+            Some(ApprovalState::Submitted) => counts.1 += 1,
+            None => counts.2 += 1,
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_bundle_verify() {
+        let entry = LogEntry {
+            id: 1,
+            start_time: chrono::Local
+                .with_ymd_and_hms(2024, 3, 13, 9, 0, 0)
+                .unwrap(),
+            end_time: chrono::Local
+                .with_ymd_and_hms(2024, 3, 13, 17, 0, 0)
+                .unwrap(),
+            ..Default::default()
+        };
+        let mut bundle = Bundle {
+            week: "2024-W11".to_string(),
+            from: chrono::NaiveDate::from_ymd_opt(2024, 3, 11).unwrap(),
+            to: chrono::NaiveDate::from_ymd_opt(2024, 3, 17).unwrap(),
+            submitted_by: None,
+            submitted_at: chrono::Local::now(),
+            entries: vec![entry.clone()],
+            hash: timesheet::digest(vec![&entry]),
+        };
+        assert!(bundle.verify().is_ok());
+
+        bundle.entries[0].end_time = chrono::Local
+            .with_ymd_and_hms(2024, 3, 13, 19, 0, 0)
+            .unwrap();
+        assert!(bundle.verify().is_err());
+    }
+}
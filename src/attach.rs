@@ -0,0 +1,137 @@
+use crate::{
+    cli::{self, Args},
+    edit,
+    error::{self, ErrorCode},
+    log,
+};
+
+/// A file or URL linked to a session, such as a deliverable produced in it.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+pub struct Attachment {
+    /// An absolute path or a URL.
+    pub reference: String,
+    pub added_at: chrono::DateTime<chrono::Local>,
+}
+
+fn is_url(reference: &str) -> bool {
+    reference
+        .split_once("://")
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && scheme.chars().all(char::is_alphanumeric))
+}
+
+/// The reference stored for `reference`: URLs as given, and files as an
+/// absolute path, so they are found again from any directory.
+fn resolve(reference: &str, dir: &std::path::Path) -> Result<String, String> {
+    if is_url(reference) {
+        return Ok(reference.to_string());
+    }
+    let path = dir.join(reference);
+    if !path.exists() {
+        return Err(format!("No such file: {}", reference));
+    }
+    let path = path.canonicalize().map_err(|e| e.to_string())?;
+    Ok(path.display().to_string())
+}
+
+/// The attachments of a session, if it has any.
+pub fn render_attachments(attachments: &[Attachment]) -> String {
+    let mut text = String::new();
+    if attachments.is_empty() {
+        return text;
+    }
+    text.push_str("Attachments:\n");
+    for attachment in attachments {
+        text.push_str(&format!("  {}\n", attachment.reference));
+    }
+    text
+}
+
+/// `attach <file|url> [--session <id|last>]`: links a file or URL to the
+/// running session, or to a logged one. Returns whether the running session
+/// changed.
+pub fn attach(args: &mut Args, working: bool, attachments: &mut Vec<Attachment>) -> bool {
+    let id = args.value("--session");
+    let reference = args.text();
+    if reference.is_empty() {
+        error::print(
+            ErrorCode::MissingArgument,
+            "Usage: attach <file|url> [--session <id|last>]",
+        );
+        return false;
+    }
+    let reference = match resolve(&reference, &std::env::current_dir().unwrap()) {
+        Ok(reference) => reference,
+        Err(e) => {
+            error::print(ErrorCode::InvalidArgument, &format!("ERROR: {}", e));
+            return false;
+        }
+    };
+    let attachment = Attachment {
+        reference: reference.clone(),
+        added_at: chrono::Local::now(),
+    };
+
+    if working && id.is_none() {
+        if !attachments
+            .iter()
+            .any(|existing| existing.reference == reference)
+        {
+            attachments.push(attachment);
+        }
+        cli::info(&format!("Attached {} to the job session", reference));
+        return true;
+    }
+    let mut entries = log::load();
+    let index = match id.as_deref() {
+        None | Some("last") => entries
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, entry)| entry.end_time)
+            .map(|(index, _)| index)
+            .ok_or("No logged job sessions".to_string()),
+        Some(id) => edit::find(&entries, id),
+    };
+    let index = match index {
+        Ok(index) => index,
+        Err(e) => {
+            error::print(ErrorCode::InvalidArgument, &format!("ERROR: {}", e));
+            return false;
+        }
+    };
+    let entry = &mut entries[index];
+    if !entry
+        .attachments
+        .iter()
+        .any(|existing| existing.reference == reference)
+    {
+        entry.attachments.push(attachment);
+    }
+    let id = entry.id;
+    match log::save(&entries) {
+        Ok(()) => cli::info(&format!("Attached {} to job session {}", reference, id)),
+        Err(e) => error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e)),
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let dir = std::env::temp_dir();
+        assert_eq!(
+            resolve("https://example.com/design.pdf", &dir).unwrap(),
+            "https://example.com/design.pdf"
+        );
+        assert!(!is_url("./design.pdf"));
+        assert!(!is_url("C:\\design.pdf"));
+        assert!(resolve("missing-design.pdf", &dir).is_err());
+
+        std::fs::write(dir.join("jobclock-design.pdf"), "").unwrap();
+        let path = resolve("jobclock-design.pdf", &dir).unwrap();
+        assert!(std::path::Path::new(&path).is_absolute());
+        std::fs::remove_file(dir.join("jobclock-design.pdf")).ok();
+    }
+}
@@ -0,0 +1,155 @@
+use std::process::Command;
+
+use crate::{
+    config,
+    error::{self, ErrorCode},
+    process, profile,
+};
+
+/// Name of the scheduled task, launch agent and desktop entry.
+const NAME: &str = "jobclock";
+
+/// The command run at login: `jobclock [--profile <name>] begin`.
+fn login_command() -> Result<Vec<String>, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let mut command = vec![exe.to_string_lossy().to_string()];
+    if let Some(profile) = profile() {
+        command.push("--profile".to_string());
+        command.push(profile.to_string());
+    }
+    command.push("begin".to_string());
+    Ok(command)
+}
+
+/// An XDG autostart entry, as used by Linux desktops.
+fn desktop_entry(command: &[String]) -> String {
+    let exec = command
+        .iter()
+        .map(|arg| {
+            if arg.contains(' ') {
+                format!("\"{}\"", arg)
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    format!(
+        "[Desktop Entry]\nType=Application\nName=JobClock\nComment=Start a job session at login\nExec={}\nTerminal=false\n",
+        exec
+    )
+}
+
+/// A launchd agent that runs once at login, as used by macOS.
+fn launch_agent(command: &[String]) -> String {
+    let arguments = command
+        .iter()
+        .map(|arg| {
+            format!(
+                "        <string>{}</string>\n",
+                arg.replace('&', "&amp;").replace('<', "&lt;")
+            )
+        })
+        .collect::<String>();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n<plist version=\"1.0\">\n<dict>\n    <key>Label</key>\n    <string>com.github.johansolbakken.{}</string>\n    <key>ProgramArguments</key>\n    <array>\n{}    </array>\n    <key>RunAtLoad</key>\n    <true/>\n</dict>\n</plist>\n",
+        NAME, arguments
+    )
+}
+
+/// Where the login item file goes on macOS and Linux.
+fn login_item_file() -> Option<std::path::PathBuf> {
+    if cfg!(target_os = "macos") {
+        Some(
+            config::home_dir()?
+                .join("Library/LaunchAgents")
+                .join(format!("com.github.johansolbakken.{}.plist", NAME)),
+        )
+    } else {
+        let autostart = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => std::path::PathBuf::from(dir),
+            None => config::home_dir()?.join(".config"),
+        };
+        Some(
+            autostart
+                .join("autostart")
+                .join(format!("{}.desktop", NAME)),
+        )
+    }
+}
+
+fn schtasks(args: &[&str]) -> Result<(), String> {
+    match process::output(Command::new("schtasks").args(args)) {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        Err(e) => Err(format!("Failed to execute schtasks: {}", e)),
+    }
+}
+
+fn install() -> Result<String, String> {
+    let command = login_command()?;
+    if cfg!(windows) {
+        let run = command
+            .iter()
+            .map(|arg| format!("\"{}\"", arg))
+            .collect::<Vec<String>>()
+            .join(" ");
+        schtasks(&["/Create", "/F", "/SC", "ONLOGON", "/TN", NAME, "/TR", &run])?;
+        return Ok(format!("scheduled task '{}'", NAME));
+    }
+
+    let file = login_item_file().ok_or("Cannot find the home directory")?;
+    let contents = if cfg!(target_os = "macos") {
+        launch_agent(&command)
+    } else {
+        desktop_entry(&command)
+    };
+    std::fs::create_dir_all(file.parent().unwrap()).map_err(|e| e.to_string())?;
+    std::fs::write(&file, contents).map_err(|e| e.to_string())?;
+    Ok(file.display().to_string())
+}
+
+fn remove() -> Result<String, String> {
+    if cfg!(windows) {
+        schtasks(&["/Delete", "/F", "/TN", NAME])?;
+        return Ok(format!("scheduled task '{}'", NAME));
+    }
+    let file = login_item_file().ok_or("Cannot find the home directory")?;
+    std::fs::remove_file(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+    Ok(file.display().to_string())
+}
+
+/// Registers (or with `remove`, unregisters) `jobclock begin` to run at
+/// login: as a scheduled task on Windows, a launch agent on macOS and an
+/// autostart entry on Linux.
+pub fn install_autostart(remove_it: bool) {
+    if remove_it {
+        match remove() {
+            Ok(location) => println!("Removed autostart {}", location),
+            Err(e) => error::print(
+                ErrorCode::IoError,
+                &format!("ERROR: Cannot remove autostart: {}", e),
+            ),
+        }
+    } else {
+        match install() {
+            Ok(location) => println!("Job sessions will start at login ({})", location),
+            Err(e) => error::print(
+                ErrorCode::IoError,
+                &format!("ERROR: Cannot install autostart: {}", e),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_items() {
+        let command = ["/opt/job clock/jobclock", "begin"].map(String::from);
+        assert!(desktop_entry(&command).contains("Exec=\"/opt/job clock/jobclock\" begin\n"));
+        assert!(launch_agent(&command).contains("<string>/opt/job clock/jobclock</string>"));
+    }
+}
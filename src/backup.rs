@@ -0,0 +1,315 @@
+use std::process::Command;
+
+use crate::{
+    cli,
+    error::{self, ErrorCode},
+    persistent_folder, process, sha256, storage,
+};
+
+/// Lists the files in a backup archive together with their checksums.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    version: String,
+    created_at: chrono::DateTime<chrono::Local>,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ManifestFile {
+    name: String,
+    size: u64,
+    sha256: String,
+}
+
+const MANIFEST: &str = "manifest.json";
+
+/// The data files of the current profile, as paths relative to the data
+/// folder. Other profiles, stored below it, are left out.
+fn data_files() -> Vec<std::path::PathBuf> {
+    fn walk(
+        folder: &std::path::Path,
+        relative: &std::path::Path,
+        files: &mut Vec<std::path::PathBuf>,
+    ) {
+        let Ok(entries) = std::fs::read_dir(folder) else {
+            return;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let relative = relative.join(entry.file_name());
+            if path.is_file() {
+                files.push(relative);
+            } else if path.is_dir()
+                && relative != std::path::Path::new("profiles")
+                && relative != std::path::Path::new(".git")
+            {
+                walk(&path, &relative, files);
+            }
+        }
+    }
+
+    let mut files = vec![];
+    walk(&persistent_folder(), std::path::Path::new(""), &mut files);
+    files.sort();
+    files
+}
+
+/// Whether a file name from a manifest stays inside the folder it is
+/// restored to: relative, and without `..` or other special components.
+fn valid_name(name: &str) -> bool {
+    let path = std::path::Path::new(name);
+    !name.is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, std::path::Component::Normal(_)))
+}
+
+fn staging_folder(name: &str) -> std::path::PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(format!("jobclock-{}-{}", name, std::process::id()));
+    path
+}
+
+fn run_tar(args: &[&std::ffi::OsStr]) -> Result<(), String> {
+    match process::output(Command::new("tar").args(args)) {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(format!(
+            "tar failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(e) => Err(format!("Failed to execute tar command: {}", e)),
+    }
+}
+
+/// Writes all data files and a manifest of their checksums to `archive`.
+fn create_archive(archive: &std::path::Path) -> Result<usize, String> {
+    let staging = staging_folder("backup");
+    storage::create_folder(&staging).map_err(|e| e.to_string())?;
+
+    let mut manifest = Manifest {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: chrono::Local::now(),
+        files: vec![],
+    };
+    for path in data_files() {
+        let data = std::fs::read(persistent_folder().join(&path)).map_err(|e| e.to_string())?;
+        let name = path.to_string_lossy().replace('\\', "/");
+        let target = staging.join(&name);
+        std::fs::create_dir_all(target.parent().unwrap()).map_err(|e| e.to_string())?;
+        std::fs::write(target, &data).map_err(|e| e.to_string())?;
+        manifest.files.push(ManifestFile {
+            name,
+            size: data.len() as u64,
+            sha256: sha256::hex_digest(&data),
+        });
+    }
+    let data = serde_json::to_string_pretty(&manifest).unwrap();
+    std::fs::write(staging.join(MANIFEST), data).map_err(|e| e.to_string())?;
+
+    let result = run_tar(&[
+        "-czf".as_ref(),
+        archive.as_os_str(),
+        "-C".as_ref(),
+        staging.as_os_str(),
+        ".".as_ref(),
+    ]);
+    std::fs::remove_dir_all(&staging).ok();
+    result.map(|_| manifest.files.len())
+}
+
+/// Extracts `archive` and checks every file against the manifest.
+fn extract_archive(archive: &std::path::Path) -> Result<(std::path::PathBuf, Manifest), String> {
+    let staging = staging_folder("restore");
+    std::fs::remove_dir_all(&staging).ok();
+    storage::create_folder(&staging).map_err(|e| e.to_string())?;
+
+    let verify = || -> Result<Manifest, String> {
+        run_tar(&[
+            "-xzf".as_ref(),
+            archive.as_os_str(),
+            "-C".as_ref(),
+            staging.as_os_str(),
+        ])?;
+        let data = std::fs::read_to_string(staging.join(MANIFEST))
+            .map_err(|_| "Backup has no manifest".to_string())?;
+        let manifest: Manifest =
+            serde_json::from_str(&data).map_err(|e| format!("Invalid manifest: {}", e))?;
+        for file in &manifest.files {
+            if !valid_name(&file.name) {
+                return Err(format!("Invalid file name in manifest: {}", file.name));
+            }
+            let data = std::fs::read(staging.join(&file.name))
+                .map_err(|_| format!("Backup is missing {}", file.name))?;
+            if sha256::hex_digest(&data) != file.sha256 {
+                return Err(format!("Checksum mismatch for {}", file.name));
+            }
+        }
+        Ok(manifest)
+    };
+
+    match verify() {
+        Ok(manifest) => Ok((staging, manifest)),
+        Err(e) => {
+            std::fs::remove_dir_all(&staging).ok();
+            Err(e)
+        }
+    }
+}
+
+fn archive_name(time: chrono::DateTime<chrono::Local>) -> String {
+    format!("jobclock-backup-{}.tar.gz", time.format("%Y%m%d-%H%M%S"))
+}
+
+pub fn backup(path: &str) {
+    let mut archive = if path.is_empty() {
+        std::path::PathBuf::from(".")
+    } else {
+        std::path::PathBuf::from(path)
+    };
+    if archive.is_dir() {
+        archive.push(archive_name(chrono::Local::now()));
+    }
+
+    match create_archive(&archive) {
+        Ok(count) => cli::info(&format!(
+            "Backed up {} files to {}",
+            count,
+            archive.display()
+        )),
+        Err(e) => error::print(ErrorCode::IoError, &format!("ERROR: Backup failed: {}", e)),
+    }
+}
+
+pub fn restore(path: &str) {
+    if path.is_empty() {
+        error::print(ErrorCode::MissingArgument, "Backup file is required");
+        return;
+    }
+    if !std::path::Path::new(path).is_file() {
+        error::print(
+            ErrorCode::IoError,
+            &format!("Backup file {} does not exist", path),
+        );
+        return;
+    }
+
+    let (staging, manifest) = match extract_archive(std::path::Path::new(path)) {
+        Ok(extracted) => extracted,
+        Err(e) => {
+            error::print(
+                ErrorCode::IoError,
+                &format!("ERROR: Cannot restore {}: {}", path, e),
+            );
+            return;
+        }
+    };
+
+    let question = format!(
+        "Replace all data with the backup made {}?",
+        manifest.created_at.format("%d-%m-%Y %H:%M:%S")
+    );
+    if !cli::confirm(&question) {
+        println!("Nothing restored");
+        std::fs::remove_dir_all(&staging).ok();
+        return;
+    }
+
+    // Keep the current data around in case the restore was a mistake.
+    let mut safety = std::env::temp_dir();
+    safety.push(format!(
+        "jobclock-before-restore-{}.tar.gz",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    if let Err(e) = create_archive(&safety) {
+        error::print(
+            ErrorCode::IoError,
+            &format!("ERROR: Cannot back up current data: {}", e),
+        );
+        std::fs::remove_dir_all(&staging).ok();
+        return;
+    }
+
+    let replace = || -> std::io::Result<()> {
+        for path in data_files() {
+            std::fs::remove_file(persistent_folder().join(path))?;
+        }
+        for file in &manifest.files {
+            let target = persistent_folder().join(&file.name);
+            storage::create_folder(target.parent().unwrap())?;
+            storage::write(&target, std::fs::read(staging.join(&file.name))?)?;
+        }
+        Ok(())
+    };
+    let replaced = replace();
+    std::fs::remove_dir_all(&staging).ok();
+    if let Err(e) = replaced {
+        error::print(
+            ErrorCode::IoError,
+            &format!(
+                "ERROR: Restore failed: {}; the previous data is saved in {}, restore it with 'restore {}'",
+                e,
+                safety.display(),
+                safety.display()
+            ),
+        );
+        return;
+    }
+
+    cli::info(&format!(
+        "Restored {} files from backup made {}",
+        manifest.files.len(),
+        manifest.created_at.format("%d-%m-%Y %H:%M:%S")
+    ));
+    cli::info(&format!("Previous data saved to {}", safety.display()));
+}
+
+fn copy_folder(from: &std::path::Path, to: &std::path::Path) -> Result<(), String> {
+    std::fs::create_dir_all(to).map_err(|e| e.to_string())?;
+    for entry in std::fs::read_dir(from).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let target = to.join(entry.file_name());
+        if entry.path().is_dir() {
+            copy_folder(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// A copy of the data folder or backup archive at `path` in a staging
+/// folder, for reading it without any chance of changing the original. The
+/// caller removes the copy when done.
+pub fn snapshot(path: &std::path::Path) -> Result<std::path::PathBuf, String> {
+    if path.is_file() {
+        return extract_archive(path).map(|(staging, _)| staging);
+    }
+    if !path.is_dir() {
+        return Err(format!("{} does not exist", path.display()));
+    }
+    let staging = staging_folder("inspect");
+    std::fs::remove_dir_all(&staging).ok();
+    match copy_folder(path, &staging) {
+        Ok(()) => Ok(staging),
+        Err(e) => {
+            std::fs::remove_dir_all(&staging).ok();
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_name() {
+        assert!(valid_name("log.json"));
+        assert!(valid_name("attachments/1/notes.txt"));
+        assert!(!valid_name(""));
+        assert!(!valid_name("../escape.json"));
+        assert!(!valid_name("attachments/../../escape.json"));
+        assert!(!valid_name("/etc/passwd"));
+        assert!(!valid_name("./log.json"));
+    }
+}
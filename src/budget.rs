@@ -0,0 +1,224 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    color, config::Config, currency, log::LogEntry, notify, persistent_folder, storage, summary,
+};
+
+/// Share of a budget at which a warning is shown.
+const WARNING_PERCENT: f64 = 80.0;
+
+/// How much of the budgets of a project has been used.
+pub struct Usage {
+    pub project: String,
+    pub hours: f64,
+    pub budget_hours: Option<f64>,
+    /// Earnings in the currency of the project.
+    pub amount: f64,
+    pub budget: Option<f64>,
+}
+
+impl Usage {
+    /// The highest share used of the hours and money budgets, in percent.
+    pub fn percent(&self) -> f64 {
+        let hours = self.budget_hours.map(|budget| self.hours / budget * 100.0);
+        let amount = self.budget.map(|budget| self.amount / budget * 100.0);
+        hours.into_iter().chain(amount).fold(0.0, f64::max)
+    }
+}
+
+/// 0 below 80% of the budget, 80 from there and 100 once it is used up.
+pub fn level(percent: f64) -> u8 {
+    if percent >= 100.0 {
+        100
+    } else if percent >= WARNING_PERCENT {
+        80
+    } else {
+        0
+    }
+}
+
+/// Whether any project has a budget.
+pub fn configured(config: &Config) -> bool {
+    config
+        .projects
+        .values()
+        .any(|settings| settings.budget_hours.is_some() || settings.budget.is_some())
+}
+
+/// Usage of every project with a budget over all of `entries`.
+pub fn usage(config: &Config, entries: &[LogEntry]) -> Vec<Usage> {
+    let mut usage = config
+        .projects
+        .iter()
+        .filter(|(_, settings)| settings.budget_hours.is_some() || settings.budget.is_some())
+        .map(|(project, settings)| Usage {
+            project: project.clone(),
+            hours: 0.0,
+            budget_hours: settings.budget_hours,
+            amount: 0.0,
+            budget: settings.budget,
+        })
+        .collect::<Vec<Usage>>();
+
+    for entry in entries {
+        for group in summary::group_by_project(entry.start_time, &entry.tasks) {
+            if let Some(usage) = usage
+                .iter_mut()
+                .find(|usage| group.name.as_deref() == Some(usage.project.as_str()))
+            {
+                usage.hours += group.duration.num_seconds() as f64 / 3600.0;
+            }
+        }
+        for (project, amount) in config.rates.iter().flat_map(|rates| rates.session(entry)) {
+            if let Some(usage) = usage
+                .iter_mut()
+                .find(|usage| project.as_deref() == Some(usage.project.as_str()))
+            {
+                usage.amount += amount;
+            }
+        }
+    }
+    usage
+}
+
+fn paint(text: String, level: u8) -> String {
+    if level == 0 || !color::enabled() {
+        return text;
+    }
+    let color = if level >= 100 { 31 } else { 33 };
+    format!("\x1b[{}m{}\x1b[0m", color, text)
+}
+
+fn alerts_file() -> std::path::PathBuf {
+    let mut path = persistent_folder();
+    path.push("budget_alerts.json");
+    path
+}
+
+/// Shows a desktop notification for each project that crossed 80% or 100%
+/// of its budget since the last time.
+fn notify_crossed(usage: &[Usage]) {
+    let mut alerted: BTreeMap<String, u8> = std::fs::read_to_string(alerts_file())
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default();
+    let mut changed = false;
+    for usage in usage {
+        let level = level(usage.percent());
+        let previous = alerted.get(&usage.project).copied().unwrap_or(0);
+        if level != previous {
+            if level > previous {
+                notify::send(
+                    "Project budget",
+                    &format!(
+                        "{} has used {:.0}% of its budget",
+                        usage.project,
+                        usage.percent()
+                    ),
+                );
+            }
+            alerted.insert(usage.project.clone(), level);
+            changed = true;
+        }
+    }
+    if changed {
+        storage::create_folder(&persistent_folder()).unwrap();
+        storage::write(&alerts_file(), serde_json::to_string(&alerted).unwrap()).unwrap();
+    }
+}
+
+/// Brings the recorded budget levels up to date after logged time was
+/// changed, notifying about budgets that are now crossed.
+pub fn refresh_alerts(config: &Config, entries: &[LogEntry]) {
+    if configured(config) {
+        notify_crossed(&usage(config, entries));
+    }
+}
+
+/// Prints the budget usage of every project with a budget except those in
+/// `hidden`, returning whether any budget is used up.
+pub fn print_budgets(config: &Config, entries: &[LogEntry], hidden: &BTreeSet<String>) -> bool {
+    let usage = usage(config, entries);
+    notify_crossed(&usage);
+    let usage = usage
+        .into_iter()
+        .filter(|usage| !hidden.contains(&usage.project))
+        .collect::<Vec<Usage>>();
+    if usage.is_empty() {
+        return false;
+    }
+
+    println!("Budgets:");
+    for usage in &usage {
+        let mut parts = vec![];
+        if let Some(budget) = usage.budget_hours {
+            parts.push(format!("{:.1}h / {:.1}h", usage.hours, budget));
+        }
+        if let Some(budget) = usage.budget {
+            let currency = currency::project_currency(config, Some(&usage.project))
+                .or(config.currency.clone());
+            parts.push(format!(
+                "{} / {}",
+                currency::format(usage.amount, currency.as_deref()),
+                currency::format(budget, currency.as_deref())
+            ));
+        }
+        let percent = usage.percent();
+        let warning = match level(percent) {
+            100 => " - over budget",
+            80 => " - nearing budget",
+            _ => "",
+        };
+        let line = format!(
+            "  {} - {} ({:.0}%){}",
+            usage.project,
+            parts.join(", "),
+            percent,
+            warning
+        );
+        println!("{}", paint(line, level(percent)));
+    }
+    usage.iter().any(|usage| level(usage.percent()) >= 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::ProjectSettings, Task};
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_usage() {
+        let at = |hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 13, hour, 0, 0)
+                .unwrap()
+        };
+        let mut task = Task::new("Build", at(17));
+        task.project = Some("acme".to_string());
+        let entry = LogEntry {
+            start_time: at(9),
+            end_time: at(17),
+            tasks: vec![task],
+            ..Default::default()
+        };
+        let config = Config {
+            projects: BTreeMap::from([(
+                "acme".to_string(),
+                ProjectSettings {
+                    budget_hours: Some(10.0),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+
+        let usage = usage(&config, &[entry]);
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].hours, 8.0);
+        assert_eq!(usage[0].percent(), 80.0);
+        assert_eq!(level(usage[0].percent()), 80);
+        assert_eq!(level(120.0), 100);
+        assert_eq!(level(10.0), 0);
+    }
+}
@@ -0,0 +1,155 @@
+use chrono::Datelike;
+
+use crate::{
+    config::Config,
+    error::{self, ErrorCode},
+    format_duration, log,
+    log::LogEntry,
+    report, timesheet,
+    week::Weeks,
+};
+
+const CELL: usize = 7;
+
+fn hours(duration: chrono::Duration) -> String {
+    format!("{:.1}h", duration.num_seconds() as f64 / 3600.0)
+}
+
+/// Renders `month` as a calendar grid, weeks starting on the day `weeks`
+/// starts them on. Each week is a row of day numbers followed by a row of
+/// hours worked, with the week total in the margin. Days without work show
+/// `-`.
+pub fn render(month: chrono::NaiveDate, entries: &[LogEntry], weeks: Weeks) -> String {
+    let first = month.with_day(1).unwrap();
+    let next = first.checked_add_months(chrono::Months::new(1)).unwrap();
+    let days = report::days(entries)
+        .into_iter()
+        .filter(|day| day.date >= first && day.date < next)
+        .collect::<Vec<report::Day>>();
+    let worked = |date: chrono::NaiveDate| {
+        days.iter()
+            .find(|day| day.date == date)
+            .map(|day| day.worked())
+            .unwrap_or_else(chrono::Duration::zero)
+    };
+
+    let mut first_day = weeks.first_day(first);
+    let title = first.format("%B %Y").to_string();
+    let mut lines = vec![
+        format!("{:^width$}", title, width = CELL * 7)
+            .trim_end()
+            .to_string(),
+        format!(
+            "{}{:>width$}",
+            first_day
+                .iter_days()
+                .take(7)
+                .map(|date| format!("{:>width$}", date.format("%a"), width = CELL))
+                .collect::<String>(),
+            "Week",
+            width = CELL + 2
+        ),
+    ];
+
+    let mut total = chrono::Duration::zero();
+    while first_day < next {
+        let mut numbers = String::new();
+        let mut cells = String::new();
+        let mut week = chrono::Duration::zero();
+        for offset in 0..7 {
+            let date = first_day + chrono::Days::new(offset);
+            if date < first || date >= next {
+                numbers.push_str(&" ".repeat(CELL));
+                cells.push_str(&" ".repeat(CELL));
+                continue;
+            }
+            let duration = worked(date);
+            week += duration;
+            numbers.push_str(&format!("{:>width$}", date.day(), width = CELL));
+            let cell = if duration > chrono::Duration::zero() {
+                hours(duration)
+            } else {
+                "-".to_string()
+            };
+            cells.push_str(&format!("{:>width$}", cell, width = CELL));
+        }
+        total += week;
+        lines.push(numbers.trim_end().to_string());
+        lines.push(format!(
+            "{}{:>width$}",
+            cells,
+            hours(week),
+            width = CELL + 2
+        ));
+        first_day = first_day + chrono::Days::new(7);
+    }
+    lines.push(format!("Total: {}", format_duration(total)));
+    lines.join("\n")
+}
+
+/// `cal [YYYY-MM]`: prints the calendar of the month, the current one by
+/// default.
+pub fn calendar(month: &str) {
+    let first = if month.is_empty() {
+        chrono::Local::now().date_naive().with_day(1).unwrap()
+    } else {
+        match timesheet::parse_month(month)
+            .and_then(|month| report::parse_date(&format!("{}-01", month)))
+        {
+            Some(first) => first,
+            None => {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("Invalid month: {} (expected YYYY-MM)", month),
+                );
+                return;
+            }
+        }
+    };
+    let last = first.checked_add_months(chrono::Months::new(1)).unwrap() - chrono::Days::new(1);
+    println!(
+        "{}",
+        render(
+            first,
+            &log::load_range(Some(first), Some(last)),
+            Weeks::from_config(&Config::current())
+        )
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render() {
+        let at = |day, hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, day, hour, 0, 0)
+                .unwrap()
+        };
+        let entry = |day, from, to| LogEntry {
+            start_time: at(day, from),
+            end_time: at(day, to),
+            ..Default::default()
+        };
+        let entries = [entry(1, 9, 17), entry(4, 9, 16), entry(4, 17, 18)];
+        let calendar = render(
+            chrono::NaiveDate::from_ymd_opt(2024, 3, 13).unwrap(),
+            &entries,
+            Weeks::default(),
+        );
+        let lines = calendar.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines[0].trim(), "March 2024");
+        assert_eq!(lines[2], format!("{}1      2      3", " ".repeat(34)));
+        assert_eq!(
+            lines[3],
+            format!("{}8.0h      -      -     8.0h", " ".repeat(31))
+        );
+        assert!(lines[5].starts_with("   8.0h      -"));
+        assert_eq!(lines.len(), 2 + 2 * 5 + 1);
+        assert_eq!(lines[lines.len() - 1], "Total: 16h 0m 0s");
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    cli::{self, Args},
+    config::Config,
+    error::{self, ErrorCode},
+    format_duration, log,
+    log::LogEntry,
+    report,
+    week::Weeks,
+};
+
+/// The most time that may be worked today and this week.
+struct Limits {
+    day: Option<chrono::Duration>,
+    week: Option<chrono::Duration>,
+}
+
+/// The time worked today and this week at `now`, one line each, with
+/// whether it is more than `limits` allow. A running session started at
+/// `running_since` is counted as far as `now`.
+fn check_limits(
+    entries: &[LogEntry],
+    running_since: Option<chrono::DateTime<chrono::Local>>,
+    now: chrono::DateTime<chrono::Local>,
+    weeks: Weeks,
+    limits: &Limits,
+) -> Vec<(String, bool)> {
+    let today = now.date_naive();
+    let first = weeks.first_day(today);
+    let days = report::days(entries);
+    let running = running_since.map_or(chrono::Duration::zero(), |start| now - start);
+    let worked = |from: chrono::NaiveDate| {
+        days.iter()
+            .filter(|day| day.date >= from && day.date <= today)
+            .fold(running, |sum, day| sum + day.worked())
+    };
+
+    let mut lines = vec![];
+    for (limit, from, period) in [
+        (limits.day, today, "today"),
+        (limits.week, first, "this week"),
+    ] {
+        let Some(limit) = limit else {
+            continue;
+        };
+        let worked = worked(from);
+        let exceeded = worked > limit;
+        lines.push((
+            format!(
+                "{}: {} worked {}, limit {}",
+                if exceeded { "EXCEEDED" } else { "OK" },
+                format_duration(worked),
+                period,
+                format_duration(limit)
+            ),
+            exceeded,
+        ));
+    }
+    lines
+}
+
+/// The duration given with `option`, exiting when it cannot be read.
+fn limit(args: &mut Args, option: &str) -> Option<chrono::Duration> {
+    let text = args.value(option)?;
+    match cli::parse_duration(&text) {
+        Some(limit) => Some(limit),
+        None => {
+            error::print(
+                ErrorCode::InvalidArgument,
+                &format!(
+                    "Invalid duration for {}: {} (expected e.g. 40h)",
+                    option, text
+                ),
+            );
+            std::process::exit(2);
+        }
+    }
+}
+
+/// `check [--max-day <duration>] [--max-week <duration>]`: exits with 1 when
+/// more time has been worked today or this week than the limits given, or
+/// those of the `working_hours` setting, so cron jobs and shell hooks can act
+/// on it. The running session counts as well.
+pub fn check(args: &mut Args, running_since: Option<chrono::DateTime<chrono::Local>>) {
+    let mut limits = Limits {
+        day: limit(args, "--max-day"),
+        week: limit(args, "--max-week"),
+    };
+    let config = Config::current();
+    if limits.day.is_none() && limits.week.is_none() {
+        if let Some(rules) = &config.working_hours {
+            let hours = |hours: f64| chrono::Duration::try_seconds((hours * 3600.0) as i64);
+            limits.day = rules.max_hours_per_day.and_then(hours);
+            limits.week = rules.max_hours_per_week.and_then(hours);
+        }
+    }
+    if limits.day.is_none() && limits.week.is_none() {
+        error::print(
+            ErrorCode::MissingArgument,
+            "Usage: check [--max-day <duration>] [--max-week <duration>]",
+        );
+        std::process::exit(2);
+    }
+
+    let now = chrono::Local::now();
+    let weeks = Weeks::from_config(&config);
+    let entries = log::load_range(
+        Some(weeks.first_day(now.date_naive())),
+        Some(now.date_naive()),
+    );
+    let lines = check_limits(&entries, running_since, now, weeks, &limits);
+    for (line, exceeded) in &lines {
+        if *exceeded {
+            println!("{}", line);
+        } else {
+            cli::info(line);
+        }
+    }
+    if lines.iter().any(|(_, exceeded)| *exceeded) {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_check_limits() {
+        let at = |day, hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, day, hour, 0, 0)
+                .unwrap()
+        };
+        let entry = |day, start, end| LogEntry {
+            start_time: at(day, start),
+            end_time: at(day, end),
+            ..Default::default()
+        };
+        let entries = [entry(2, 8, 16), entry(3, 8, 18), entry(4, 8, 12)];
+        let limits = Limits {
+            day: Some(chrono::Duration::try_hours(9).unwrap()),
+            week: Some(chrono::Duration::try_hours(14).unwrap()),
+        };
+        let lines = check_limits(
+            &entries,
+            Some(at(4, 13)),
+            at(4, 15),
+            Weeks::default(),
+            &limits,
+        );
+        assert_eq!(
+            lines,
+            vec![
+                (
+                    "OK: 6h 0m 0s worked today, limit 9h 0m 0s".to_string(),
+                    false
+                ),
+                (
+                    "EXCEEDED: 16h 0m 0s worked this week, limit 14h 0m 0s".to_string(),
+                    true
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,337 @@
+/// Arguments following the subcommand. Options are pulled out by name as each
+/// command asks for them, and whatever remains is treated as free text.
+pub struct Args {
+    args: Vec<String>,
+}
+
+impl Args {
+    pub fn new(args: Vec<String>) -> Args {
+        Args { args }
+    }
+
+    /// Removes and returns the first argument.
+    pub fn shift(&mut self) -> Option<String> {
+        if self.args.is_empty() {
+            None
+        } else {
+            Some(self.args.remove(0))
+        }
+    }
+
+    /// Removes `name` from the arguments, returning whether it was present.
+    pub fn flag(&mut self, name: &str) -> bool {
+        let count = self.args.len();
+        self.args.retain(|arg| arg != name);
+        self.args.len() != count
+    }
+
+    /// Removes `name <value>` or `name=<value>` from the arguments, returning
+    /// the value.
+    pub fn value(&mut self, name: &str) -> Option<String> {
+        let prefix = format!("{}=", name);
+        let index = self
+            .args
+            .iter()
+            .position(|arg| arg == name || arg.starts_with(&prefix))?;
+        let arg = self.args.remove(index);
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Some(value.to_string());
+        }
+        if index < self.args.len() {
+            Some(self.args.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Removes `name` and the `count` arguments following it, as in
+    /// `--diff <first> <second>`. Returns `None` when `name` is not given,
+    /// and fewer values when the arguments run out.
+    pub fn values(&mut self, name: &str, count: usize) -> Option<Vec<String>> {
+        let index = self.args.iter().position(|arg| arg == name)?;
+        let end = (index + 1 + count).min(self.args.len());
+        Some(self.args.drain(index..end).skip(1).collect())
+    }
+
+    /// Removes `name` and the number following it, if there is one, as in
+    /// `--week` or `--week 23`. Returns `None` when `name` is not given.
+    pub fn optional_number(&mut self, name: &str) -> Option<Option<u32>> {
+        let index = self.args.iter().position(|arg| arg == name)?;
+        self.args.remove(index);
+        match self.args.get(index).and_then(|arg| arg.parse().ok()) {
+            Some(number) => {
+                self.args.remove(index);
+                Some(Some(number))
+            }
+            None => Some(None),
+        }
+    }
+
+    /// The remaining arguments joined by spaces.
+    pub fn text(&self) -> String {
+        self.args.join(" ")
+    }
+
+    /// Replaces a first argument named in `aliases` with the words of the
+    /// command it stands for. Aliases are expanded once, so an alias can
+    /// add options to the command with its own name.
+    pub fn expand_alias(
+        &mut self,
+        aliases: &std::collections::BTreeMap<String, String>,
+    ) -> Result<(), String> {
+        let Some(command) = self.args.first().and_then(|first| aliases.get(first)) else {
+            return Ok(());
+        };
+        let words =
+            split_words(command).map_err(|e| format!("Invalid alias {}: {}", self.args[0], e))?;
+        self.args.splice(0..1, words);
+        Ok(())
+    }
+}
+
+/// Splits a command line into words at whitespace. Single and double quotes
+/// group words, and a backslash outside single quotes escapes the next
+/// character.
+pub fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => word.get_or_insert_with(String::new).push(c),
+            (_, '\\') => match chars.next() {
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err("trailing backslash".to_string()),
+            },
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err("unterminated quote".to_string());
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Parses a local time given as `YYYY-MM-DD HH:MM`, or `HH:MM` on `today`.
+pub fn parse_time(time: &str, today: chrono::NaiveDate) -> Option<chrono::DateTime<chrono::Local>> {
+    let time = time.trim();
+    let naive = chrono::NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M")
+        .ok()
+        .or_else(|| {
+            chrono::NaiveTime::parse_from_str(time, "%H:%M")
+                .ok()
+                .map(|time| today.and_time(time))
+        })?;
+    naive.and_local_timezone(chrono::Local).earliest()
+}
+
+/// Parses a length of time such as `1h30m`, `90m`, `2h`, `1.5h` or `45s`.
+/// A number without a unit is in hours.
+pub fn parse_duration(text: &str) -> Option<chrono::Duration> {
+    let text = text.trim().to_lowercase();
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(hours) = text.parse::<f64>() {
+        return chrono::Duration::try_seconds((hours * 3600.0) as i64);
+    }
+    let mut seconds = 0.0;
+    let mut number = String::new();
+    for c in text.chars() {
+        let unit = match c {
+            'h' => 3600.0,
+            'm' => 60.0,
+            's' => 1.0,
+            c if c.is_ascii_digit() || c == '.' => {
+                number.push(c);
+                continue;
+            }
+            _ => return None,
+        };
+        seconds += number.parse::<f64>().ok()? * unit;
+        number.clear();
+    }
+    if !number.is_empty() || !seconds.is_finite() {
+        return None;
+    }
+    chrono::Duration::try_seconds(seconds as i64)
+}
+
+/// Set by `--quiet` to leave out informational messages, for use from cron
+/// and scripts. Errors, warnings and the output of queries are still shown.
+static QUIET: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+
+/// Set by `--yes` to confirm destructive operations without asking.
+static YES: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.set(quiet).ok();
+}
+
+pub fn set_yes(yes: bool) {
+    YES.set(yes).ok();
+}
+
+pub fn quiet() -> bool {
+    QUIET.get().copied().unwrap_or(false)
+}
+
+/// Prints an informational message, unless `--quiet` was given.
+pub fn info(message: &str) {
+    if !quiet() {
+        println!("{}", message);
+    }
+}
+
+/// Asks to confirm a destructive operation, which `--yes` confirms right
+/// away. Without a terminal to ask on, the operation is not confirmed.
+pub fn confirm(question: &str) -> bool {
+    if YES.get().copied().unwrap_or(false) {
+        return true;
+    }
+    match ask_yes_no(question) {
+        Some(answer) => answer,
+        None => {
+            println!("Not confirmed, pass --yes to confirm without asking");
+            false
+        }
+    }
+}
+
+/// Asks a question on the terminal and returns the trimmed answer, or `None`
+/// when there is no terminal to ask on.
+pub fn ask(question: &str) -> Option<String> {
+    use std::io::{IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        return None;
+    }
+    print!("{} ", question);
+    std::io::stdout().flush().ok()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).ok()?;
+    Some(answer.trim().to_string())
+}
+
+/// Asks a yes/no question on the terminal. Returns `None` when there is no
+/// terminal to ask on or the answer is neither yes nor no.
+pub fn ask_yes_no(question: &str) -> Option<bool> {
+    parse_yes_no(&ask(&format!("{} (y/n)", question))?)
+}
+
+fn parse_yes_no(answer: &str) -> Option<bool> {
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(true),
+        "n" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let mut args = Args::new(
+            [
+                "--from",
+                "2024-03-01",
+                "fix",
+                "--breaks",
+                "parser",
+                "--to=2024-03-31",
+            ]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect(),
+        );
+        assert!(args.flag("--breaks"));
+        assert!(!args.flag("--breaks"));
+        assert_eq!(args.value("--from").as_deref(), Some("2024-03-01"));
+        assert_eq!(args.value("--to").as_deref(), Some("2024-03-31"));
+        assert_eq!(args.value("--week"), None);
+        assert_eq!(args.optional_number("--breaks"), None);
+        assert_eq!(args.text(), "fix parser");
+
+        let aliases = [
+            ("s", "status --short"),
+            ("status", "status --verbose"),
+            ("x", "task 'half"),
+        ]
+        .iter()
+        .map(|(alias, command)| (alias.to_string(), command.to_string()))
+        .collect();
+        let expand = |line: &str| {
+            let mut args = Args::new(split_words(line).unwrap());
+            args.expand_alias(&aliases).map(|_| args.text())
+        };
+        assert_eq!(expand("s --json").as_deref(), Ok("status --short --json"));
+        assert_eq!(expand("status").as_deref(), Ok("status --verbose"));
+        assert_eq!(expand("task s").as_deref(), Ok("task s"));
+        assert!(expand("x").is_err());
+
+        let mut args = Args::new(split_words("--diff 2024-W10 2024-W11 --plain").unwrap());
+        assert_eq!(
+            args.values("--diff", 2),
+            Some(vec!["2024-W10".to_string(), "2024-W11".to_string()])
+        );
+        assert_eq!(args.text(), "--plain");
+        let mut args = Args::new(split_words("--diff 2024-W10").unwrap());
+        assert_eq!(args.values("--diff", 2), Some(vec!["2024-W10".to_string()]));
+        assert_eq!(args.values("--diff", 2), None);
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            split_words("  task \"fix the\" 'parser'\"s\" \\\"x\n").unwrap(),
+            vec!["task", "fix the", "parsers", "\"x"]
+        );
+        assert_eq!(split_words("begin -m ''").unwrap(), vec!["begin", "-m", ""]);
+        assert!(split_words("task \"fix").is_err());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+        let time = |time| parse_time(time, today).map(|time| time.naive_local());
+        assert_eq!(
+            time("2024-03-11 17:30"),
+            chrono::NaiveDate::from_ymd_opt(2024, 3, 11)
+                .unwrap()
+                .and_hms_opt(17, 30, 0)
+        );
+        assert_eq!(time("09:15"), today.and_hms_opt(9, 15, 0));
+        assert_eq!(time("yesterday"), None);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let minutes = |minutes| chrono::Duration::try_minutes(minutes);
+        assert_eq!(parse_duration("1h30m"), minutes(90));
+        assert_eq!(parse_duration("90m"), minutes(90));
+        assert_eq!(parse_duration("1.5"), minutes(90));
+        assert_eq!(parse_duration("2H"), minutes(120));
+        assert_eq!(parse_duration("45s"), chrono::Duration::try_seconds(45));
+        assert_eq!(parse_duration("1h30"), None);
+        assert_eq!(parse_duration("soon"), None);
+    }
+
+    #[test]
+    fn test_parse_yes_no() {
+        assert_eq!(parse_yes_no("Y\n"), Some(true));
+        assert_eq!(parse_yes_no(" no "), Some(false));
+        assert_eq!(parse_yes_no("maybe"), None);
+    }
+}
@@ -0,0 +1,83 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::process;
+
+/// The clipboard programs to try, in order: `pbcopy` on macOS, `clip` on
+/// Windows, and `wl-copy` on Wayland or `xclip` and `xsel` on X11 elsewhere.
+fn programs(os: &str, wayland: bool) -> Vec<(&'static str, &'static [&'static str])> {
+    match os {
+        "macos" => vec![("pbcopy", &[])],
+        "windows" => vec![("clip", &[])],
+        _ => {
+            let mut programs: Vec<(&str, &[&str])> = vec![
+                ("xclip", &["-selection", "clipboard"]),
+                ("xsel", &["--clipboard", "--input"]),
+            ];
+            if wayland {
+                programs.insert(0, ("wl-copy", &[]));
+            }
+            programs
+        }
+    }
+}
+
+fn run(program: &str, args: &[&str], text: &str) -> Result<(), String> {
+    let mut child = process::spawn(
+        Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped()),
+    )
+    .map_err(|e| format!("Failed to execute {}: {}", program, e))?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(text.as_bytes())
+        .map_err(|e| e.to_string())?;
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Places `text` on the system clipboard with the first clipboard program
+/// that works.
+pub fn copy(text: &str) -> Result<(), String> {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some_and(|display| !display.is_empty());
+    let mut errors = vec![];
+    for (program, args) in programs(std::env::consts::OS, wayland) {
+        match run(program, args, text) {
+            Ok(()) => return Ok(()),
+            Err(e) => errors.push(e),
+        }
+    }
+    Err(errors.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_programs() {
+        assert_eq!(programs("macos", false)[0].0, "pbcopy");
+        assert_eq!(programs("windows", true)[0].0, "clip");
+        let names = |wayland| {
+            programs("linux", wayland)
+                .into_iter()
+                .map(|(program, _)| program)
+                .collect::<Vec<&str>>()
+        };
+        assert_eq!(names(false), vec!["xclip", "xsel"]);
+        assert_eq!(names(true), vec!["wl-copy", "xclip", "xsel"]);
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use crate::config::Config;
+
+/// Colors for projects without a configured color, picked by the name.
+pub const PALETTE: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#9c755f",
+];
+
+/// Color of time without a project.
+const NO_PROJECT: &str = "#9d9d9d";
+
+/// Whether output to the terminal may be colored: standard output is a
+/// terminal and `NO_COLOR` is not set.
+#[cfg(not(test))]
+pub fn enabled() -> bool {
+    use std::io::IsTerminal;
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
+
+/// Tests compare plain text, wherever they run.
+#[cfg(test)]
+pub fn enabled() -> bool {
+    false
+}
+
+/// The red, green and blue of a `#rrggbb` color.
+fn rgb(hex: &str) -> Option<(u8, u8, u8)> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// A color from `PALETTE` for `project`, the same one every time. FNV-1a
+/// is used since it does not change between Rust versions.
+fn palette_color(project: &str) -> &'static str {
+    let hash = project.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    PALETTE[(hash % PALETTE.len() as u64) as usize]
+}
+
+/// The color of each project: the `color` configured for it, or one picked
+/// by its name, so a project looks the same in every listing.
+#[derive(Default)]
+pub struct ProjectColors {
+    configured: BTreeMap<String, String>,
+    /// Whether `paint` colors text for the terminal.
+    terminal: bool,
+}
+
+impl ProjectColors {
+    pub fn new(config: &Config, terminal: bool) -> ProjectColors {
+        ProjectColors {
+            configured: config
+                .projects
+                .iter()
+                .filter_map(|(project, settings)| {
+                    let color = settings.color.as_ref()?;
+                    rgb(color)?;
+                    Some((project.clone(), color.to_lowercase()))
+                })
+                .collect(),
+            terminal,
+        }
+    }
+
+    /// The colors for output to the terminal, if it can show them.
+    pub fn for_terminal(config: &Config) -> ProjectColors {
+        ProjectColors::new(config, enabled())
+    }
+
+    /// The color of `project` as `#rrggbb`.
+    pub fn hex(&self, project: Option<&str>) -> String {
+        match project {
+            Some(project) => match self.configured.get(project) {
+                Some(color) => color.clone(),
+                None => palette_color(project).to_string(),
+            },
+            None => NO_PROJECT.to_string(),
+        }
+    }
+
+    /// `text` in the color of `project` on the terminal, or as it is.
+    pub fn paint(&self, project: Option<&str>, text: &str) -> String {
+        if !self.terminal || text.is_empty() {
+            return text.to_string();
+        }
+        let (red, green, blue) = rgb(&self.hex(project)).unwrap();
+        format!("\x1b[38;2;{};{};{}m{}\x1b[0m", red, green, blue, text)
+    }
+}
+
+/// The number of characters of `text` shown on the terminal, leaving out
+/// color codes.
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProjectSettings;
+
+    #[test]
+    fn test_project_colors() {
+        let config = Config {
+            projects: BTreeMap::from([
+                (
+                    "acme".to_string(),
+                    ProjectSettings {
+                        color: Some("#FF8800".to_string()),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "broken".to_string(),
+                    ProjectSettings {
+                        color: Some("orange".to_string()),
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            ..Default::default()
+        };
+        let colors = ProjectColors::new(&config, true);
+        assert_eq!(colors.hex(Some("acme")), "#ff8800");
+        assert_eq!(colors.hex(Some("broken")), palette_color("broken"));
+        assert_eq!(colors.hex(Some("jobclock")), colors.hex(Some("jobclock")));
+        assert_eq!(colors.hex(None), NO_PROJECT);
+
+        let painted = colors.paint(Some("acme"), "[acme]");
+        assert_eq!(painted, "\x1b[38;2;255;136;0m[acme]\x1b[0m");
+        assert_eq!(visible_width(&painted), 6);
+        assert_eq!(
+            ProjectColors::new(&config, false).paint(Some("acme"), "[acme]"),
+            "[acme]"
+        );
+    }
+}
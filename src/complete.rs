@@ -0,0 +1,89 @@
+use std::collections::BTreeSet;
+
+use crate::{config::Config, log, project, tags, Task};
+
+/// Project or tag names used by `tasks`, for shell completion.
+fn task_names<'a>(tasks: impl Iterator<Item = &'a Task>, kind: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    for task in tasks {
+        match kind {
+            "projects" => names.extend(task.project.clone()),
+            _ => names.extend(task.tags.iter().map(|tag| tags::canonical(tag))),
+        }
+    }
+    names
+}
+
+/// Prints the existing project or tag names, one per line, from the log, the
+/// current session and the config. Called by completion scripts through the
+/// hidden `_complete` command.
+pub fn complete(kind: &str, session_tasks: &[Task]) {
+    if kind != "projects" && kind != "tags" {
+        return;
+    }
+    let entries = log::load();
+    let mut names = task_names(
+        entries
+            .iter()
+            .flat_map(|entry| &entry.tasks)
+            .chain(session_tasks),
+        kind,
+    );
+
+    let config = Config::current();
+    for rule in &config.rules {
+        match kind {
+            "projects" => names.extend(rule.project.clone()),
+            _ => names.extend(rule.tags.iter().cloned()),
+        }
+    }
+    if kind == "projects" {
+        names.extend(config.paths.iter().map(|rule| rule.project.clone()));
+        names.extend(config.projects.keys().cloned());
+        names.extend(
+            entries
+                .iter()
+                .flat_map(|entry| &entry.expenses)
+                .filter_map(|expense| expense.project.clone()),
+        );
+        for archived in project::archived() {
+            names.remove(&archived);
+        }
+    }
+
+    for name in names {
+        println!("{}", name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_names() {
+        let task = |project: Option<&str>, tags: &[&str]| {
+            let mut task = Task::new("Work", chrono::Local::now());
+            task.project = project.map(|project| project.to_string());
+            task.tags = tags.iter().map(|tag| tag.to_string()).collect();
+            task
+        };
+        let tasks = [
+            task(Some("acme"), &["review"]),
+            task(None, &["meeting", "review"]),
+            task(Some("acme"), &[]),
+        ];
+        assert_eq!(
+            task_names(tasks.iter(), "projects")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["acme"]
+        );
+        assert_eq!(
+            task_names(tasks.iter(), "tags")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["meeting", "review"]
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use crate::{format_duration, log::LogEntry, report::Day, week::Weeks, Task};
+
+/// Working more than `after_hours` in a day requires at least `minutes` of
+/// breaks that day.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct BreakRule {
+    pub after_hours: f64,
+    pub minutes: i64,
+}
+
+/// The break required after `worked`, taken from the strictest rule that
+/// applies.
+pub fn required_break(rules: &[BreakRule], worked: chrono::Duration) -> chrono::Duration {
+    let hours = worked.num_seconds() as f64 / 3600.0;
+    rules
+        .iter()
+        .filter(|rule| hours > rule.after_hours)
+        .map(|rule| chrono::Duration::try_minutes(rule.minutes).unwrap())
+        .max()
+        .unwrap_or(chrono::Duration::zero())
+}
+
+/// Reminds to take a break after `after_minutes` of work without a pause of
+/// at least `pause_minutes`.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct BreakReminder {
+    pub after_minutes: i64,
+    pub pause_minutes: i64,
+}
+
+impl Default for BreakReminder {
+    fn default() -> BreakReminder {
+        BreakReminder {
+            after_minutes: 90,
+            pause_minutes: 5,
+        }
+    }
+}
+
+/// Asks on `end` whether long stretches without a new task were breaks.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct IdleGaps {
+    pub after_minutes: i64,
+}
+
+impl Default for IdleGaps {
+    fn default() -> IdleGaps {
+        IdleGaps { after_minutes: 60 }
+    }
+}
+
+/// Stretches of at least `threshold` between the start of a session, its
+/// tasks and its end, in time order.
+pub fn idle_gaps(
+    start: chrono::DateTime<chrono::Local>,
+    end: chrono::DateTime<chrono::Local>,
+    tasks: &[Task],
+    threshold: chrono::Duration,
+) -> Vec<(
+    chrono::DateTime<chrono::Local>,
+    chrono::DateTime<chrono::Local>,
+)> {
+    let mut times = tasks
+        .iter()
+        .map(|task| task.created_at.clamp(start, end))
+        .collect::<Vec<chrono::DateTime<chrono::Local>>>();
+    times.push(start);
+    times.push(end);
+    times.sort();
+    times
+        .windows(2)
+        .filter(|pair| pair[1] - pair[0] >= threshold)
+        .map(|pair| (pair[0], pair[1]))
+        .collect()
+}
+
+/// When work started without a pause, for a session started at `start`.
+/// Logged sessions that ended less than `pause` before the next one started
+/// count as the same stretch of work.
+pub fn continuous_since(
+    entries: &[LogEntry],
+    start: chrono::DateTime<chrono::Local>,
+    pause: chrono::Duration,
+) -> chrono::DateTime<chrono::Local> {
+    let mut entries = entries
+        .iter()
+        .filter(|entry| entry.end_time <= start)
+        .collect::<Vec<&LogEntry>>();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.end_time));
+
+    let mut since = start;
+    for entry in entries {
+        if since - entry.end_time >= pause {
+            break;
+        }
+        since = since.min(entry.start_time);
+    }
+    since
+}
+
+/// Limits on working time, by default those of the Norwegian Working
+/// Environment Act. A limit set to `null` is not checked.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct WorkingHours {
+    pub max_hours_per_day: Option<f64>,
+    pub max_hours_per_week: Option<f64>,
+    /// Rest required between the work of one day and the next.
+    pub daily_rest_hours: Option<f64>,
+    /// Rest required at a stretch once every week.
+    pub weekly_rest_hours: Option<f64>,
+}
+
+impl Default for WorkingHours {
+    fn default() -> WorkingHours {
+        WorkingHours {
+            max_hours_per_day: Some(9.0),
+            max_hours_per_week: Some(40.0),
+            daily_rest_hours: Some(11.0),
+            weekly_rest_hours: Some(35.0),
+        }
+    }
+}
+
+fn hours(hours: f64) -> chrono::Duration {
+    chrono::Duration::try_seconds((hours * 3600.0) as i64).unwrap()
+}
+
+fn midnight(date: chrono::NaiveDate) -> chrono::DateTime<chrono::Local> {
+    date.and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .unwrap()
+}
+
+/// The longest time without work from `start` to `end`, given the sessions
+/// in that time.
+fn longest_rest(
+    sessions: &[&LogEntry],
+    start: chrono::DateTime<chrono::Local>,
+    end: chrono::DateTime<chrono::Local>,
+) -> chrono::Duration {
+    let mut sessions = sessions.to_vec();
+    sessions.sort_by_key(|session| session.start_time);
+    let mut longest = chrono::Duration::zero();
+    let mut free_since = start;
+    for session in sessions {
+        longest = longest.max(session.start_time.min(end) - free_since);
+        free_since = free_since.max(session.end_time);
+    }
+    longest.max(end - free_since)
+}
+
+/// Descriptions of the breaches of `rules` on `days`. The weekly rest is
+/// only checked for weeks that are over at `now`.
+pub fn working_hours_violations(
+    days: &[Day],
+    rules: &WorkingHours,
+    weeks: Weeks,
+    now: chrono::DateTime<chrono::Local>,
+) -> Vec<String> {
+    let mut violations = vec![];
+    if let Some(max) = rules.max_hours_per_day {
+        for day in days.iter().filter(|day| day.worked() > hours(max)) {
+            violations.push(format!(
+                "{} - {} worked, more than {}h a day",
+                day.date.format("%a %d-%m-%Y"),
+                format_duration(day.worked()),
+                max
+            ));
+        }
+    }
+    if let Some(min) = rules.daily_rest_hours {
+        for pair in days.windows(2) {
+            let ended = pair[0]
+                .sessions
+                .iter()
+                .map(|session| session.end_time)
+                .max();
+            let started = pair[1]
+                .sessions
+                .iter()
+                .map(|session| session.start_time)
+                .min();
+            let (Some(ended), Some(started)) = (ended, started) else {
+                continue;
+            };
+            if started - ended < hours(min) {
+                violations.push(format!(
+                    "{} - {} rest after the day before, less than {}h",
+                    pair[1].date.format("%a %d-%m-%Y"),
+                    format_duration(started - ended),
+                    min
+                ));
+            }
+        }
+    }
+
+    let mut week_starts = days
+        .iter()
+        .map(|day| weeks.first_day(day.date))
+        .collect::<Vec<chrono::NaiveDate>>();
+    week_starts.dedup();
+    for first in week_starts {
+        let last = first + chrono::Days::new(6);
+        let sessions = days
+            .iter()
+            .filter(|day| day.date >= first && day.date <= last)
+            .flat_map(|day| &day.sessions)
+            .collect::<Vec<&LogEntry>>();
+        if let Some(max) = rules.max_hours_per_week {
+            let worked = sessions
+                .iter()
+                .fold(chrono::Duration::zero(), |total, session| {
+                    total + session.duration()
+                });
+            if worked > hours(max) {
+                violations.push(format!(
+                    "Week {} - {} worked, more than {}h a week",
+                    weeks.label(first),
+                    format_duration(worked),
+                    max
+                ));
+            }
+        }
+        let (start, end) = (midnight(first), midnight(last + chrono::Days::new(1)));
+        if let Some(min) = rules.weekly_rest_hours.filter(|_| end <= now) {
+            let rest = longest_rest(&sessions, start, end);
+            if rest < hours(min) {
+                violations.push(format!(
+                    "Week {} - longest rest {}, less than {}h",
+                    weeks.label(first),
+                    format_duration(rest),
+                    min
+                ));
+            }
+        }
+    }
+    violations
+}
+
+/// The working hours section of the report: the breaches of `rules`, or a
+/// line saying there were none.
+pub fn render_working_hours(
+    days: &[Day],
+    rules: &WorkingHours,
+    weeks: Weeks,
+    now: chrono::DateTime<chrono::Local>,
+) -> String {
+    let mut text = "Working hours:\n".to_string();
+    let violations = working_hours_violations(days, rules, weeks, now);
+    if violations.is_empty() {
+        text.push_str("  Within the working hour rules\n");
+    }
+    for violation in violations {
+        text.push_str(&format!("  {}\n", violation));
+    }
+    text
+}
+
+pub fn print_break_compliance(days: &[Day], rules: &[BreakRule]) {
+    println!("Break compliance:");
+    if rules.is_empty() {
+        println!("  No break rules configured");
+        return;
+    }
+
+    let mut compliant = true;
+    for day in days {
+        let required = required_break(rules, day.worked());
+        if day.breaks() < required {
+            compliant = false;
+            println!(
+                "  {} - Insufficient break: {} worked, {} break, {} required",
+                day.date.format("%a %d-%m-%Y"),
+                format_duration(day.worked()),
+                format_duration(day.breaks()),
+                format_duration(required)
+            );
+        }
+    }
+    if compliant {
+        println!("  All days have sufficient breaks");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_break() {
+        let rules = vec![
+            BreakRule {
+                after_hours: 5.5,
+                minutes: 30,
+            },
+            BreakRule {
+                after_hours: 9.0,
+                minutes: 45,
+            },
+        ];
+        let required =
+            |hours: i64| required_break(&rules, chrono::Duration::try_minutes(hours * 60).unwrap());
+        assert_eq!(required(5), chrono::Duration::zero());
+        assert_eq!(required(6), chrono::Duration::try_minutes(30).unwrap());
+        assert_eq!(required(10), chrono::Duration::try_minutes(45).unwrap());
+    }
+
+    #[test]
+    fn test_continuous_since() {
+        use chrono::TimeZone;
+        let at = |hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 13, hour, minute, 0)
+                .unwrap()
+        };
+        let entry = |start, end| LogEntry {
+            start_time: start,
+            end_time: end,
+            ..Default::default()
+        };
+        let entries = [
+            entry(at(8, 0), at(9, 0)),
+            entry(at(9, 30), at(10, 58)),
+            entry(at(11, 0), at(11, 30)),
+        ];
+        let pause = chrono::Duration::try_minutes(5).unwrap();
+        assert_eq!(continuous_since(&entries, at(11, 32), pause), at(9, 30));
+        assert_eq!(continuous_since(&entries, at(12, 0), pause), at(12, 0));
+    }
+
+    #[test]
+    fn test_working_hours_violations() {
+        use chrono::TimeZone;
+        let at = |day, hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, day, hour, 0, 0)
+                .unwrap()
+        };
+        let entry = |day, from, to| LogEntry {
+            start_time: at(day, from),
+            end_time: at(day, to),
+            ..Default::default()
+        };
+        // A 10 hour Monday, 9 hours of rest before Tuesday, 45 hours in the
+        // week and work on Saturday leaving 34 hours of rest.
+        let days = crate::report::days(&[
+            entry(3, 8, 18),
+            entry(4, 3, 12),
+            entry(5, 8, 16),
+            entry(6, 8, 16),
+            entry(7, 8, 16),
+            entry(8, 12, 14),
+        ]);
+        let rules = WorkingHours::default();
+        assert_eq!(
+            working_hours_violations(&days, &rules, Weeks::default(), at(20, 12)),
+            vec![
+                "Mon 03-06-2024 - 10h 0m 0s worked, more than 9h a day",
+                "Tue 04-06-2024 - 9h 0m 0s rest after the day before, less than 11h",
+                "Week 2024-W23 - 45h 0m 0s worked, more than 40h a week",
+                "Week 2024-W23 - longest rest 34h 0m 0s, less than 35h",
+            ]
+        );
+        // The weekly rest can still be taken before the week is over.
+        assert_eq!(
+            working_hours_violations(&days, &rules, Weeks::default(), at(8, 15)).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_idle_gaps() {
+        use chrono::TimeZone;
+        let at = |hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 13, hour, minute, 0)
+                .unwrap()
+        };
+        let tasks = [
+            Task::new("Deploy", at(13, 0)),
+            Task::new("Fix parser", at(9, 30)),
+            Task::new("Review", at(10, 0)),
+        ];
+        let threshold = chrono::Duration::try_minutes(60).unwrap();
+        assert_eq!(
+            idle_gaps(at(9, 0), at(15, 0), &tasks, threshold),
+            vec![(at(10, 0), at(13, 0)), (at(13, 0), at(15, 0))]
+        );
+        assert!(idle_gaps(at(9, 0), at(9, 45), &tasks, threshold).is_empty());
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    activity::ActivitySampling,
+    ai::AiSummaryConfig,
+    compliance::{BreakReminder, BreakRule, IdleGaps, WorkingHours},
+    earnings::RateCard,
+    recurring::RecurringTask,
+    rounding::Rounding,
+    rules::{PathRule, Rule},
+    storage::StorageMode,
+    summary::SummaryFormat,
+    team::{ServerConfig, TeamServer},
+    timebank::Timebank,
+    week::{WeekNumbering, WeekStart},
+};
+
+/// User configuration, read from `config.json` in the config folder. Every
+/// field is optional so a partial file only overrides what it mentions.
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+#[serde(default)]
+pub struct Config {
+    pub rules: Vec<Rule>,
+    /// Tags reported under another tag, such as `{"mtg": "meeting"}`.
+    pub tag_aliases: BTreeMap<String, String>,
+    /// Shortcuts for commands, such as `{"t": "task"}`.
+    pub aliases: BTreeMap<String, String>,
+    pub breaks: Vec<BreakRule>,
+    /// Limits on working time checked in reports.
+    pub working_hours: Option<WorkingHours>,
+    pub break_reminder: Option<BreakReminder>,
+    pub idle_gaps: Option<IdleGaps>,
+    /// Sampling of the active window with `activity sample`, off by default.
+    pub activity: Option<ActivitySampling>,
+    pub repositories: Vec<String>,
+    pub paths: Vec<PathRule>,
+    /// Start a session when a heartbeat arrives and none is running.
+    pub heartbeat_auto_start: bool,
+    /// Refuse to begin while a session older than 24 hours is still open.
+    pub require_end_same_day: bool,
+    /// Tag tasks added in a git repository with its current branch, such as
+    /// `branch:feature/login`.
+    pub branch_tags: bool,
+    /// Offer to resume the last session on `begin` when it ended less than
+    /// this many minutes ago.
+    pub resume_within_minutes: Option<i64>,
+    /// Let `cancel` discard a session up to this many minutes after it
+    /// began without `--force`, 5 by default.
+    pub cancel_within_minutes: Option<i64>,
+    pub rates: Option<RateCard>,
+    /// Home currency that totals are converted to.
+    pub currency: Option<String>,
+    /// Value of one unit of each currency in the home currency.
+    pub exchange_rates: BTreeMap<String, f64>,
+    pub projects: BTreeMap<String, ProjectSettings>,
+    pub summary: SummaryFormat,
+    /// Path to the git executable, for when it is not on the `PATH`.
+    pub git_path: Option<String>,
+    pub recurring: Vec<RecurringTask>,
+    /// Rounding of billed time in exports.
+    pub rounding: Option<Rounding>,
+    pub ai_summary: Option<AiSummaryConfig>,
+    /// Commands `eod` runs, in order, instead of the default routine.
+    pub eod: Option<Vec<String>>,
+    /// Team server to `push` the log to.
+    pub team: Option<TeamServer>,
+    /// Users allowed to push to `server`.
+    pub server: Option<ServerConfig>,
+    /// Project for sessions that no `--project` flag or path rule assigns.
+    pub project: Option<String>,
+    /// Flexitime agreement for `timebank`.
+    pub timebank: Option<Timebank>,
+    pub week_start: WeekStart,
+    pub week_numbering: WeekNumbering,
+    /// `synced` when the data folder is kept in Dropbox, iCloud Drive or the
+    /// like.
+    pub storage: StorageMode,
+}
+
+/// Settings for a single project, keyed by project name in the config.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ProjectSettings {
+    /// Currency the project is billed in, if not the home currency.
+    pub currency: Option<String>,
+    /// Client the project is done for, used in accounting exports.
+    pub client: Option<String>,
+    /// Hours the project may take in total.
+    pub budget_hours: Option<f64>,
+    /// Earnings the project may reach in total, in its currency.
+    pub budget: Option<f64>,
+    /// Purchase order number the client bills the project against, added to
+    /// every line of the exports.
+    pub po_number: Option<String>,
+    /// Cost center the client books the project on, added to every line of
+    /// the exports.
+    pub cost_center: Option<String>,
+    /// Color the project is shown in, as `#rrggbb`. Without one, a color is
+    /// picked by the project name.
+    pub color: Option<String>,
+}
+
+/// The user's home directory, from `HOME` or, on Windows, `USERPROFILE`.
+pub fn home_dir() -> Option<std::path::PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .or_else(|| std::env::var_os("USERPROFILE").filter(|home| !home.is_empty()))
+        .map(std::path::PathBuf::from)
+}
+
+/// Expands a leading `~/` (or `~\`) in a configured path to the home
+/// directory.
+pub fn expand_home(path: &str) -> std::path::PathBuf {
+    match path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")) {
+        Some(rest) => match home_dir() {
+            Some(home) => home.join(rest),
+            None => std::path::PathBuf::from(path),
+        },
+        None => std::path::PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+pub fn config_folder() -> std::path::PathBuf {
+    let mut path = std::path::PathBuf::new();
+    path.push("tmp");
+    path
+}
+
+/// The folder configuration goes in: `XDG_CONFIG_HOME`, the roaming
+/// application data folder on Windows, or `~/.config`.
+#[cfg(not(test))]
+fn config_home() -> Option<std::path::PathBuf> {
+    let dir = |name| std::env::var_os(name).filter(|dir| !dir.is_empty());
+    if let Some(dir) = dir("XDG_CONFIG_HOME") {
+        return Some(std::path::PathBuf::from(dir));
+    }
+    if cfg!(windows) {
+        if let Some(dir) = dir("APPDATA") {
+            return Some(std::path::PathBuf::from(dir));
+        }
+    }
+    home_dir().map(|home| home.join(".config"))
+}
+
+#[cfg(not(test))]
+pub fn config_folder() -> std::path::PathBuf {
+    let Some(mut path) = config_home() else {
+        return crate::persistent_folder();
+    };
+    path.push("jobclock");
+    if let Some(profile) = crate::profile() {
+        path.push("profiles");
+        path.push(profile);
+    }
+    path
+}
+
+pub fn config_file() -> std::path::PathBuf {
+    let mut path = config_folder();
+    path.push("config.json");
+    path
+}
+
+/// The most minutes a setting in minutes can be, a year.
+const MAX_MINUTES: i64 = 366 * 24 * 60;
+
+fn parse_bool(name: &str, value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("{} must be true or false, not {}", name, value)),
+    }
+}
+
+impl Config {
+    /// The settings of the config file, overridden by environment variables.
+    /// Command line flags are applied on top by the commands that take them.
+    pub fn load() -> Result<Config, String> {
+        let path = config_file();
+        let mut config = if path.exists() {
+            std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|data| serde_json::from_str(&data).map_err(|e| e.to_string()))
+                .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?
+        } else {
+            Config::default()
+        };
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        config
+            .apply_env(var)
+            .map_err(|e| format!("Invalid environment variable: {}", e))?;
+        config
+            .check_minutes()
+            .map_err(|e| format!("Invalid config: {}", e))?;
+        Ok(config)
+    }
+
+    /// Checks that the settings in minutes are at most `MAX_MINUTES` and
+    /// not negative, so they can be turned into durations.
+    fn check_minutes(&self) -> Result<(), String> {
+        let mut settings = vec![
+            ("resume_within_minutes", self.resume_within_minutes),
+            ("cancel_within_minutes", self.cancel_within_minutes),
+            (
+                "break_reminder.after_minutes",
+                self.break_reminder
+                    .as_ref()
+                    .map(|reminder| reminder.after_minutes),
+            ),
+            (
+                "break_reminder.pause_minutes",
+                self.break_reminder
+                    .as_ref()
+                    .map(|reminder| reminder.pause_minutes),
+            ),
+            (
+                "idle_gaps.after_minutes",
+                self.idle_gaps
+                    .as_ref()
+                    .map(|idle_gaps| idle_gaps.after_minutes),
+            ),
+            (
+                "activity.every_minutes",
+                self.activity
+                    .as_ref()
+                    .map(|activity| activity.every_minutes),
+            ),
+            (
+                "rounding.minutes",
+                self.rounding.as_ref().map(|rounding| rounding.minutes),
+            ),
+        ];
+        settings.extend(
+            self.breaks
+                .iter()
+                .map(|rule| ("breaks.minutes", Some(rule.minutes))),
+        );
+        for (name, minutes) in settings {
+            if let Some(minutes) = minutes.filter(|minutes| !(0..=MAX_MINUTES).contains(minutes)) {
+                return Err(format!(
+                    "{} must be between 0 and {}, not {}",
+                    name, MAX_MINUTES, minutes
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The config for a command. It is checked to load before every command
+    /// runs, and should it become invalid while one runs, the defaults are
+    /// used.
+    pub fn current() -> Config {
+        Config::load().unwrap_or_default()
+    }
+
+    /// Overrides settings with the `JOBCLOCK_*` variables that `var` finds,
+    /// so containers and CI jobs can be configured without a config file.
+    /// Every setting has a variable named after it in upper case, such as
+    /// `JOBCLOCK_WEEK_START` for `week_start`, holding its value as in the
+    /// config file. Text can be given without quotes, and `true`/`false`
+    /// settings also take `yes`, `no`, `on`, `off`, `1` and `0`.
+    /// `JOBCLOCK_RATE` sets `rates.hourly` alone.
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        let mut settings = serde_json::to_value(&*self).map_err(|e| e.to_string())?;
+        let names = settings
+            .as_object()
+            .map(|fields| fields.keys().cloned().collect::<Vec<String>>())
+            .unwrap_or_default();
+        for name in names {
+            let variable = format!("JOBCLOCK_{}", name.to_uppercase());
+            let Some(text) = var(&variable) else {
+                continue;
+            };
+            let candidates = if settings[&name].is_boolean() {
+                vec![serde_json::Value::Bool(parse_bool(&variable, &text)?)]
+            } else {
+                serde_json::from_str(&text)
+                    .into_iter()
+                    .chain([serde_json::Value::String(text.clone())])
+                    .collect()
+            };
+            let mut error = String::new();
+            let accepted = candidates.into_iter().find_map(|candidate| {
+                let mut changed = settings.clone();
+                changed[&name] = candidate;
+                match serde_json::from_value::<Config>(changed.clone()) {
+                    Ok(_) => Some(changed),
+                    Err(e) => {
+                        error = e.to_string();
+                        None
+                    }
+                }
+            });
+            match accepted {
+                Some(changed) => settings = changed,
+                None => return Err(format!("{} is not a valid {}: {}", variable, name, error)),
+            }
+        }
+        *self = serde_json::from_value(settings).map_err(|e| e.to_string())?;
+
+        if let Some(rate) = var("JOBCLOCK_RATE") {
+            let hourly = rate
+                .parse::<f64>()
+                .ok()
+                .filter(|hourly| *hourly >= 0.0)
+                .ok_or_else(|| format!("JOBCLOCK_RATE must be a number, not {}", rate))?;
+            self.rates.get_or_insert_with(RateCard::default).hourly = hourly;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let mut config = Config {
+            currency: Some("NOK".to_string()),
+            git_path: Some("/usr/bin/git".to_string()),
+            ..Default::default()
+        };
+        config
+            .apply_env(env(&[
+                ("JOBCLOCK_PROJECT", "acme"),
+                ("JOBCLOCK_RATE", "950.5"),
+                ("JOBCLOCK_CURRENCY", "EUR"),
+                ("JOBCLOCK_HEARTBEAT_AUTO_START", "yes"),
+            ]))
+            .unwrap();
+        assert_eq!(config.project.as_deref(), Some("acme"));
+        assert_eq!(config.rates.as_ref().map(|rates| rates.hourly), Some(950.5));
+        assert_eq!(config.currency.as_deref(), Some("EUR"));
+        assert_eq!(config.git_path.as_deref(), Some("/usr/bin/git"));
+        assert!(config.heartbeat_auto_start);
+
+        config
+            .apply_env(env(&[
+                ("JOBCLOCK_PROJECT", "1234"),
+                ("JOBCLOCK_BRANCH_TAGS", "on"),
+                ("JOBCLOCK_CANCEL_WITHIN_MINUTES", "15"),
+                ("JOBCLOCK_WEEK_START", "sunday"),
+                ("JOBCLOCK_STORAGE", "synced"),
+                ("JOBCLOCK_REPOSITORIES", r#"["~/src/jobclock"]"#),
+                ("JOBCLOCK_ALIASES", r#"{"t": "task"}"#),
+            ]))
+            .unwrap();
+        assert_eq!(config.project.as_deref(), Some("1234"));
+        assert!(config.branch_tags);
+        assert_eq!(config.cancel_within_minutes, Some(15));
+        assert_eq!(config.week_start, WeekStart::Sunday);
+        assert_eq!(config.storage, StorageMode::Synced);
+        assert_eq!(config.repositories, vec!["~/src/jobclock".to_string()]);
+        assert_eq!(config.aliases.get("t").map(String::as_str), Some("task"));
+        assert_eq!(config.rates.as_ref().map(|rates| rates.hourly), Some(950.5));
+        assert!(config
+            .apply_env(env(&[("JOBCLOCK_CANCEL_WITHIN_MINUTES", "soon")]))
+            .is_err());
+
+        assert!(config.apply_env(env(&[("JOBCLOCK_RATE", "lots")])).is_err());
+        assert!(config
+            .apply_env(env(&[("JOBCLOCK_REQUIRE_END_SAME_DAY", "maybe")]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_check_minutes() {
+        let mut config = Config::default();
+        assert!(config.check_minutes().is_ok());
+        config.resume_within_minutes = Some(i64::MAX);
+        assert!(config.check_minutes().is_err());
+        config.resume_within_minutes = Some(15);
+        config.idle_gaps = Some(IdleGaps { after_minutes: -1 });
+        assert!(config.check_minutes().is_err());
+    }
+}
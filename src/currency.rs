@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use crate::config::Config;
+
+/// Converts amounts into the home currency using the configured exchange
+/// rates, given as the value of one unit in the home currency.
+pub struct Converter {
+    pub home: Option<String>,
+    rates: BTreeMap<String, f64>,
+}
+
+impl Converter {
+    pub fn new(config: &Config) -> Converter {
+        Converter {
+            home: config.currency.clone(),
+            rates: config.exchange_rates.clone(),
+        }
+    }
+
+    /// Converts `amount` in `currency` (the home currency if `None`).
+    pub fn to_home(&self, amount: f64, currency: Option<&str>) -> Result<f64, String> {
+        match currency {
+            None => Ok(amount),
+            Some(currency) if Some(currency) == self.home.as_deref() => Ok(amount),
+            Some(currency) => match self.rates.get(currency) {
+                Some(rate) => Ok(amount * rate),
+                None => Err(format!("No exchange rate configured for {}", currency)),
+            },
+        }
+    }
+
+    /// Formats an amount in the home currency.
+    pub fn format(&self, amount: f64) -> String {
+        format(amount, self.home.as_deref())
+    }
+}
+
+pub fn format(amount: f64, currency: Option<&str>) -> String {
+    match currency {
+        Some(currency) => format!("{:.2} {}", amount, currency),
+        None => format!("{:.2}", amount),
+    }
+}
+
+/// The currency of `project`, if it has one other than the home currency.
+pub fn project_currency(config: &Config, project: Option<&str>) -> Option<String> {
+    config
+        .projects
+        .get(project?)
+        .and_then(|settings| settings.currency.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let config = Config {
+            currency: Some("NOK".to_string()),
+            exchange_rates: BTreeMap::from([("EUR".to_string(), 11.5)]),
+            ..Default::default()
+        };
+        let converter = Converter::new(&config);
+        assert_eq!(converter.to_home(10.0, Some("EUR")), Ok(115.0));
+        assert_eq!(converter.to_home(10.0, Some("NOK")), Ok(10.0));
+        assert_eq!(converter.to_home(10.0, None), Ok(10.0));
+        assert!(converter.to_home(10.0, Some("USD")).is_err());
+        assert_eq!(converter.format(115.0), "115.00 NOK");
+    }
+}
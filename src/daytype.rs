@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+
+use chrono::Datelike;
+
+use crate::{
+    cli::{self, Args},
+    config::Config,
+    error::{self, ErrorCode},
+    persistent_folder, recurring, report, storage,
+};
+
+/// What kind of day a date is, set with `day set` where it differs from the
+/// working days of the week.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DayType {
+    Workday,
+    Weekend,
+    Holiday,
+    Sick,
+}
+
+impl DayType {
+    fn parse(text: &str) -> Option<DayType> {
+        match text.trim().to_lowercase().as_str() {
+            "workday" => Some(DayType::Workday),
+            "weekend" => Some(DayType::Weekend),
+            "holiday" => Some(DayType::Holiday),
+            "sick" => Some(DayType::Sick),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            DayType::Workday => "workday",
+            DayType::Weekend => "weekend",
+            DayType::Holiday => "holiday",
+            DayType::Sick => "sick",
+        }
+    }
+}
+
+fn day_types_file() -> std::path::PathBuf {
+    let mut path = persistent_folder();
+    path.push("day_types.json");
+    path
+}
+
+pub fn load() -> BTreeMap<chrono::NaiveDate, DayType> {
+    if !day_types_file().exists() {
+        return BTreeMap::new();
+    }
+    let data = std::fs::read_to_string(day_types_file()).unwrap();
+    serde_json::from_str(&data).unwrap()
+}
+
+fn save(types: &BTreeMap<chrono::NaiveDate, DayType>) {
+    if !persistent_folder().exists() {
+        storage::create_folder(&persistent_folder()).unwrap();
+    }
+    let data = serde_json::to_string(types).unwrap();
+    storage::write(&day_types_file(), data).unwrap();
+}
+
+/// The days of the week that are working days: the `days` of the time bank,
+/// or weekdays.
+pub fn working_days(config: &Config) -> String {
+    config
+        .timebank
+        .as_ref()
+        .map_or("weekdays".to_string(), |timebank| timebank.days.clone())
+}
+
+/// Whether hours are expected on `date`: on a day marked as a workday, and
+/// on the `working_days` of the week unless it is marked otherwise.
+pub fn is_working_day(
+    types: &BTreeMap<chrono::NaiveDate, DayType>,
+    date: chrono::NaiveDate,
+    working_days: &str,
+) -> bool {
+    match types.get(&date) {
+        Some(DayType::Workday) => true,
+        Some(_) => false,
+        None => recurring::runs_on(working_days, date.weekday()),
+    }
+}
+
+/// The share of the working days from `first` to `last` that are not taken
+/// as holidays or sick days, such as 0.6 for a week with two sick days. A
+/// period without working days counts as fully available.
+pub fn available_share(
+    types: &BTreeMap<chrono::NaiveDate, DayType>,
+    first: chrono::NaiveDate,
+    last: chrono::NaiveDate,
+    working_days: &str,
+) -> f64 {
+    let mut scheduled = 0;
+    let mut available = 0;
+    for date in first.iter_days().take_while(|date| *date <= last) {
+        let scheduled_day = match types.get(&date) {
+            Some(DayType::Workday) => true,
+            Some(DayType::Weekend) => false,
+            _ => recurring::runs_on(working_days, date.weekday()),
+        };
+        if scheduled_day {
+            scheduled += 1;
+            if is_working_day(types, date, working_days) {
+                available += 1;
+            }
+        }
+    }
+    if scheduled == 0 {
+        1.0
+    } else {
+        available as f64 / scheduled as f64
+    }
+}
+
+/// `day set <YYYY-MM-DD> <workday|weekend|holiday|sick>`, `day clear
+/// <YYYY-MM-DD>` and `day list`.
+pub fn day(args: &mut Args) {
+    let usage = "Usage: day set <YYYY-MM-DD> <workday|weekend|holiday|sick>";
+    let action = args.shift().unwrap_or("list".to_string());
+    let mut types = load();
+    if action == "list" {
+        if types.is_empty() {
+            println!("No day types set");
+        }
+        for (date, day_type) in &types {
+            println!("  {} - {}", date.format("%a %d-%m-%Y"), day_type.name());
+        }
+        return;
+    }
+    if action != "set" && action != "clear" {
+        println!("{} [clear <YYYY-MM-DD>] [list]", usage);
+        return;
+    }
+    let date = args.shift().unwrap_or_default();
+    let Some(date) = report::parse_date(&date) else {
+        error::print(
+            ErrorCode::InvalidArgument,
+            &format!("Invalid date: {} (expected YYYY-MM-DD)", date),
+        );
+        return;
+    };
+    if action == "clear" {
+        if types.remove(&date).is_none() {
+            println!("No day type set for {}", date.format("%a %d-%m-%Y"));
+            return;
+        }
+        save(&types);
+        cli::info(&format!(
+            "Day type of {} cleared",
+            date.format("%a %d-%m-%Y")
+        ));
+        return;
+    }
+    let Some(day_type) = DayType::parse(&args.text()) else {
+        error::print(ErrorCode::InvalidArgument, usage);
+        return;
+    };
+    types.insert(date, day_type);
+    save(&types);
+    cli::info(&format!(
+        "{} marked as {}",
+        date.format("%a %d-%m-%Y"),
+        day_type.name()
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_available_share() {
+        let date = |day| chrono::NaiveDate::from_ymd_opt(2024, 6, day).unwrap();
+        let types = BTreeMap::from([
+            (date(3), DayType::Sick),
+            (date(4), DayType::Sick),
+            (date(5), DayType::Weekend),
+            (date(8), DayType::Workday),
+        ]);
+        assert!(!is_working_day(&types, date(3), "weekdays"));
+        assert!(is_working_day(&types, date(6), "weekdays"));
+        assert!(is_working_day(&types, date(8), "weekdays"));
+        assert!(!is_working_day(&types, date(9), "weekdays"));
+        // Mon 3 to Sun 9 June: Mon, Tue, Thu, Fri and Sat scheduled, Mon
+        // and Tue sick.
+        assert_eq!(available_share(&types, date(3), date(9), "weekdays"), 0.6);
+        assert_eq!(available_share(&types, date(9), date(9), "weekdays"), 1.0);
+        assert_eq!(DayType::parse("Holiday"), Some(DayType::Holiday));
+        assert_eq!(DayType::parse("vacation"), None);
+    }
+}
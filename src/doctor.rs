@@ -0,0 +1,122 @@
+use crate::{
+    cli::{self, Args},
+    config,
+    error::{self, ErrorCode},
+    persistent_folder,
+};
+
+/// Files and folders in `folder`, and `folder` itself, that other users can
+/// access, with their permission bits. Symbolic links are not followed.
+#[cfg(unix)]
+fn too_open(folder: &std::path::Path) -> Vec<(std::path::PathBuf, u32)> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut found = vec![];
+    let Ok(metadata) = std::fs::symlink_metadata(folder) else {
+        return found;
+    };
+    if metadata.file_type().is_symlink() {
+        return found;
+    }
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        found.push((folder.to_path_buf(), mode));
+    }
+    if metadata.is_dir() {
+        for entry in std::fs::read_dir(folder).into_iter().flatten().flatten() {
+            found.extend(too_open(&entry.path()));
+        }
+    }
+    found
+}
+
+/// Takes the access of other users away from `path`, keeping the owner's.
+#[cfg(unix)]
+fn restrict(path: &std::path::Path, mode: u32) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode & 0o700))
+}
+
+/// Lists the data and config files that other users can read, and after
+/// confirmation restricts them to the owner.
+#[cfg(unix)]
+fn check_permissions() {
+    let found: Vec<(std::path::PathBuf, u32)> = [persistent_folder(), config::config_folder()]
+        .iter()
+        .flat_map(|folder| too_open(folder))
+        .collect();
+    if found.is_empty() {
+        println!("Permissions: no data or config files are accessible by other users");
+        return;
+    }
+    println!("Permissions: accessible by other users:");
+    for (path, mode) in &found {
+        println!("  {:03o}  {}", mode, path.display());
+    }
+    if !cli::confirm("Restrict them to your user?") {
+        return;
+    }
+    let mut fixed = 0;
+    for (path, mode) in &found {
+        match restrict(path, *mode) {
+            Ok(()) => fixed += 1,
+            Err(e) => println!("WARNING: Cannot change {}: {}", path.display(), e),
+        }
+    }
+    cli::info(&format!(
+        "Restricted {} files and folders to your user",
+        fixed
+    ));
+}
+
+#[cfg(not(unix))]
+fn check_permissions() {
+    println!("Permissions: not checked on this system");
+}
+
+/// `doctor --permissions`: looks for problems with the data and config
+/// files, and offers to fix them.
+pub fn doctor(args: &mut Args) {
+    if !args.flag("--permissions") {
+        error::print(ErrorCode::MissingArgument, "Usage: doctor --permissions");
+        return;
+    }
+    check_permissions();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_too_open() {
+        let folder = std::env::temp_dir().join(format!("jobclock-doctor-{}", std::process::id()));
+        let private = folder.join("log");
+        std::fs::create_dir_all(&private).unwrap();
+        std::fs::write(folder.join("session.json"), "{}").unwrap();
+        std::fs::write(private.join("2024-06.json"), "[]").unwrap();
+        let set = |path: &std::path::Path, mode| {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap()
+        };
+        set(&folder, 0o755);
+        set(&folder.join("session.json"), 0o644);
+        set(&private, 0o700);
+        set(&private.join("2024-06.json"), 0o600);
+
+        let mut found = too_open(&folder);
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                (folder.clone(), 0o755),
+                (folder.join("session.json"), 0o644)
+            ]
+        );
+        for (path, mode) in found {
+            restrict(&path, mode).unwrap();
+        }
+        assert_eq!(too_open(&folder), vec![]);
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use crate::{log::LogEntry, summary, Task};
+
+/// Hourly rate and the multipliers applied to it. Time multipliers do not
+/// stack: the highest one that applies is used. A tag multiplier is applied
+/// on top, using the first of a task's tags that has one.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RateCard {
+    pub hourly: f64,
+    /// Multiplier for Saturdays and Sundays.
+    pub weekend: Option<f64>,
+    pub evening: Option<EveningRate>,
+    /// Multiplier per tag, such as `{"meeting": 0.5}`.
+    pub tags: BTreeMap<String, f64>,
+    /// Hourly rate per project, in the project's currency, replacing `hourly`.
+    pub projects: BTreeMap<String, f64>,
+}
+
+/// Multiplier for hours from `from` until `to` the next morning.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct EveningRate {
+    pub from: u32,
+    pub to: u32,
+    pub multiplier: f64,
+}
+
+impl RateCard {
+    fn time_multiplier(&self, time: chrono::DateTime<chrono::Local>) -> f64 {
+        use chrono::{Datelike, Timelike};
+
+        let mut multiplier: f64 = 1.0;
+        if let Some(weekend) = self.weekend {
+            if matches!(time.weekday(), chrono::Weekday::Sat | chrono::Weekday::Sun) {
+                multiplier = multiplier.max(weekend);
+            }
+        }
+        if let Some(evening) = &self.evening {
+            let hour = time.hour();
+            let inside = if evening.from <= evening.to {
+                evening.from <= hour && hour < evening.to
+            } else {
+                hour >= evening.from || hour < evening.to
+            };
+            if inside {
+                multiplier = multiplier.max(evening.multiplier);
+            }
+        }
+        multiplier
+    }
+
+    fn tag_multiplier(&self, task: Option<&Task>) -> f64 {
+        task.and_then(|task| {
+            task.tags
+                .iter()
+                .find_map(|tag| self.tags.get(&crate::tags::canonical(tag)))
+        })
+        .copied()
+        .unwrap_or(1.0)
+    }
+
+    /// Earnings for the time from `start` to `end`, priced minute by minute.
+    fn span(
+        &self,
+        start: chrono::DateTime<chrono::Local>,
+        end: chrono::DateTime<chrono::Local>,
+        task: Option<&Task>,
+    ) -> f64 {
+        let tag = self.tag_multiplier(task);
+        let hourly = task
+            .and_then(|task| task.project.as_ref())
+            .and_then(|project| self.projects.get(project))
+            .copied()
+            .unwrap_or(self.hourly);
+        let mut earned = 0.0;
+        let mut time = start;
+        while time < end {
+            let next = (time + chrono::Duration::try_minutes(1).unwrap()).min(end);
+            let hours = (next - time).num_seconds() as f64 / 3600.0;
+            earned += hours * hourly * self.time_multiplier(time) * tag;
+            time = next;
+        }
+        earned
+    }
+
+    /// Earnings per project for a logged session, in each project's
+    /// currency. Each task is paid for the time since the previous task, and
+    /// the time after the last task at the plain rate without a project.
+    /// Non-billable tasks earn nothing.
+    pub fn session(&self, entry: &LogEntry) -> Vec<(Option<String>, f64)> {
+        let mut earned: Vec<(Option<String>, f64)> = vec![];
+        let mut add = |project: Option<String>, amount: f64| match earned
+            .iter_mut()
+            .find(|(name, _)| *name == project)
+        {
+            Some((_, total)) => *total += amount,
+            None => earned.push((project, amount)),
+        };
+        let mut last = entry.start_time;
+        for (task, duration) in summary::task_durations(entry.start_time, &entry.tasks) {
+            let end = task.created_at.min(entry.end_time);
+            if task.billable {
+                add(
+                    task.project.clone(),
+                    self.span(end - duration, end, Some(&task)),
+                );
+            }
+            last = last.max(end);
+        }
+        add(None, self.span(last, entry.end_time, None));
+        earned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_session_earnings() {
+        let card = RateCard {
+            hourly: 100.0,
+            weekend: Some(2.0),
+            evening: Some(EveningRate {
+                from: 18,
+                to: 6,
+                multiplier: 1.5,
+            }),
+            tags: BTreeMap::from([("meeting".to_string(), 0.5)]),
+            projects: BTreeMap::from([("acme".to_string(), 200.0)]),
+        };
+        let total = |entry: &LogEntry| {
+            card.session(entry)
+                .iter()
+                .map(|(_, amount)| amount)
+                .sum::<f64>()
+        };
+        // Tuesday
+        let at = |hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 12, hour, 0, 0)
+                .unwrap()
+        };
+        let mut meeting = Task::new("Standup", at(10));
+        meeting.tags = vec!["meeting".to_string()];
+        let mut lunch = Task::new("Lunch", at(11));
+        lunch.billable = false;
+        let mut client = Task::new("Client work", at(12));
+        client.project = Some("acme".to_string());
+        let entry = LogEntry {
+            start_time: at(9),
+            end_time: at(19),
+            tasks: vec![meeting, lunch, client],
+            ..Default::default()
+        };
+        // 1h meeting at half rate, 1h unpaid, 1h for acme, 6h plain and 1h
+        // evening.
+        assert!((total(&entry) - (50.0 + 200.0 + 600.0 + 150.0)).abs() < 1e-6);
+        let earned = card.session(&entry);
+        assert_eq!(earned[1].0.as_deref(), Some("acme"));
+        assert!((earned[1].1 - 200.0).abs() < 1e-6);
+
+        let saturday = LogEntry {
+            start_time: chrono::Local
+                .with_ymd_and_hms(2024, 3, 16, 17, 0, 0)
+                .unwrap(),
+            end_time: chrono::Local
+                .with_ymd_and_hms(2024, 3, 16, 19, 0, 0)
+                .unwrap(),
+            ..Default::default()
+        };
+        assert!((total(&saturday) - 400.0).abs() < 1e-6);
+    }
+}
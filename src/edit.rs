@@ -0,0 +1,263 @@
+use crate::{
+    budget,
+    cli::{self, Args},
+    config::Config,
+    error::{self, ErrorCode},
+    format_duration, log,
+    log::LogEntry,
+};
+
+/// The logged session with `id`, given as text on the command line.
+pub fn find(entries: &[LogEntry], id: &str) -> Result<usize, String> {
+    let id = id
+        .trim_start_matches('#')
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid session id: {}", id))?;
+    entries
+        .iter()
+        .position(|entry| entry.id == id)
+        .ok_or_else(|| format!("No logged session with id {}", id))
+}
+
+/// Divides `entry` at `at` into the session before and the session after,
+/// which gets `id`. A task belongs to the part its time ends in, so a task
+/// added exactly at `at` stays in the first part. Timers, the goal and the
+/// approval stay with the first part, and expenses go by their date.
+fn split_entry(
+    entry: &LogEntry,
+    at: chrono::DateTime<chrono::Local>,
+    id: u64,
+) -> Result<(LogEntry, LogEntry), String> {
+    if at <= entry.start_time || at >= entry.end_time {
+        return Err(format!(
+            "The split time must be between {} and {}",
+            entry.start_time.format("%d-%m-%Y %H:%M:%S"),
+            entry.end_time.format("%d-%m-%Y %H:%M:%S")
+        ));
+    }
+    let mut first = entry.clone();
+    first.end_time = at;
+    first.tasks.retain(|task| task.created_at <= at);
+    first.expenses.retain(|expense| expense.date <= at);
+
+    let second = LogEntry {
+        id,
+        start_time: at,
+        end_time: entry.end_time,
+        tasks: entry
+            .tasks
+            .iter()
+            .filter(|task| task.created_at > at)
+            .cloned()
+            .collect(),
+        expenses: entry
+            .expenses
+            .iter()
+            .filter(|expense| expense.date > at)
+            .cloned()
+            .collect(),
+        ..Default::default()
+    };
+    Ok((first, second))
+}
+
+/// `split <session-id> --at <time>`: divides a logged session in two at a
+/// time given as `HH:MM` on the day the session started or as
+/// `YYYY-MM-DD HH:MM`.
+pub fn split(args: &mut Args) {
+    let at = args.value("--at");
+    let id = args.text();
+    let (Some(at), false) = (at, id.is_empty()) else {
+        error::print(
+            ErrorCode::MissingArgument,
+            "Usage: split <session-id> --at <HH:MM>",
+        );
+        return;
+    };
+
+    let mut entries = log::load();
+    let index = match find(&entries, &id) {
+        Ok(index) => index,
+        Err(e) => {
+            error::print(ErrorCode::InvalidArgument, &e);
+            return;
+        }
+    };
+    let Some(time) = cli::parse_time(&at, entries[index].start_time.date_naive()) else {
+        error::print(
+            ErrorCode::InvalidArgument,
+            &format!("Invalid time: {} (expected YYYY-MM-DD HH:MM or HH:MM)", at),
+        );
+        return;
+    };
+    let next_id = entries.iter().map(|entry| entry.id).max().unwrap_or(0) + 1;
+    let (first, second) = match split_entry(&entries[index], time, next_id) {
+        Ok(parts) => parts,
+        Err(e) => {
+            error::print(ErrorCode::InvalidArgument, &e);
+            return;
+        }
+    };
+
+    entries[index] = first.clone();
+    entries.insert(index + 1, second.clone());
+    if let Err(e) = log::save(&entries) {
+        error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+        return;
+    }
+    cli::info(&format!(
+        "Session {} split at {}",
+        first.id,
+        time.format("%H:%M")
+    ));
+    for part in [first, second] {
+        cli::info(&format!(
+            "  Session {}: {} - {} ({}, {} tasks)",
+            part.id,
+            part.start_time.format("%d-%m-%Y %H:%M:%S"),
+            part.end_time.format("%d-%m-%Y %H:%M:%S"),
+            format_duration(part.duration()),
+            part.tasks.len()
+        ));
+    }
+}
+
+/// Assigns every task and expense of `entry` to `project`, returning how
+/// many were changed.
+fn move_entry(entry: &mut LogEntry, project: &str) -> usize {
+    let mut moved = 0;
+    let projects = entry.tasks.iter_mut().map(|task| &mut task.project).chain(
+        entry
+            .expenses
+            .iter_mut()
+            .map(|expense| &mut expense.project),
+    );
+    for current in projects {
+        if current.as_deref() != Some(project) {
+            *current = Some(project.to_string());
+            moved += 1;
+        }
+    }
+    moved
+}
+
+/// `task comment <index> <text> --session <id>`: adds a comment to a task of
+/// a logged session, counting the tasks in the order they were added.
+pub fn comment_logged_task(id: &str, index: usize, comment: &str) {
+    let mut entries = log::load();
+    let entry = match find(&entries, id) {
+        Ok(entry) => entry,
+        Err(e) => {
+            error::print(ErrorCode::InvalidArgument, &e);
+            return;
+        }
+    };
+    let tasks = &mut entries[entry].tasks;
+    tasks.sort_by_key(|task| task.created_at);
+    let Some(task) = index.checked_sub(1).and_then(|i| tasks.get_mut(i)) else {
+        error::print(
+            ErrorCode::InvalidArgument,
+            &format!("Session {} has no task with index {}", id, index),
+        );
+        return;
+    };
+    task.comments.push(comment.to_string());
+    let name = task.name.clone();
+    if let Err(e) = log::save(&entries) {
+        error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+        return;
+    }
+    cli::info(&format!("Comment added to task '{}'", name));
+}
+
+/// `move <session-id> --to-project <name>`: assigns a logged session's tasks
+/// and expenses to another project.
+pub fn move_session(args: &mut Args) {
+    let project = args.value("--to-project");
+    let id = args.text();
+    let (Some(project), false) = (project, id.is_empty()) else {
+        error::print(
+            ErrorCode::MissingArgument,
+            "Usage: move <session-id> --to-project <name>",
+        );
+        return;
+    };
+
+    let mut entries = log::load();
+    let index = match find(&entries, &id) {
+        Ok(index) => index,
+        Err(e) => {
+            error::print(ErrorCode::InvalidArgument, &e);
+            return;
+        }
+    };
+    let moved = move_entry(&mut entries[index], &project);
+    if moved == 0 {
+        println!(
+            "Session {} is already in project {}",
+            entries[index].id, project
+        );
+        return;
+    }
+    if let Err(e) = log::save(&entries) {
+        error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+        return;
+    }
+    budget::refresh_alerts(&Config::current(), &entries);
+    cli::info(&format!(
+        "Moved {} tasks and expenses of session {} to project {}",
+        moved, entries[index].id, project
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Task;
+
+    #[test]
+    fn test_split_entry() {
+        use chrono::TimeZone;
+        let at = |hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 13, hour, 0, 0)
+                .unwrap()
+        };
+        let entry = LogEntry {
+            id: 4,
+            start_time: at(9),
+            end_time: at(17),
+            tasks: vec![
+                Task::new("Fix parser", at(11)),
+                Task::new("Review", at(13)),
+                Task::new("Invoice", at(16)),
+            ],
+            goal: Some("Ship the parser".to_string()),
+            ..Default::default()
+        };
+
+        let (first, second) = split_entry(&entry, at(13), 9).unwrap();
+        assert_eq!(
+            (first.id, first.start_time, first.end_time),
+            (4, at(9), at(13))
+        );
+        assert_eq!(first.tasks.len(), 2);
+        assert_eq!(
+            (second.id, second.start_time, second.end_time),
+            (9, at(13), at(17))
+        );
+        assert_eq!(second.tasks[0].name, "Invoice");
+        assert_eq!(second.goal, None);
+        assert_eq!(first.duration() + second.duration(), entry.duration());
+        assert!(split_entry(&entry, at(17), 9).is_err());
+
+        let mut entry = entry;
+        entry.tasks[0].project = Some("beta".to_string());
+        assert_eq!(move_entry(&mut entry, "beta"), 2);
+        assert!(entry
+            .tasks
+            .iter()
+            .all(|task| task.project.as_deref() == Some("beta")));
+        assert_eq!(find(&[entry], "#4"), Ok(0));
+    }
+}
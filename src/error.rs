@@ -0,0 +1,91 @@
+/// What went wrong, for wrappers reading errors printed with `--json`.
+#[derive(serde::Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    NoActiveSession,
+    SessionAlreadyStarted,
+    UnknownCommand,
+    MissingArgument,
+    /// An argument such as a date, amount or format could not be used.
+    InvalidArgument,
+    /// A file could not be read or written.
+    IoError,
+    CommandFailed,
+}
+
+impl ErrorCode {
+    /// The exit status of a command that failed with this error: 2 when it
+    /// was called wrong, 1 when it could not be done.
+    fn exit_status(self) -> i32 {
+        match self {
+            ErrorCode::UnknownCommand | ErrorCode::MissingArgument | ErrorCode::InvalidArgument => {
+                2
+            }
+            _ => 1,
+        }
+    }
+}
+
+/// Whether errors are printed as JSON, set with the `--json` option.
+static JSON: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+
+pub fn set_json(json: bool) {
+    JSON.set(json).ok();
+}
+
+pub fn json() -> bool {
+    JSON.get().copied().unwrap_or(false)
+}
+
+/// The exit status of the last error printed, or 0 when there was none.
+static STATUS: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+pub fn status() -> i32 {
+    STATUS.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Forgets the errors printed so far, before the next command of
+/// interactive mode.
+pub fn reset() {
+    STATUS.store(0, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// `message` as a JSON error object, without its "ERROR: " prefix.
+fn to_json(code: ErrorCode, message: &str) -> String {
+    serde_json::json!({
+        "error": {
+            "code": code,
+            "message": message.strip_prefix("ERROR: ").unwrap_or(message),
+        }
+    })
+    .to_string()
+}
+
+/// Prints an error message, or with `--json`, a JSON object such as
+/// `{"error":{"code":"NO_ACTIVE_SESSION","message":"No job session started"}}`,
+/// and makes jobclock exit with the status of `code`.
+pub fn print(code: ErrorCode, message: &str) {
+    STATUS.store(code.exit_status(), std::sync::atomic::Ordering::Relaxed);
+    if json() {
+        println!("{}", to_json(code, message));
+    } else {
+        println!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            to_json(ErrorCode::NoActiveSession, "No job session started"),
+            r#"{"error":{"code":"NO_ACTIVE_SESSION","message":"No job session started"}}"#
+        );
+        assert_eq!(
+            to_json(ErrorCode::IoError, "ERROR: Cannot write a.json: denied"),
+            r#"{"error":{"code":"IO_ERROR","message":"Cannot write a.json: denied"}}"#
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    config::Config,
+    currency::{self, Converter},
+    report::Day,
+};
+
+/// Money spent for work, logged alongside the time.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Expense {
+    pub date: chrono::DateTime<chrono::Local>,
+    pub amount: f64,
+    pub description: String,
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Currency of the amount, if not the home currency.
+    #[serde(default)]
+    pub currency: Option<String>,
+}
+
+/// Parses an amount such as `45`, `45.00` or `45,50`.
+pub fn parse_amount(amount: &str) -> Option<f64> {
+    let amount = amount.replace(',', ".").parse::<f64>().ok()?;
+    if amount.is_finite() && amount > 0.0 {
+        Some(amount)
+    } else {
+        None
+    }
+}
+
+/// Total expenses per project in the home currency, with expenses without a
+/// project last.
+pub fn project_totals(
+    expenses: &[Expense],
+    converter: &Converter,
+) -> Result<Vec<(Option<String>, f64)>, String> {
+    let mut totals: Vec<(Option<String>, f64)> = vec![];
+    for expense in expenses {
+        let amount = converter.to_home(expense.amount, expense.currency.as_deref())?;
+        match totals.iter_mut().find(|(name, _)| *name == expense.project) {
+            Some((_, total)) => *total += amount,
+            None => totals.push((expense.project.clone(), amount)),
+        }
+    }
+    totals.sort_by_key(|(name, _)| (name.is_none(), name.clone()));
+    Ok(totals)
+}
+
+/// The expenses logged on `days`, with their total in the home currency.
+pub fn render_expenses(days: &[Day], config: &Config) -> Result<(String, f64), String> {
+    let converter = Converter::new(config);
+    let expenses = days
+        .iter()
+        .flat_map(|day| &day.sessions)
+        .flat_map(|session| session.expenses.clone())
+        .collect::<Vec<Expense>>();
+    if expenses.is_empty() {
+        return Ok((String::new(), 0.0));
+    }
+    let totals = project_totals(&expenses, &converter)?;
+
+    let mut text = "Expenses:\n".to_string();
+    for expense in &expenses {
+        text.push_str(&format!(
+            "  {} - {}{} - {}\n",
+            expense.date.format("%d-%m-%Y"),
+            expense.description,
+            expense
+                .project
+                .as_ref()
+                .map(|project| format!(" [{}]", project))
+                .unwrap_or_default(),
+            currency::format(
+                expense.amount,
+                expense.currency.as_deref().or(converter.home.as_deref())
+            )
+        ));
+    }
+    if totals.len() > 1 || totals[0].0.is_some() {
+        for (project, total) in &totals {
+            text.push_str(&format!(
+                "  {}: {}\n",
+                project.as_deref().unwrap_or("No project"),
+                converter.format(*total)
+            ));
+        }
+    }
+    let total = totals.iter().map(|(_, total)| total).sum::<f64>();
+    text.push_str(&format!("Total expenses: {}\n", converter.format(total)));
+    Ok((text, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expenses() {
+        assert_eq!(parse_amount("45.00"), Some(45.0));
+        assert_eq!(parse_amount("12,50"), Some(12.5));
+        assert_eq!(parse_amount("-3"), None);
+        assert_eq!(parse_amount("train"), None);
+
+        let expense = |amount, project: Option<&str>, currency: Option<&str>| Expense {
+            date: chrono::Local::now(),
+            amount,
+            description: "ticket".to_string(),
+            project: project.map(|project| project.to_string()),
+            currency: currency.map(|currency| currency.to_string()),
+        };
+        let config = Config {
+            exchange_rates: std::collections::BTreeMap::from([("EUR".to_string(), 10.0)]),
+            ..Default::default()
+        };
+        let totals = project_totals(
+            &[
+                expense(10.0, None, None),
+                expense(4.5, Some("acme"), Some("EUR")),
+                expense(5.0, Some("acme"), None),
+            ],
+            &Converter::new(&config),
+        );
+        assert_eq!(
+            totals,
+            Ok(vec![(Some("acme".to_string()), 50.0), (None, 10.0)])
+        );
+    }
+}
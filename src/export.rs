@@ -0,0 +1,622 @@
+use crate::{
+    cli::Args,
+    config::Config,
+    error::{self, ErrorCode},
+    log,
+    log::LogEntry,
+    persistent_folder, report,
+    rounding::{self, RoundedTime},
+    sha256, storage, summary,
+};
+
+/// A logged session as exported. With rounding configured, the raw and
+/// rounded time of each project is included, so billed time can be audited.
+#[derive(serde::Serialize)]
+struct ExportedEntry {
+    #[serde(flatten)]
+    entry: LogEntry,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    rounding: Vec<RoundedTime>,
+    /// The billing references of the projects worked on in the session.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    billing: Vec<BillingReference>,
+}
+
+/// The purchase order number and cost center a project is billed against.
+#[derive(serde::Serialize, PartialEq, Debug)]
+struct BillingReference {
+    project: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    po_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost_center: Option<String>,
+}
+
+/// The billing reference configured for `project`, if it has any.
+fn billing_reference(config: &Config, project: Option<&str>) -> Option<BillingReference> {
+    let project = project?;
+    let settings = config.projects.get(project)?;
+    if settings.po_number.is_none() && settings.cost_center.is_none() {
+        return None;
+    }
+    Some(BillingReference {
+        project: project.to_string(),
+        po_number: settings.po_number.clone(),
+        cost_center: settings.cost_center.clone(),
+    })
+}
+
+/// The billing references of the projects of the tasks of `entry`.
+fn billing_references(config: &Config, entry: &LogEntry) -> Vec<BillingReference> {
+    let mut references: Vec<BillingReference> = vec![];
+    for task in &entry.tasks {
+        if let Some(reference) = billing_reference(config, task.project.as_deref()) {
+            if !references.contains(&reference) {
+                references.push(reference);
+            }
+        }
+    }
+    references
+}
+
+/// Secret mixed into pseudonyms, so they cannot be reversed by hashing
+/// likely client names. Created on first use and kept, so the same name gets
+/// the same pseudonym in every export.
+fn anonymize_key() -> String {
+    let mut path = persistent_folder();
+    path.push("anonymize.key");
+    if let Ok(key) = std::fs::read_to_string(&path) {
+        return key;
+    }
+    let seed = format!(
+        "{:?}|{}|{:p}",
+        std::time::SystemTime::now(),
+        std::process::id(),
+        &path
+    );
+    let key = sha256::hex_digest(seed.as_bytes());
+    storage::create_folder(&persistent_folder()).unwrap();
+    storage::write(&path, &key).unwrap();
+    key
+}
+
+/// Replaces names with stable pseudonyms such as `project-3fa2c1d0`.
+pub struct Anonymizer {
+    key: String,
+}
+
+impl Anonymizer {
+    pub fn new(key: String) -> Anonymizer {
+        Anonymizer { key }
+    }
+
+    pub fn pseudonym(&self, kind: &str, name: &str) -> String {
+        let digest = sha256::hex_digest(format!("{}|{}|{}", self.key, kind, name).as_bytes());
+        format!("{}-{}", kind, &digest[..8])
+    }
+
+    /// Replaces every name in `entry`, keeping its dates and durations.
+    pub fn entry(&self, entry: &LogEntry) -> LogEntry {
+        let project = |project: &Option<String>| {
+            project
+                .as_ref()
+                .map(|project| self.pseudonym("project", project))
+        };
+        let mut entry = entry.clone();
+        for task in &mut entry.tasks {
+            task.name = self.pseudonym("task", &task.name);
+            task.project = project(&task.project);
+            for comment in &mut task.comments {
+                *comment = self.pseudonym("comment", comment);
+            }
+            for tag in &mut task.tags {
+                *tag = self.pseudonym("tag", tag);
+            }
+        }
+        entry.goal = entry.goal.as_ref().map(|goal| self.pseudonym("goal", goal));
+        for timer in &mut entry.timers {
+            timer.name = self.pseudonym("timer", &timer.name);
+        }
+        for meeting in &mut entry.meetings {
+            meeting.name = self.pseudonym("meeting", &meeting.name);
+        }
+        for expense in &mut entry.expenses {
+            expense.description = self.pseudonym("expense", &expense.description);
+            expense.project = project(&expense.project);
+        }
+        for attachment in &mut entry.attachments {
+            attachment.reference = self.pseudonym("attachment", &attachment.reference);
+        }
+        for value in entry.metadata.values_mut() {
+            *value = self.pseudonym("meta", value);
+        }
+        if let Some(approval) = &mut entry.approval {
+            approval.by = None;
+        }
+        entry
+    }
+}
+
+/// The ledger account of `project`: `client:project` when the project has a
+/// client configured.
+fn account(config: &Config, project: Option<&str>) -> String {
+    let Some(project) = project else {
+        return "unassigned".to_string();
+    };
+    match config
+        .projects
+        .get(project)
+        .and_then(|settings| settings.client.as_deref())
+    {
+        Some(client) => format!("{}:{}", client, project),
+        None => project.to_string(),
+    }
+}
+
+/// A stretch of a session: the project and name of the task worked on, or
+/// `None` for time not covered by a task, with its start and end.
+type Span = (
+    Option<String>,
+    Option<String>,
+    chrono::DateTime<chrono::Local>,
+    chrono::DateTime<chrono::Local>,
+);
+
+/// The stretches of `entry` in order, with the time of each task as
+/// `summary::task_durations` counts it. The time before a timed task that no
+/// task covers, and the time after the last task, are stretches of their own.
+fn spans(entry: &LogEntry) -> Vec<Span> {
+    let mut spans = vec![];
+    let mut start = entry.start_time;
+    for (task, from, to) in summary::task_spans(entry.start_time, entry.end_time, &entry.tasks) {
+        if from > start {
+            spans.push((None, None, start, from));
+        }
+        spans.push((task.project, Some(task.name), from, to));
+        start = start.max(to);
+    }
+    spans.push((None, None, start, entry.end_time));
+    spans
+}
+
+/// Writes `entries` as timeclock entries for hledger and ledger-cli: an `i`
+/// line clocking in to an account and an `o` line clocking out for each run
+/// of tasks on the same project, tagged with the project's billing
+/// reference. Time no task covers is clocked to `unassigned`.
+fn timeclock(config: &Config, entries: &[LogEntry]) -> String {
+    let mut lines = vec![];
+    for entry in entries {
+        let mut runs: Vec<(Option<String>, Vec<String>, chrono::DateTime<chrono::Local>)> = vec![];
+        for (project, name, _, end) in spans(entry) {
+            match (runs.last_mut(), name) {
+                (Some((run_project, names, run_end)), Some(name))
+                    if *run_project == project && !names.is_empty() =>
+                {
+                    names.push(name);
+                    *run_end = end;
+                }
+                (_, name) => runs.push((project, name.into_iter().collect(), end)),
+            }
+        }
+
+        let mut start = entry.start_time;
+        for (project, names, end) in runs {
+            if end <= start {
+                continue;
+            }
+            let mut line = format!(
+                "i {} {}  {}",
+                start.format("%Y/%m/%d %H:%M:%S"),
+                account(config, project.as_deref()),
+                names.join("; ")
+            )
+            .trim_end()
+            .to_string();
+            if let Some(reference) = billing_reference(config, project.as_deref()) {
+                line.push_str(&format!("  ; {}", ledger_tags(&reference)));
+            }
+            lines.push(line);
+            lines.push(format!("o {}", end.format("%Y/%m/%d %H:%M:%S")));
+            start = end;
+        }
+    }
+    lines.join("\n")
+}
+
+/// The billing reference as hledger tags, such as `po:4500012345`.
+fn ledger_tags(reference: &BillingReference) -> String {
+    let mut tags = vec![];
+    if let Some(po_number) = &reference.po_number {
+        tags.push(format!("po:{}", po_number));
+    }
+    if let Some(cost_center) = &reference.cost_center {
+        tags.push(format!("cost_center:{}", cost_center));
+    }
+    tags.join(", ")
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Writes `entries` as CSV with a line per task, giving the time it covers
+/// and the client and billing reference of its project. Time no task covers
+/// is a line of its own, "Unlogged work".
+fn csv(config: &Config, entries: &[LogEntry]) -> String {
+    let mut lines =
+        vec!["Date,Start,End,Hours,Project,Client,Task,PO number,Cost center".to_string()];
+    for entry in entries {
+        for (project, name, start, end) in spans(entry) {
+            if end <= start {
+                continue;
+            }
+            let settings = project
+                .as_ref()
+                .and_then(|project| config.projects.get(project))
+                .cloned()
+                .unwrap_or_default();
+            let fields = [
+                start.format("%Y-%m-%d").to_string(),
+                start.format("%H:%M:%S").to_string(),
+                end.format("%H:%M:%S").to_string(),
+                format!("{:.2}", (end - start).num_seconds() as f64 / 3600.0),
+                project.unwrap_or_default(),
+                settings.client.unwrap_or_default(),
+                name.unwrap_or("Unlogged work".to_string()),
+                settings.po_number.unwrap_or_default(),
+                settings.cost_center.unwrap_or_default(),
+            ];
+            lines.push(
+                fields
+                    .iter()
+                    .map(|field| csv_field(field))
+                    .collect::<Vec<String>>()
+                    .join(","),
+            );
+        }
+    }
+    lines.join("\n")
+}
+
+/// Writes `entries` as an org-mode document with a heading per project, a
+/// subheading per task and a `CLOCK` line for every time it was worked on,
+/// under a clocktable block that org fills in. Time no task covers is
+/// clocked to "Unlogged work".
+fn org(config: &Config, entries: &[LogEntry]) -> String {
+    type Clocks = Vec<(String, Vec<String>)>;
+    let mut projects: Vec<(Option<String>, Clocks)> = vec![];
+    let stamp = |time: chrono::DateTime<chrono::Local>| time.format("[%Y-%m-%d %a %H:%M]");
+
+    for entry in entries {
+        for (project, name, start, end) in spans(entry) {
+            if end <= start {
+                continue;
+            }
+            let name = name.unwrap_or("Unlogged work".to_string());
+            let minutes = (end - start).num_minutes();
+            let clock = format!(
+                "CLOCK: {}--{} => {:>2}:{:02}",
+                stamp(start),
+                stamp(end),
+                minutes / 60,
+                minutes % 60
+            );
+            let tasks = match projects.iter_mut().find(|(name, _)| *name == project) {
+                Some((_, tasks)) => tasks,
+                None => {
+                    projects.push((project, vec![]));
+                    &mut projects.last_mut().unwrap().1
+                }
+            };
+            match tasks.iter_mut().find(|(task, _)| *task == name) {
+                Some((_, clocks)) => clocks.push(clock),
+                None => tasks.push((name, vec![clock])),
+            }
+        }
+    }
+    projects.sort_by_key(|(project, _)| (project.is_none(), project.clone()));
+
+    let mut lines = vec![
+        "#+TITLE: jobclock".to_string(),
+        "#+BEGIN: clocktable :maxlevel 2 :scope file".to_string(),
+        "#+END:".to_string(),
+    ];
+    for (project, tasks) in projects {
+        lines.push(format!("* {}", project.as_deref().unwrap_or("No project")));
+        if let Some(reference) = billing_reference(config, project.as_deref()) {
+            lines.push(":PROPERTIES:".to_string());
+            if let Some(po_number) = reference.po_number {
+                lines.push(format!(":PO_NUMBER: {}", po_number));
+            }
+            if let Some(cost_center) = reference.cost_center {
+                lines.push(format!(":COST_CENTER: {}", cost_center));
+            }
+            lines.push(":END:".to_string());
+        }
+        for (task, clocks) in tasks {
+            lines.push(format!("** {}", task));
+            lines.push(":LOGBOOK:".to_string());
+            lines.extend(clocks);
+            lines.push(":END:".to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+/// `export [json|csv|timeclock|org] [--from YYYY-MM-DD] [--to YYYY-MM-DD]
+/// [--anonymize] [--output <file>]`: writes the logged sessions as JSON,
+/// CSV, timeclock entries or an org-mode document, to standard output by
+/// default.
+pub fn export(args: &mut Args) {
+    let anonymize = args.flag("--anonymize");
+    let output = args.value("--output");
+    let mut dates = [None, None];
+    for (date, name) in dates.iter_mut().zip(["--from", "--to"]) {
+        if let Some(value) = args.value(name) {
+            match report::parse_date(&value) {
+                Some(value) => *date = Some(value),
+                None => {
+                    error::print(
+                        ErrorCode::InvalidArgument,
+                        &format!("Invalid date for {}: {} (expected YYYY-MM-DD)", name, value),
+                    );
+                    return;
+                }
+            }
+        }
+    }
+    let [from, to] = dates;
+    let format = args.shift().unwrap_or("json".to_string());
+    if !["json", "csv", "timeclock", "org"].contains(&format.as_str()) {
+        error::print(
+            ErrorCode::InvalidArgument,
+            &format!(
+                "Unknown export format: {} (expected json, csv, timeclock or org)",
+                format
+            ),
+        );
+        return;
+    }
+
+    let mut entries = log::load_range(from, to)
+        .into_iter()
+        .filter(|entry| from.is_none_or(|from| entry.start_time.date_naive() >= from))
+        .filter(|entry| to.is_none_or(|to| entry.start_time.date_naive() <= to))
+        .collect::<Vec<LogEntry>>();
+    if anonymize {
+        let anonymizer = Anonymizer::new(anonymize_key());
+        entries = entries
+            .iter()
+            .map(|entry| anonymizer.entry(entry))
+            .collect();
+    }
+
+    let config = Config::current();
+    if format != "json" {
+        let data = match format.as_str() {
+            "csv" => csv(&config, &entries),
+            "timeclock" => timeclock(&config, &entries),
+            _ => org(&config, &entries),
+        };
+        match output {
+            Some(file) => match std::fs::write(&file, data + "\n") {
+                Ok(()) => println!("{} sessions exported to {}", entries.len(), file),
+                Err(e) => error::print(
+                    ErrorCode::IoError,
+                    &format!("ERROR: Cannot write {}: {}", file, e),
+                ),
+            },
+            None => println!("{}", data),
+        }
+        return;
+    }
+    let exported = entries
+        .into_iter()
+        .map(|entry| ExportedEntry {
+            rounding: config
+                .rounding
+                .as_ref()
+                .map(|rounding| rounding::entry(rounding, &entry))
+                .unwrap_or_default(),
+            billing: billing_references(&config, &entry),
+            entry,
+        })
+        .collect::<Vec<ExportedEntry>>();
+
+    let data = serde_json::to_string_pretty(&exported).unwrap();
+    match output {
+        Some(file) => match std::fs::write(&file, data) {
+            Ok(()) => println!("{} sessions exported to {}", exported.len(), file),
+            Err(e) => error::print(
+                ErrorCode::IoError,
+                &format!("ERROR: Cannot write {}: {}", file, e),
+            ),
+        },
+        None => println!("{}", data),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Task;
+
+    #[test]
+    fn test_anonymize() {
+        let anonymizer = Anonymizer::new("key".to_string());
+        let mut task = Task::new("Fix invoice export", chrono::Local::now());
+        task.project = Some("acme".to_string());
+        task.tags = vec!["client".to_string()];
+        let entry = LogEntry {
+            tasks: vec![task.clone(), task],
+            goal: Some("Ship acme release".to_string()),
+            ..Default::default()
+        };
+
+        let anonymized = anonymizer.entry(&entry);
+        assert_eq!(anonymized.duration(), entry.duration());
+        let task = &anonymized.tasks[0];
+        assert!(task.name.starts_with("task-"));
+        assert_eq!(task.name, anonymized.tasks[1].name);
+        assert_eq!(
+            task.project.as_deref(),
+            Some(anonymizer.pseudonym("project", "acme").as_str())
+        );
+        assert!(!serde_json::to_string(&anonymized).unwrap().contains("acme"));
+        assert_ne!(
+            Anonymizer::new("other".to_string()).pseudonym("project", "acme"),
+            anonymizer.pseudonym("project", "acme")
+        );
+    }
+
+    #[test]
+    fn test_timeclock() {
+        use chrono::TimeZone;
+        let at = |hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 13, hour, minute, 0)
+                .unwrap()
+        };
+        let task = |name, hour, minute, project: &str| {
+            let mut task = Task::new(name, at(hour, minute));
+            task.project = Some(project.to_string());
+            task
+        };
+        let entry = LogEntry {
+            start_time: at(9, 0),
+            end_time: at(12, 0),
+            tasks: vec![
+                task("Fix parser, again", 10, 0, "jobclock"),
+                task("Review", 10, 30, "jobclock"),
+                task("Invoice", 11, 0, "billing"),
+            ],
+            ..Default::default()
+        };
+        let config = Config {
+            projects: std::collections::BTreeMap::from([(
+                "jobclock".to_string(),
+                crate::config::ProjectSettings {
+                    client: Some("acme".to_string()),
+                    po_number: Some("4500012345".to_string()),
+                    cost_center: Some("CC-7".to_string()),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        assert_eq!(
+            csv(&config, std::slice::from_ref(&entry)),
+            "Date,Start,End,Hours,Project,Client,Task,PO number,Cost center\n\
+             2024-03-13,09:00:00,10:00:00,1.00,jobclock,acme,\"Fix parser, again\",4500012345,CC-7\n\
+             2024-03-13,10:00:00,10:30:00,0.50,jobclock,acme,Review,4500012345,CC-7\n\
+             2024-03-13,10:30:00,11:00:00,0.50,billing,,Invoice,,\n\
+             2024-03-13,11:00:00,12:00:00,1.00,,,Unlogged work,,"
+        );
+        assert_eq!(
+            billing_references(&config, &entry),
+            vec![BillingReference {
+                project: "jobclock".to_string(),
+                po_number: Some("4500012345".to_string()),
+                cost_center: Some("CC-7".to_string()),
+            }]
+        );
+        assert_eq!(
+            timeclock(&config, &[entry]),
+            "i 2024/03/13 09:00:00 acme:jobclock  Fix parser, again; Review  ; po:4500012345, cost_center:CC-7\n\
+             o 2024/03/13 10:30:00\n\
+             i 2024/03/13 10:30:00 billing  Invoice\n\
+             o 2024/03/13 11:00:00\n\
+             i 2024/03/13 11:00:00 unassigned\n\
+             o 2024/03/13 12:00:00"
+        );
+    }
+
+    #[test]
+    fn test_timed_task() {
+        use chrono::TimeZone;
+        let at = |hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 13, hour, minute, 0)
+                .unwrap()
+        };
+        let mut deploy = Task::new("Deploy", at(10, 0));
+        deploy.started_at = Some(at(10, 0));
+        deploy.ended_at = Some(at(10, 45));
+        let entry = LogEntry {
+            start_time: at(9, 0),
+            end_time: at(12, 0),
+            tasks: vec![
+                Task::new("Plan", at(9, 30)),
+                deploy,
+                Task::new("Review", at(11, 30)),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            csv(&Config::default(), std::slice::from_ref(&entry)),
+            "Date,Start,End,Hours,Project,Client,Task,PO number,Cost center\n\
+             2024-03-13,09:00:00,09:30:00,0.50,,,Plan,,\n\
+             2024-03-13,09:30:00,10:00:00,0.50,,,Unlogged work,,\n\
+             2024-03-13,10:00:00,10:45:00,0.75,,,Deploy,,\n\
+             2024-03-13,10:45:00,11:30:00,0.75,,,Review,,\n\
+             2024-03-13,11:30:00,12:00:00,0.50,,,Unlogged work,,"
+        );
+        assert_eq!(
+            timeclock(&Config::default(), &[entry]),
+            "i 2024/03/13 09:00:00 unassigned  Plan\n\
+             o 2024/03/13 09:30:00\n\
+             i 2024/03/13 09:30:00 unassigned\n\
+             o 2024/03/13 10:00:00\n\
+             i 2024/03/13 10:00:00 unassigned  Deploy; Review\n\
+             o 2024/03/13 11:30:00\n\
+             i 2024/03/13 11:30:00 unassigned\n\
+             o 2024/03/13 12:00:00"
+        );
+    }
+
+    #[test]
+    fn test_org() {
+        use chrono::TimeZone;
+        let at = |day, hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, day, hour, minute, 0)
+                .unwrap()
+        };
+        let entry = |day, project: Option<&str>| {
+            let mut task = Task::new("Fix parser", at(day, 10, 30));
+            task.project = project.map(str::to_string);
+            LogEntry {
+                start_time: at(day, 9, 0),
+                end_time: at(day, 11, 0),
+                tasks: vec![task],
+                ..Default::default()
+            }
+        };
+        assert_eq!(
+            org(
+                &Config::default(),
+                &[entry(13, Some("jobclock")), entry(14, Some("jobclock"))]
+            ),
+            "#+TITLE: jobclock\n\
+             #+BEGIN: clocktable :maxlevel 2 :scope file\n\
+             #+END:\n\
+             * jobclock\n\
+             ** Fix parser\n\
+             :LOGBOOK:\n\
+             CLOCK: [2024-03-13 Wed 09:00]--[2024-03-13 Wed 10:30] =>  1:30\n\
+             CLOCK: [2024-03-14 Thu 09:00]--[2024-03-14 Thu 10:30] =>  1:30\n\
+             :END:\n\
+             * No project\n\
+             ** Unlogged work\n\
+             :LOGBOOK:\n\
+             CLOCK: [2024-03-13 Wed 10:30]--[2024-03-13 Wed 11:00] =>  0:30\n\
+             CLOCK: [2024-03-14 Thu 10:30]--[2024-03-14 Thu 11:00] =>  0:30\n\
+             :END:"
+        );
+    }
+}
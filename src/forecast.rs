@@ -0,0 +1,192 @@
+use chrono::Datelike;
+
+use crate::{
+    cli::Args,
+    config::Config,
+    error::{self, ErrorCode},
+    log, recurring,
+    report::{self, Day},
+};
+
+/// Days looked back on to measure the velocity, unless `--days` is given.
+const DEFAULT_DAYS: u64 = 28;
+/// Working days looked ahead before giving up on a forecast.
+const MAX_WORKING_DAYS: u64 = 5 * 365;
+
+/// When the remaining budget of a project is expected to be used up.
+#[derive(PartialEq, Debug)]
+struct Forecast {
+    used: f64,
+    remaining: f64,
+    /// Hours on the project per working day in the recent days.
+    velocity: f64,
+    /// The last working day needed and how many working days that is.
+    finish: Option<(chrono::NaiveDate, u64)>,
+}
+
+fn project_hours(days: &[Day], project: &str) -> f64 {
+    report::project_totals(days)
+        .iter()
+        .find(|(name, _)| name.as_deref() == Some(project))
+        .map_or(0.0, |(_, duration)| duration.num_seconds() as f64 / 3600.0)
+}
+
+/// Forecasts when `budget_hours` on `project` are used up, working as many
+/// hours on it per working day after `today` as in the `window` days up to
+/// and including `today`. Working days are those `working_days` runs on,
+/// like the days of a recurring task.
+fn estimate(
+    days: &[Day],
+    project: &str,
+    budget_hours: f64,
+    window: u64,
+    working_days: &str,
+    today: chrono::NaiveDate,
+) -> Forecast {
+    let used = project_hours(days, project);
+    let remaining = (budget_hours - used).max(0.0);
+    let first = today - chrono::Days::new(window.saturating_sub(1));
+    // `days` are in order, so the recent ones are next to each other.
+    let recent = &days[days.partition_point(|day| day.date < first)
+        ..days.partition_point(|day| day.date <= today)];
+    let recent_working_days = first
+        .iter_days()
+        .take_while(|date| *date <= today)
+        .filter(|date| recurring::runs_on(working_days, date.weekday()))
+        .count();
+    let velocity = if recent_working_days == 0 {
+        0.0
+    } else {
+        project_hours(recent, project) / recent_working_days as f64
+    };
+
+    let mut finish = None;
+    if remaining > 0.0 && velocity > 0.0 {
+        let needed = (remaining / velocity).ceil() as u64;
+        if needed <= MAX_WORKING_DAYS {
+            finish = today
+                .succ_opt()
+                .unwrap()
+                .iter_days()
+                .filter(|date| recurring::runs_on(working_days, date.weekday()))
+                .nth(needed as usize - 1)
+                .map(|date| (date, needed));
+        }
+    }
+    Forecast {
+        used,
+        remaining,
+        velocity,
+        finish,
+    }
+}
+
+/// `forecast --project <name> [--days <n>]`: estimates when the hour budget
+/// of a project is used up, from the hours worked on it per working day in
+/// the last 28 days, or the given number of days.
+pub fn forecast(args: &mut Args) {
+    let Some(project) = args.value("--project") else {
+        error::print(
+            ErrorCode::MissingArgument,
+            "Usage: forecast --project <name> [--days <n>]",
+        );
+        return;
+    };
+    let window = match args.value("--days") {
+        Some(days) => match days.parse::<u64>() {
+            Ok(days) if days > 0 => days,
+            _ => {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("Invalid number of days: {}", days),
+                );
+                return;
+            }
+        },
+        None => DEFAULT_DAYS,
+    };
+    let config = Config::current();
+    let Some(budget_hours) = config
+        .projects
+        .get(&project)
+        .and_then(|settings| settings.budget_hours)
+    else {
+        error::print(
+            ErrorCode::InvalidArgument,
+            &format!("Project {} has no budget_hours set", project),
+        );
+        return;
+    };
+    let working_days = config
+        .timebank
+        .map_or("weekdays".to_string(), |timebank| timebank.days);
+
+    let days = report::days(&log::load());
+    let today = chrono::Local::now().date_naive();
+    let forecast = estimate(&days, &project, budget_hours, window, &working_days, today);
+    println!(
+        "{}: {:.1}h of {:.1}h used, {:.1}h left",
+        project, forecast.used, budget_hours, forecast.remaining
+    );
+    println!(
+        "Velocity: {:.1}h per working day over the last {} days",
+        forecast.velocity, window
+    );
+    match forecast.finish {
+        _ if forecast.remaining == 0.0 => println!("The budget is used up"),
+        Some((date, needed)) => println!(
+            "Forecast: used up on {} ({} working days)",
+            date.format("%a %d-%m-%Y"),
+            needed
+        ),
+        None => println!("Forecast: none, no recent work on {}", project),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{log::LogEntry, Task};
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_forecast() {
+        let at = |day, hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, day, hour, 0, 0)
+                .unwrap()
+        };
+        let entry = |day, hours: u32| {
+            let mut task = Task::new("Build", at(day, 8 + hours));
+            task.project = Some("acme".to_string());
+            LogEntry {
+                start_time: at(day, 8),
+                end_time: at(day, 8 + hours),
+                tasks: vec![task],
+                ..Default::default()
+            }
+        };
+        // 20 hours in total, 12 of them in the five days up to Friday 7 June.
+        let days = report::days(&[entry(1, 8), entry(3, 6), entry(5, 6)]);
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 6, 7).unwrap();
+
+        let forecast = estimate(&days, "acme", 50.0, 5, "weekdays", today);
+        assert_eq!(forecast.used, 20.0);
+        assert_eq!(forecast.remaining, 30.0);
+        assert_eq!(forecast.velocity, 2.4);
+        // 13 working days from Monday 10 June.
+        assert_eq!(
+            forecast.finish,
+            Some((chrono::NaiveDate::from_ymd_opt(2024, 6, 26).unwrap(), 13))
+        );
+
+        assert_eq!(
+            estimate(&days, "acme", 10.0, 5, "weekdays", today).finish,
+            None
+        );
+        assert_eq!(
+            estimate(&days, "globex", 10.0, 5, "weekdays", today).velocity,
+            0.0
+        );
+    }
+}
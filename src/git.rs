@@ -0,0 +1,464 @@
+use std::{process::Command, str};
+
+use crate::{
+    cli,
+    config::{self, Config},
+    error::{self, ErrorCode},
+    log, process, rules, Task,
+};
+
+pub struct Commit {
+    pub hash: String,
+    pub date: chrono::DateTime<chrono::Local>,
+    pub title: String,
+    pub author_email: String,
+    pub stats: Option<DiffStats>,
+}
+
+/// Size of a commit's diff, from `git log --numstat`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+pub struct DiffStats {
+    pub files_changed: u64,
+    pub insertions: u64,
+    pub deletions: u64,
+}
+
+impl DiffStats {
+    pub fn lines_touched(&self) -> u64 {
+        self.insertions + self.deletions
+    }
+}
+
+/// Parses `git log --numstat --format=%x1e%H` output into stats per commit
+/// hash. Binary files count as changed files without lines.
+fn parse_numstat(output: &str) -> std::collections::HashMap<String, DiffStats> {
+    let mut stats = std::collections::HashMap::new();
+    for record in output
+        .split('\x1e')
+        .filter(|record| !record.trim().is_empty())
+    {
+        let mut lines = record.lines();
+        let hash = lines.next().unwrap_or("").trim().to_string();
+        let mut commit = DiffStats::default();
+        for line in lines {
+            let mut columns = line.split('\t');
+            let (Some(insertions), Some(deletions), Some(_)) =
+                (columns.next(), columns.next(), columns.next())
+            else {
+                continue;
+            };
+            commit.files_changed += 1;
+            commit.insertions += insertions.parse().unwrap_or(0);
+            commit.deletions += deletions.parse().unwrap_or(0);
+        }
+        stats.insert(hash, commit);
+    }
+    stats
+}
+
+/// Diff stats for the commits `git log` selects with `options`.
+fn get_stats(
+    repository: &std::path::Path,
+    options: &[String],
+) -> std::collections::HashMap<String, DiffStats> {
+    match process::output(
+        git_command()
+            .arg("-C")
+            .arg(repository)
+            .args(["log", "--numstat", "--format=%x1e%H"])
+            .args(options),
+    ) {
+        Ok(output) if output.status.success() => {
+            parse_numstat(str::from_utf8(&output.stdout).unwrap_or(""))
+        }
+        _ => std::collections::HashMap::new(),
+    }
+}
+
+/// Commits extracted from one repository.
+pub struct RepositoryCommits {
+    pub repository: std::path::PathBuf,
+    /// The author the commits were limited to, if any.
+    pub author_email: Option<String>,
+    pub commits: Vec<Commit>,
+}
+
+/// The git executable: the configured `git_path`, or `git` looked up on the
+/// `PATH` (as `git.exe` on Windows).
+fn git_program() -> &'static std::ffi::OsStr {
+    static PROGRAM: std::sync::OnceLock<std::ffi::OsString> = std::sync::OnceLock::new();
+    PROGRAM.get_or_init(|| {
+        Config::current()
+            .git_path
+            .map(|path| config::expand_home(&path).into_os_string())
+            .unwrap_or_else(|| "git".into())
+    })
+}
+
+pub fn git_command() -> Command {
+    Command::new(git_program())
+}
+
+/// The email git attributes commits to in `repository`, from
+/// `git config user.email`.
+fn user_email(repository: &std::path::Path) -> Option<String> {
+    let output = process::output(
+        git_command()
+            .arg("-C")
+            .arg(repository)
+            .args(["config", "user.email"]),
+    )
+    .ok()?;
+    let email = str::from_utf8(&output.stdout).ok()?.trim().to_string();
+    if output.status.success() && !email.is_empty() {
+        Some(email)
+    } else {
+        None
+    }
+}
+
+/// The project name in a remote URL, such as `jobclock` for
+/// `git@github.com:johansolbakken/jobclock.git`.
+pub fn remote_project(url: &str) -> Option<String> {
+    let name = url.trim().trim_end_matches('/').rsplit(['/', ':']).next()?;
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    if name.is_empty() {
+        None
+    } else {
+        Some(name.to_string())
+    }
+}
+
+/// The project name of the `origin` remote of the repository containing
+/// `folder`.
+pub fn remote_name(folder: &std::path::Path) -> Option<String> {
+    let output = process::output(
+        git_command()
+            .arg("-C")
+            .arg(folder)
+            .args(["remote", "get-url", "origin"]),
+    )
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    remote_project(str::from_utf8(&output.stdout).ok()?)
+}
+
+/// Output of a git command run in `folder`, if it succeeded.
+fn git_output(folder: &std::path::Path, args: &[&str]) -> Option<String> {
+    let output = process::output(git_command().arg("-C").arg(folder).args(args)).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(str::from_utf8(&output.stdout).ok()?.trim().to_string())
+}
+
+/// Prefix of the tags that record the branch a task was added on.
+pub const BRANCH_TAG_PREFIX: &str = "branch:";
+
+/// The branch checked out in the repository containing `folder`, or `None`
+/// outside a repository and with a detached `HEAD`.
+pub fn current_branch(folder: &std::path::Path) -> Option<String> {
+    git_output(folder, &["symbolic-ref", "--quiet", "--short", "HEAD"])
+        .filter(|branch| !branch.is_empty())
+}
+
+/// The hooks folder of the repository containing `folder`.
+pub fn hooks_folder(folder: &std::path::Path) -> Option<std::path::PathBuf> {
+    let hooks = git_output(folder, &["rev-parse", "--git-path", "hooks"])?;
+    Some(folder.join(hooks))
+}
+
+/// The reflog of `HEAD` in the repository containing `folder`, which git
+/// appends a line to for every commit.
+#[cfg(feature = "daemon")]
+pub fn reflog_file(folder: &std::path::Path) -> Option<std::path::PathBuf> {
+    let reflog = git_output(folder, &["rev-parse", "--git-path", "logs/HEAD"])?;
+    Some(folder.join(reflog))
+}
+
+/// The title of the latest commit in the repository containing `folder`.
+pub fn last_commit_title(folder: &std::path::Path) -> Option<String> {
+    git_output(folder, &["log", "-1", "--format=%s"]).filter(|title| !title.is_empty())
+}
+
+/// Format given to `git log`: one record per commit, starting with a record
+/// separator, with the fields separated by unit separators. The author date
+/// is in strict ISO 8601, so parsing does not depend on the locale or on the
+/// user's `log.date` setting.
+const LOG_FORMAT: &str = "--format=%x1e%H%x1f%aI%x1f%ae%x1f%s";
+
+/// Parses `git log` output in `LOG_FORMAT` into commits. Records that do not
+/// parse are skipped.
+fn parse_log(output: &str) -> Vec<Commit> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_end_matches('\n').split('\x1f');
+            let hash = fields.next()?.trim().to_string();
+            let date = chrono::DateTime::parse_from_rfc3339(fields.next()?.trim()).ok()?;
+            let author_email = fields.next()?.trim().to_string();
+            let title = fields.next()?.trim().to_string();
+            Some(Commit {
+                hash,
+                date: date.into(),
+                title,
+                author_email,
+                stats: None,
+            })
+        })
+        .collect()
+}
+
+/// Runs `git log` in `repository` with `options` (such as `--since`) and
+/// parses the commits.
+pub fn get_commits(repository: &std::path::Path, options: &[String]) -> Vec<Commit> {
+    match process::output(
+        git_command()
+            .arg("-C")
+            .arg(repository)
+            .args(["log", "--no-color", LOG_FORMAT])
+            .args(options),
+    ) {
+        Ok(output) => {
+            if output.status.success() {
+                parse_log(&String::from_utf8_lossy(&output.stdout))
+            } else {
+                eprintln!("There was an error!\n");
+
+                eprintln!(
+                    "Git command failed in {} with error: {}",
+                    repository.display(),
+                    str::from_utf8(&output.stderr).unwrap_or("Unknown error")
+                );
+                Vec::new()
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to execute git command: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// The repositories registered in the config, or the current directory when
+/// none are.
+pub fn repositories(config: &Config) -> Vec<std::path::PathBuf> {
+    if config.repositories.is_empty() {
+        return vec![std::path::PathBuf::from(".")];
+    }
+    config
+        .repositories
+        .iter()
+        .map(|repository| config::expand_home(repository))
+        .collect()
+}
+
+/// Runs `git log` with `options` in every repository in parallel. Unless
+/// `all_authors` is set, only commits by the repository's configured git user
+/// are kept. With `with_stats`, each commit's diff stats are read as well.
+pub fn commits_by_repository(
+    repositories: &[std::path::PathBuf],
+    options: &[String],
+    all_authors: bool,
+    with_stats: bool,
+) -> Vec<RepositoryCommits> {
+    std::thread::scope(|scope| {
+        let handles = repositories
+            .iter()
+            .map(|repository| {
+                scope.spawn(move || {
+                    let author_email = if all_authors {
+                        None
+                    } else {
+                        user_email(repository)
+                    };
+                    let mut commits = get_commits(repository, options)
+                        .into_iter()
+                        .filter(|commit| {
+                            author_email
+                                .as_ref()
+                                .is_none_or(|email| commit.author_email.eq_ignore_ascii_case(email))
+                        })
+                        .collect::<Vec<Commit>>();
+                    if with_stats {
+                        let stats = get_stats(repository, options);
+                        for commit in &mut commits {
+                            commit.stats = stats.get(&commit.hash).copied();
+                        }
+                    }
+                    RepositoryCommits {
+                        repository: repository.clone(),
+                        author_email,
+                        commits,
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// Prints how many commits were taken from each repository, and warns about
+/// repositories where commits could not be limited to the git user.
+pub fn print_repository_counts(repositories: &[RepositoryCommits], all_authors: bool) {
+    for repository in repositories {
+        if !all_authors && repository.author_email.is_none() {
+            cli::info(&format!(
+                "No git user.email configured in {}, extracting commits by all authors",
+                repository.repository.display()
+            ));
+        }
+    }
+    if repositories.len() > 1 {
+        for repository in repositories {
+            cli::info(&format!(
+                "  {}: {} commits",
+                repository.repository.display(),
+                repository.commits.len()
+            ));
+        }
+    }
+}
+
+/// Imports the commits in a `git log --since/--until` range into the log,
+/// as one entry per day spanning from the first to the last commit of that
+/// day. Works without an active session.
+pub fn extract_range(
+    since: Option<String>,
+    until: Option<String>,
+    all_authors: bool,
+    with_stats: bool,
+) {
+    let mut options = vec![];
+    if let Some(since) = since {
+        options.push(format!("--since={}", since));
+    }
+    if let Some(until) = until {
+        options.push(format!("--until={}", until));
+    }
+
+    let config = Config::current();
+    let repositories =
+        commits_by_repository(&repositories(&config), &options, all_authors, with_stats);
+    print_repository_counts(&repositories, all_authors);
+
+    // Commits imported before, by an overlapping range or while a session
+    // ran, are already tasks in the log.
+    let logged = log::load()
+        .into_iter()
+        .flat_map(|entry| entry.tasks)
+        .map(|task| (task.created_at, task.name))
+        .collect::<std::collections::BTreeSet<_>>();
+    let mut tasks = repositories
+        .into_iter()
+        .flat_map(|repository| repository.commits)
+        .filter(|commit| !logged.contains(&(commit.date, commit.title.clone())))
+        .map(|commit| {
+            let mut task = Task::new(&commit.title, commit.date);
+            task.stats = commit.stats;
+            rules::categorize(&config.rules, &mut task);
+            task
+        })
+        .collect::<Vec<Task>>();
+    tasks.sort_by_key(|task| task.created_at);
+
+    if tasks.is_empty() {
+        println!("No new git commits found in this range");
+        return;
+    }
+
+    let mut days: Vec<Vec<Task>> = vec![];
+    for task in tasks {
+        match days.last_mut() {
+            Some(day) if day[0].created_at.date_naive() == task.created_at.date_naive() => {
+                day.push(task)
+            }
+            _ => days.push(vec![task]),
+        }
+    }
+
+    for tasks in days {
+        let start_time = tasks[0].created_at;
+        let end_time = tasks[tasks.len() - 1].created_at;
+        let count = tasks.len();
+        let entry = log::LogEntry {
+            start_time,
+            end_time,
+            tasks,
+            ..Default::default()
+        };
+        match log::append(entry) {
+            Ok(id) => cli::info(&format!(
+                "Logged {} tasks from git commits as session {} ({} - {})",
+                count,
+                id,
+                start_time.format("%d-%m-%Y %H:%M:%S"),
+                end_time.format("%d-%m-%Y %H:%M:%S")
+            )),
+            Err(e) => {
+                error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_numstat() {
+        let output = "\x1eabc\n\n10\t2\tsrc/main.rs\n-\t-\tlogo.png\n\x1edef\n\n1\t0\tREADME.md\n";
+        let stats = parse_numstat(output);
+        assert_eq!(
+            stats["abc"],
+            DiffStats {
+                files_changed: 2,
+                insertions: 10,
+                deletions: 2
+            }
+        );
+        assert_eq!(stats["def"].lines_touched(), 1);
+    }
+
+    #[test]
+    fn test_remote_project() {
+        let project = |url| remote_project(url);
+        assert_eq!(
+            project("git@github.com:johansolbakken/jobclock.git").as_deref(),
+            Some("jobclock")
+        );
+        assert_eq!(
+            project("https://github.com/johansolbakken/jobclock/\n").as_deref(),
+            Some("jobclock")
+        );
+        assert_eq!(project(""), None);
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let output = "\x1eabc\x1f2024-03-13T09:30:00+01:00\x1fkari@example.com\x1fFix parser\n\
+                      \x1edef\x1fnot a date\x1fola@example.com\x1fBroken\n";
+        let commits = parse_log(output);
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].hash, "abc");
+        assert_eq!(commits[0].title, "Fix parser");
+        assert_eq!(commits[0].author_email, "kari@example.com");
+        assert_eq!(
+            commits[0].date,
+            chrono::DateTime::parse_from_rfc3339("2024-03-13T08:30:00Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_commits() {
+        let commits = get_commits(std::path::Path::new("."), &[]);
+        println!("Commits {:?}", commits.len());
+    }
+}
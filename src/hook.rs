@@ -0,0 +1,100 @@
+use crate::{
+    error::{self, ErrorCode},
+    git, profile,
+};
+
+/// First line after the shebang of hooks written by `install-hook`, so they
+/// can be told apart from hooks the user wrote.
+const MARKER: &str = "# Installed by jobclock install-hook";
+
+/// A post-commit hook running `<exe> [--profile <name>] _post-commit`. It
+/// never fails, so a problem with jobclock does not get in the way of
+/// committing.
+fn post_commit_hook(exe: &str, profile: Option<&str>) -> String {
+    let profile = profile
+        .map(|profile| format!(" --profile '{}'", profile))
+        .unwrap_or_default();
+    format!(
+        "#!/bin/sh\n{}\n'{}'{} _post-commit || true\n",
+        MARKER,
+        exe.replace('\\', "/").replace('\'', "'\\''"),
+        profile
+    )
+}
+
+/// Installs (or with `remove`, removes) the post-commit hook in the
+/// repository containing the current directory.
+pub fn install_hook(remove: bool) {
+    let folder = std::env::current_dir().unwrap();
+    let Some(hooks) = git::hooks_folder(&folder) else {
+        error::print(
+            ErrorCode::CommandFailed,
+            &format!("ERROR: Not in a git repository: {}", folder.display()),
+        );
+        return;
+    };
+    let file = hooks.join("post-commit");
+    let ours = std::fs::read_to_string(&file)
+        .map(|hook| hook.contains(MARKER))
+        .ok();
+
+    if remove {
+        if ours == Some(true) {
+            std::fs::remove_file(&file).unwrap();
+            println!("Removed post-commit hook {}", file.display());
+        } else {
+            println!("No jobclock post-commit hook in {}", hooks.display());
+        }
+        return;
+    }
+
+    if ours == Some(false) {
+        error::print(
+            ErrorCode::CommandFailed,
+            &format!(
+                "ERROR: A post-commit hook already exists: {}",
+                file.display()
+            ),
+        );
+        println!("Add this line to it instead:");
+        println!(
+            "{}",
+            post_commit_hook(&exe(), profile()).lines().last().unwrap()
+        );
+        return;
+    }
+    std::fs::create_dir_all(&hooks).unwrap();
+    std::fs::write(&file, post_commit_hook(&exe(), profile())).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    println!("Installed post-commit hook {}", file.display());
+}
+
+fn exe() -> String {
+    std::env::current_exe()
+        .unwrap()
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_post_commit_hook() {
+        assert_eq!(
+            post_commit_hook("C:\\Tools\\jobclock.exe", Some("work")),
+            format!(
+                "#!/bin/sh\n{}\n'C:/Tools/jobclock.exe' --profile 'work' _post-commit || true\n",
+                MARKER
+            )
+        );
+        assert!(
+            post_commit_hook("/opt/it's/jobclock", None).contains("'/opt/it'\\''s/jobclock' _post")
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{
+    color::ProjectColors,
+    format_duration,
+    report::{project_totals, Day},
+    summary,
+};
+
+const STYLE: &str = "body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; color: #222; max-width: 52em; margin: 2em auto; padding: 0 1em; }
+h1 { font-size: 1.6em; margin-bottom: 0.2em; }
+h2 { font-size: 1.2em; margin-top: 2em; border-bottom: 1px solid #ddd; }
+.period { color: #666; }
+.total { font-size: 1.1em; }
+.bars { display: grid; grid-template-columns: 9em 1fr 7em; gap: 0.4em; align-items: center; }
+.bar { background: #4e79a7; height: 1.1em; border-radius: 2px; }
+.projects { display: flex; gap: 2em; align-items: center; }
+.legend span { display: inline-block; width: 0.8em; height: 0.8em; margin-right: 0.4em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1em; }
+th, td { text-align: left; padding: 0.25em 0.5em; border-bottom: 1px solid #eee; }
+td.duration { text-align: right; white-space: nowrap; }
+";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn hours(duration: chrono::Duration) -> f64 {
+    duration.num_seconds() as f64 / 3600.0
+}
+
+/// An SVG pie chart with one slice per value, in its color.
+fn pie_chart(values: &[(f64, String)]) -> String {
+    let total: f64 = values.iter().map(|(value, _)| value).sum();
+    let mut svg = String::from("<svg width=\"160\" height=\"160\" viewBox=\"-1 -1 2 2\">");
+    if values.iter().filter(|(value, _)| *value > 0.0).count() == 1 {
+        let (_, color) = values.iter().find(|(value, _)| *value > 0.0).unwrap();
+        svg.push_str(&format!("<circle r=\"1\" fill=\"{}\"/>", color));
+    } else if total > 0.0 {
+        let mut angle: f64 = 0.0;
+        for (value, color) in values {
+            let sweep = value / total * std::f64::consts::TAU;
+            let point = |angle: f64| (angle.sin(), -angle.cos());
+            let (x1, y1) = point(angle);
+            let (x2, y2) = point(angle + sweep);
+            svg.push_str(&format!(
+                "<path d=\"M 0 0 L {:.4} {:.4} A 1 1 0 {} 1 {:.4} {:.4} Z\" fill=\"{}\"/>",
+                x1,
+                y1,
+                if sweep > std::f64::consts::PI { 1 } else { 0 },
+                x2,
+                y2,
+                color
+            ));
+            angle += sweep;
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Renders a self-contained HTML page with daily bars, a project pie chart
+/// and a table of tasks per day.
+pub fn render(
+    days: &[Day],
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+    colors: &ProjectColors,
+) -> String {
+    let total = days
+        .iter()
+        .fold(chrono::Duration::zero(), |total, day| total + day.worked());
+    let longest = days
+        .iter()
+        .map(|day| hours(day.worked()))
+        .fold(0.0, f64::max);
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!(
+        "<title>Work report {} - {}</title>\n<style>\n{}</style>\n</head>\n<body>\n",
+        from.format("%d-%m-%Y"),
+        to.format("%d-%m-%Y"),
+        STYLE
+    ));
+    html.push_str(&format!(
+        "<h1>Work report</h1>\n<p class=\"period\">{} - {}</p>\n",
+        from.format("%d-%m-%Y"),
+        to.format("%d-%m-%Y")
+    ));
+    html.push_str(&format!(
+        "<p class=\"total\">Total time: <strong>{}</strong> ({:.2} hours)</p>\n",
+        format_duration(total),
+        hours(total)
+    ));
+
+    html.push_str("<h2>Hours per day</h2>\n<div class=\"bars\">\n");
+    for day in days {
+        let width = if longest > 0.0 {
+            hours(day.worked()) / longest * 100.0
+        } else {
+            0.0
+        };
+        html.push_str(&format!(
+            "<div>{}</div><div><div class=\"bar\" style=\"width: {:.1}%\"></div></div><div>{:.2}h</div>\n",
+            day.date.format("%a %d-%m-%Y"),
+            width,
+            hours(day.worked())
+        ));
+    }
+    html.push_str("</div>\n");
+
+    let projects = project_totals(days);
+    if !projects.is_empty() {
+        html.push_str("<h2>Projects</h2>\n<div class=\"projects\">\n");
+        let values = projects
+            .iter()
+            .map(|(name, duration)| (hours(*duration), colors.hex(name.as_deref())))
+            .collect::<Vec<(f64, String)>>();
+        html.push_str(&pie_chart(&values));
+        html.push_str("\n<div class=\"legend\">\n");
+        for (name, duration) in &projects {
+            html.push_str(&format!(
+                "<div><span style=\"background: {}\"></span>{} - {}</div>\n",
+                colors.hex(name.as_deref()),
+                escape(name.as_deref().unwrap_or("No project")),
+                format_duration(*duration)
+            ));
+        }
+        html.push_str("</div>\n</div>\n");
+    }
+
+    html.push_str("<h2>Tasks</h2>\n");
+    for day in days {
+        html.push_str(&format!(
+            "<h3>{} - {}</h3>\n<table>\n<tr><th>Time</th><th>Task</th><th>Project</th><th>Duration</th></tr>\n",
+            day.date.format("%a %d-%m-%Y"),
+            format_duration(day.worked())
+        ));
+        for session in &day.sessions {
+            for (task, duration) in summary::task_durations(session.start_time, &session.tasks) {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"duration\">{}</td></tr>\n",
+                    task.created_at.format("%H:%M"),
+                    escape(&task.name),
+                    escape(task.project.as_deref().unwrap_or("")),
+                    format_duration(duration)
+                ));
+            }
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{log::LogEntry, report, Task};
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render() {
+        let at = |hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 12, hour, 0, 0)
+                .unwrap()
+        };
+        let task = |name, hour, project: Option<&str>| {
+            let mut task = Task::new(name, at(hour));
+            task.project = project.map(|project| project.to_string());
+            task
+        };
+        let days = report::days(&[LogEntry {
+            id: 1,
+            start_time: at(8),
+            end_time: at(12),
+            tasks: vec![
+                task("Fix <parser>", 10, Some("acme")),
+                task("Review", 11, None),
+            ],
+            ..Default::default()
+        }]);
+
+        let totals = project_totals(&days);
+        assert_eq!(totals[0].0.as_deref(), Some("acme"));
+        assert_eq!(totals[0].1, chrono::Duration::try_hours(2).unwrap());
+        assert_eq!(totals[1].0, None);
+
+        let date = days[0].date;
+        let html = render(&days, date, date, &ProjectColors::default());
+        assert!(html.contains("Fix &lt;parser&gt;"));
+        assert!(html.contains("acme - 2h 0m 0s"));
+        assert_eq!(html.matches("<path").count(), 2);
+    }
+}
@@ -0,0 +1,257 @@
+use std::io::Write;
+
+use crate::{config, persistent_folder, process, sha256, storage};
+
+/// A response kept by `get_cached`, sent again when the server says it has
+/// not changed.
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+struct CachedResponse {
+    url: String,
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    body: Option<String>,
+    /// Until when the server asked not to be sent requests.
+    #[serde(default)]
+    rate_limited_until: Option<chrono::DateTime<chrono::Local>>,
+}
+
+/// Quotes `value` for a curl config file.
+fn curl_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Sends a request with `curl`, returning the status code and the body of
+/// the response. The options are read from standard input, so tokens and
+/// API keys in `headers` do not show up in the process list.
+pub fn request(
+    method: &str,
+    url: &str,
+    headers: &[String],
+    body: Option<&str>,
+) -> Result<(u16, String), String> {
+    send(method, url, headers, body, false)
+}
+
+/// Runs curl for `request`, with the response headers in front of the body
+/// when `include` is set.
+fn send(
+    method: &str,
+    url: &str,
+    headers: &[String],
+    body: Option<&str>,
+    include: bool,
+) -> Result<(u16, String), String> {
+    let mut options = vec![
+        format!("url = {}", curl_quote(url)),
+        format!("request = {}", curl_quote(method)),
+        "write-out = \"\\n%{http_code}\"".to_string(),
+    ];
+    if include {
+        options.push("include".to_string());
+    }
+    for header in headers {
+        options.push(format!("header = {}", curl_quote(header)));
+    }
+    if let Some(body) = body {
+        options.push("header = \"Content-Type: application/json\"".to_string());
+        options.push(format!("data-binary = {}", curl_quote(body)));
+    }
+
+    let mut curl = process::spawn(
+        std::process::Command::new("curl")
+            .args(["--silent", "--show-error", "--config", "-"])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped()),
+    )
+    .map_err(|e| format!("Failed to execute curl: {}", e))?;
+    curl.stdin
+        .take()
+        .unwrap()
+        .write_all(options.join("\n").as_bytes())
+        .map_err(|e| e.to_string())?;
+    let output = curl.wait_with_output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    parse_output(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Splits the output of curl into the body and the status code that
+/// `write-out` puts on the last line.
+fn parse_output(output: &str) -> Result<(u16, String), String> {
+    let (body, status) = output.rsplit_once('\n').unwrap_or(("", output));
+    let status = status
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid response from curl: {}", output))?;
+    Ok((status, body.to_string()))
+}
+
+/// Splits a response with headers into its headers, with lowercase names,
+/// and its body. Interim `1xx` responses in front of it are skipped.
+fn split_headers(response: &str) -> (Vec<(String, String)>, String) {
+    let mut rest = response;
+    loop {
+        let (head, body) = rest
+            .split_once("\r\n\r\n")
+            .or_else(|| rest.split_once("\n\n"))
+            .unwrap_or((rest, ""));
+        if head.starts_with("HTTP/") && head.split(' ').nth(1).is_some_and(|s| s.starts_with('1')) {
+            rest = body;
+            continue;
+        }
+        let headers = head
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_lowercase(), value.trim().to_string()))
+            })
+            .collect();
+        return (headers, body.to_string());
+    }
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Until when a response asks not to be sent more requests: after
+/// `Retry-After` seconds, or when the quota resets once `X-RateLimit-Remaining`
+/// (GitHub) or `RateLimit-Remaining` (GitLab) reaches zero.
+fn rate_limited_until(
+    status: u16,
+    headers: &[(String, String)],
+    now: chrono::DateTime<chrono::Local>,
+) -> Option<chrono::DateTime<chrono::Local>> {
+    if status == 403 || status == 429 {
+        if let Some(seconds) = header(headers, "retry-after").and_then(|value| value.parse().ok()) {
+            return Some(now + chrono::Duration::try_seconds(seconds).unwrap_or_default());
+        }
+    }
+    let remaining = header(headers, "x-ratelimit-remaining")
+        .or_else(|| header(headers, "ratelimit-remaining"))?;
+    if remaining != "0" {
+        return None;
+    }
+    let reset = header(headers, "x-ratelimit-reset")
+        .or_else(|| header(headers, "ratelimit-reset"))
+        .and_then(|value| value.parse().ok())
+        .and_then(|seconds| chrono::DateTime::from_timestamp(seconds, 0))?;
+    Some(reset.with_timezone(&chrono::Local))
+}
+
+/// The folder responses are cached in: `XDG_CACHE_HOME`, the local
+/// application data folder on Windows, or `~/.cache`. Kept out of the data
+/// folder so the cache is not backed up or synced.
+fn cache_folder() -> std::path::PathBuf {
+    let dir = |name| std::env::var_os(name).filter(|dir: &std::ffi::OsString| !dir.is_empty());
+    let home = if let Some(dir) = dir("XDG_CACHE_HOME") {
+        Some(std::path::PathBuf::from(dir))
+    } else if let Some(dir) = dir("LOCALAPPDATA").filter(|_| cfg!(windows)) {
+        Some(std::path::PathBuf::from(dir))
+    } else {
+        config::home_dir().map(|home| home.join(".cache"))
+    };
+    match home {
+        Some(home) => home.join("jobclock").join("http"),
+        None => persistent_folder().join("http-cache"),
+    }
+}
+
+/// Fetches `url` with a GET request, keeping the response on disk. A cached
+/// response is only sent again by the server when it changed, which APIs
+/// such as GitHub's do not count against the rate limit. While the server
+/// asks not to be sent requests, the cached response is used as it is, or an
+/// error is returned when there is none.
+pub fn get_cached(url: &str, headers: &[String]) -> Result<String, String> {
+    let key = sha256::hex_digest(format!("{}\n{}", url, headers.join("\n")).as_bytes());
+    let file = cache_folder().join(format!("{}.json", key));
+    let mut cached = std::fs::read_to_string(&file)
+        .ok()
+        .and_then(|data| serde_json::from_str::<CachedResponse>(&data).ok())
+        .filter(|cached| cached.url == url)
+        .unwrap_or(CachedResponse {
+            url: url.to_string(),
+            ..Default::default()
+        });
+    let now = chrono::Local::now();
+    if let Some(until) = cached.rate_limited_until.filter(|until| *until > now) {
+        return cached.body.clone().ok_or(format!(
+            "Rate limited by the server until {}",
+            until.format("%H:%M:%S")
+        ));
+    }
+
+    let mut headers = headers.to_vec();
+    if let (Some(etag), Some(_)) = (&cached.etag, &cached.body) {
+        headers.push(format!("If-None-Match: {}", etag));
+    }
+    let (status, response) = send("GET", url, &headers, None, true)?;
+    let (response_headers, body) = split_headers(&response);
+    cached.rate_limited_until = rate_limited_until(status, &response_headers, now);
+    let result = match status {
+        304 => cached
+            .body
+            .clone()
+            .ok_or("Empty response from the server".to_string()),
+        200..=299 => {
+            cached.etag = header(&response_headers, "etag").map(str::to_string);
+            cached.body = Some(body);
+            Ok(cached.body.clone().unwrap())
+        }
+        _ => match (&cached.body, cached.rate_limited_until) {
+            (Some(body), Some(_)) => Ok(body.clone()),
+            _ => Err(format!("The server responded with status {}", status)),
+        },
+    };
+    storage::create_folder(&cache_folder())
+        .and_then(|_| storage::write(&file, serde_json::to_string(&cached).unwrap()))
+        .ok();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_output() {
+        assert_eq!(curl_quote(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+        assert_eq!(
+            parse_output("{\"sessions\":2}\n200"),
+            Ok((200, "{\"sessions\":2}".to_string()))
+        );
+        assert_eq!(parse_output("401"), Ok((401, String::new())));
+        assert!(parse_output("").is_err());
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let (headers, body) = split_headers(
+            "HTTP/1.1 100 Continue\r\n\r\n\
+             HTTP/2 403\r\nETag: \"abc\"\r\nX-RateLimit-Remaining: 0\r\n\
+             X-RateLimit-Reset: 1717405200\r\n\r\n{\"message\":\"rate limit\"}",
+        );
+        assert_eq!(header(&headers, "etag"), Some("\"abc\""));
+        assert_eq!(body, "{\"message\":\"rate limit\"}");
+
+        let now = chrono::Local::now();
+        assert_eq!(
+            rate_limited_until(403, &headers, now).map(|until| until.timestamp()),
+            Some(1717405200)
+        );
+        let retry = vec![("retry-after".to_string(), "60".to_string())];
+        assert_eq!(
+            rate_limited_until(429, &retry, now),
+            Some(now + chrono::Duration::try_seconds(60).unwrap())
+        );
+        let remaining = vec![("ratelimit-remaining".to_string(), "12".to_string())];
+        assert_eq!(rate_limited_until(200, &remaining, now), None);
+    }
+}
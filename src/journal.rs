@@ -0,0 +1,49 @@
+use crate::{log, Task};
+
+/// A change to the session written ahead to the journal, so it survives the
+/// process being killed before the session is saved.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    AddTask(Task),
+}
+
+/// Appends `operation` to the journal, which holds one operation per line
+/// and is emptied every time the session is saved.
+pub fn record(operation: &Operation) {
+    log::append_journal(&serde_json::to_string(operation).unwrap()).unwrap();
+}
+
+/// Parses journal lines. A line cut short by a crash while it was written
+/// is skipped.
+fn parse(data: &str) -> Vec<Operation> {
+    data.lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// The operations recorded since the session was last saved.
+pub fn pending() -> Vec<Operation> {
+    parse(&log::load_journal())
+}
+
+pub fn clear() {
+    log::clear_journal();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let task = Task::new("Fix parser", chrono::Local::now());
+        let line = serde_json::to_string(&Operation::AddTask(task)).unwrap();
+        let data = format!("{}\n{}\n{}", line, line, &line[..line.len() / 2]);
+
+        let operations = parse(&data);
+        assert_eq!(operations.len(), 2);
+        let Operation::AddTask(task) = &operations[0];
+        assert_eq!(task.name, "Fix parser");
+    }
+}
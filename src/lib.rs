@@ -0,0 +1,2230 @@
+mod activity;
+mod ai;
+mod apply;
+mod approval;
+mod attach;
+mod autostart;
+mod backup;
+mod budget;
+mod calendar;
+mod check;
+mod cli;
+mod clipboard;
+mod color;
+mod complete;
+mod compliance;
+mod config;
+mod currency;
+mod daytype;
+mod doctor;
+mod earnings;
+mod edit;
+mod error;
+mod expense;
+mod export;
+mod forecast;
+mod git;
+mod hook;
+mod html;
+mod http;
+mod journal;
+mod log;
+mod meeting;
+mod merge;
+mod meta;
+mod notify;
+mod process;
+mod project;
+mod queue;
+mod quick;
+mod recurring;
+mod regex;
+mod render;
+mod report;
+mod retro;
+mod rewrite;
+mod rounding;
+mod rules;
+mod search;
+#[cfg(feature = "server")]
+mod server;
+mod sha256;
+mod show;
+mod standup;
+mod stats;
+mod storage;
+mod summary;
+mod sync;
+mod table;
+mod tags;
+mod target;
+mod taskwarrior;
+mod team;
+mod timebank;
+mod timeline;
+mod timer;
+mod timesheet;
+mod update;
+#[cfg(feature = "daemon")]
+mod watch;
+mod week;
+
+use error::ErrorCode;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+struct Task {
+    name: String,
+    created_at: chrono::DateTime<chrono::Local>,
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default = "default_billable")]
+    billable: bool,
+    #[serde(default)]
+    state: TaskState,
+    #[serde(default)]
+    carried_over: bool,
+    #[serde(default)]
+    stats: Option<git::DiffStats>,
+    /// Added automatically from a recurring task.
+    #[serde(default)]
+    recurring: bool,
+    /// UUID of the Taskwarrior task this task was added from.
+    #[serde(default)]
+    taskwarrior_uuid: Option<String>,
+    /// Whether the time spent has been pushed to Taskwarrior.
+    #[serde(default)]
+    taskwarrior_annotated: bool,
+    /// When a task begun with `task start` was started. Such a task covers
+    /// the time from then until `ended_at`, while other tasks cover the time
+    /// since the task before them. Tasks saved before timed tasks existed
+    /// load without it and keep their inferred durations.
+    #[serde(default)]
+    started_at: Option<chrono::DateTime<chrono::Local>>,
+    /// When a timed task was stopped, or `None` while it is running.
+    #[serde(default)]
+    ended_at: Option<chrono::DateTime<chrono::Local>>,
+    /// Notes added afterwards with `task comment`.
+    #[serde(default)]
+    comments: Vec<String>,
+}
+
+fn default_billable() -> bool {
+    true
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum TaskState {
+    #[default]
+    Done,
+    InProgress,
+}
+
+impl Task {
+    fn new(name: &str, created_at: chrono::DateTime<chrono::Local>) -> Task {
+        Task {
+            name: name.to_string(),
+            created_at,
+            project: None,
+            tags: vec![],
+            billable: true,
+            state: TaskState::Done,
+            carried_over: false,
+            stats: None,
+            recurring: false,
+            taskwarrior_uuid: None,
+            taskwarrior_annotated: false,
+            started_at: None,
+            ended_at: None,
+            comments: vec![],
+        }
+    }
+
+    /// Project, tags and billable flag formatted for display after the name.
+    fn labels(&self) -> String {
+        let mut labels = String::new();
+        if let Some(project) = &self.project {
+            labels.push_str(&format!(" [{}]", project));
+        }
+        for tag in &self.tags {
+            labels.push_str(&format!(" #{}", tags::canonical(tag)));
+        }
+        if self.recurring {
+            labels.push_str(" (recurring)");
+        }
+        if !self.billable {
+            labels.push_str(" (non-billable)");
+        }
+        labels
+    }
+}
+
+fn usage() {
+    println!(
+        "Usage: {} <subcommand> [args]",
+        std::env::args().next().unwrap()
+    );
+    println!("Options: ");
+    println!("  --profile <name>      - Use a separate data and config profile");
+    println!("                          (or set JOBCLOCK_PROFILE)");
+    println!("  --json                - Print errors as JSON objects with an error code");
+    println!("  --quiet               - Leave out informational messages");
+    println!("  --yes                 - Confirm restoring, reopening and updating");
+    println!("Environment: ");
+    println!("  JOBCLOCK_DATA_DIR     - Folder to keep the log and session in");
+    println!("  JOBCLOCK_<SETTING>    - Override a setting of the config file, such as");
+    println!("                          JOBCLOCK_PROJECT or JOBCLOCK_WEEK_START");
+    println!("  JOBCLOCK_RATE         - Override rates.hourly");
+    println!("Subcommands: ");
+    println!("  begin [-m <goal>]     - Start a new job session, optionally with a goal");
+    println!("                          [--project <name>] to override the detected project");
+    println!("                          [--new] to not offer resuming the last session");
+    println!("  cancel [--force]      - Discard a job session begun by mistake, without");
+    println!("                          logging it");
+    println!("  end [--at <time>]     - End the current job session, now or at a past time");
+    println!("                          [--ai-summary] to add a summary written by an AI model");
+    println!("                          [--copy] to copy the summary to the clipboard");
+    println!("                          [--receipt] to print a receipt for 40-column printers");
+    println!("  quick <duration> <task> - Log a finished job session with one task, such as");
+    println!("                          quick 1h30m \"incident response\" [--project <name>]");
+    println!("                          [--ago <duration>] to end it that long ago");
+    println!("  task <name>           - Add a new task to the current job session");
+    println!("                          [--project <name>] to override the detected project");
+    println!("  task start <name>     - Start a task timed until 'task stop', stopping the");
+    println!("                          running one [--project <name>]");
+    println!("  task stop             - Stop the running timed task");
+    println!("  task done <index>     - Mark a task as done");
+    println!("  task undone <index>   - Mark a task as in progress");
+    println!("  task move <index> --to-project <name> - Move a task to another project");
+    println!("  task comment <index> <text> - Add a comment to a task");
+    println!("                          [--session <id>] for a task of a logged session");
+    println!("  status                - Show the current job session status");
+    println!("                          [--short] for the state and time on one line,");
+    println!("                          [--tasks-only] or [--time-only]");
+    println!("                          [--verbose] to show the sampled activity and comments");
+    println!("  summary               - Show the summary of the running job session");
+    println!("                          [--style bullets|concise|detailed] [--language <code>]");
+    println!("  timer start <name>    - Start a named timer within the job session");
+    println!("  timer stop <name>     - Stop a named timer, keeping the time so far");
+    println!("  timer list            - Show the timers of the job session");
+    println!("  meeting start <name>  - Start a meeting, counted apart from the tasks");
+    println!("  meeting end           - End the running meeting [list]");
+    println!("  tw pull               - List pending Taskwarrior tasks");
+    println!("  tw add <id>           - Add a Taskwarrior task to the job session");
+    println!("  tw push               - Annotate Taskwarrior tasks with the time logged");
+    println!("  interactive           - Run commands from a prompt, 'exit' to quit");
+    println!("  git [--all-authors]   - Extract tasks from your git commits");
+    println!("                          [--stats] to record lines changed per commit");
+    println!("                          [--since <date>] [--until <date>] to log a past range,");
+    println!("                          a session per day from its first to last commit");
+    println!("  expense add <amount> <description> - Log an expense");
+    println!("                          [--project <name>] [--currency <code>]");
+    println!("  attach <file|url>     - Link a file or URL to the job session, or to a");
+    println!("                          logged one with [--session <id|last>]");
+    println!("  meta set <key> <value> - Attach metadata such as a ticket ID to the job");
+    println!("                          session [unset <key>] [list]");
+    println!("  heartbeat <file>      - Record editor activity on a file [--project <name>]");
+    println!("  activity sample       - Record the category of the active window, for cron");
+    println!("  show <id|last|today>  - Show the timeline, tasks and earnings of logged");
+    println!("                          job sessions");
+    println!("  standup               - Write yesterday's tasks and today's goal for a standup");
+    println!("  eod                   - End the day: extract git commits, end the session,");
+    println!("                          copy a standup update and push the log");
+    println!("  retro --week [<number>] - Write a retrospective of the week as Markdown");
+    println!("                          [--year <year>]");
+    println!("                          [--blockers <text>] [--copy]");
+    println!("  timeline [YYYY-MM-DD] - Show the sessions, pauses and tasks of a day");
+    println!("  cal [YYYY-MM]         - Show hours per day of a month as a calendar");
+    println!("  search <query>        - Search logged job sessions for matching tasks");
+    println!("  stats                 - Show task switches, the longest stretch on one task");
+    println!("                          and the focus score per day this week");
+    println!("                          [--from YYYY-MM-DD] [--to YYYY-MM-DD]");
+    println!("                          [--by-hour] for time and tasks per hour of the day");
+    println!("  split <id> --at <HH:MM> - Divide a logged job session in two");
+    println!("  move <id> --to-project <name> - Move a logged job session to a project");
+    println!("  report                - Show time per day from the log");
+    println!("                          [--week [<number>] [--year <year>]]");
+    println!("                          [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--breaks]");
+    println!("                          [--verbose] to list tasks with lines touched and comments");
+    println!("                          [--no-merge] to list repeated tasks separately");
+    println!("                          [--html <file>] to write an HTML page with charts");
+    println!("                          [--check-budgets] to fail when a budget is used up");
+    println!("                          [--rounding-diff] to show time added by rounding");
+    println!("                          [--compare-previous] to compare with the period before");
+    println!("                          [--diff <period> <period>] to compare two periods, each");
+    println!("                          YYYY-MM-DD..YYYY-MM-DD, YYYY-MM-DD or YYYY-Www");
+    println!("                          [--copy] to copy the tasks and totals to the clipboard");
+    println!("                          [--include-archived] to include archived projects");
+    println!("                          [--borders] to draw table borders, or [--plain] for");
+    println!("                          tab separated tables");
+    println!("  export [csv|timeclock|org] - Write the logged sessions as JSON, CSV,");
+    println!("                          timeclock or org-mode");
+    println!("                          [--output <file>]");
+    println!("                          [--from YYYY-MM-DD] [--to YYYY-MM-DD]");
+    println!("                          [--anonymize] to replace names with pseudonyms");
+    println!("  goal set --project <name> <hours>/week - Set a weekly target for a project");
+    println!("  goal list             - List weekly project targets [clear --project <name>]");
+    println!("  check --max-week <duration> - Exit with 1 when more was worked this week");
+    println!("                          [--max-day <duration>] to check today as well");
+    println!("  day set <YYYY-MM-DD> <type> - Mark a day as a workday, weekend, holiday");
+    println!("                          or sick day [clear <YYYY-MM-DD>] [list]");
+    println!("  forecast --project <name> - Estimate when the hour budget of a project is");
+    println!("                          used up [--days <n>] of recent work to go by");
+    println!("  timebank              - Show the flexitime balance, or take time off with");
+    println!("                          take <hours> [YYYY-MM-DD] [note] [list]");
+    println!("  project archive <name> - Hide a finished project from completions, status");
+    println!("                          and reports [unarchive <name>] [list]");
+    println!("  submit --week [YYYY-MM-DD] - Write the week to a bundle for approval");
+    println!("                          [--sign] to sign it with GPG [--output <file>]");
+    println!("  approve <bundle>      - Verify a submitted bundle and record its approval");
+    println!("  close-month [YYYY-MM] - Close a month, or list closed months [--reopen]");
+    println!("  install-autostart     - Start a job session at login [--remove]");
+    println!("  install-hook          - Add commits in this repository as tasks, starting");
+    println!("                          a job session if needed [--remove]");
+    println!("  watch                 - Add commits as tasks as soon as they are made");
+    println!("                          [--interval <seconds>]");
+    println!("  backup [path]         - Save all data files to a tar.gz archive");
+    println!("  doctor --permissions  - Find data files other users can read and fix them");
+    println!("  restore <file>        - Replace all data files with a backup");
+    println!("  apply <file.yaml>     - Log the sessions described in a YAML or JSON file");
+    println!("  inspect --data-dir <path> <command> - Run a read-only command such as");
+    println!("                          report on another data folder or a backup");
+    println!("  tag rename <old> <new> - Rename a tag on all logged and running tasks");
+    println!("  rewrite --match <regex> --replace <text> - Replace text in the names of");
+    println!("                          logged and running tasks [--project] [--dry-run]");
+    println!("  merge-logs <file>     - Merge the log of another machine into this one");
+    println!("                          [--dry-run] to only list what would change");
+    println!("  sync [git <remote>]   - Keep the data folder in a git repository shared");
+    println!("                          between machines, or sync it now");
+    println!("  sync status           - Show pushes waiting to be sent again");
+    println!("  push                  - Upload the log to the team server");
+    println!("  pull                  - Replace the log with the copy on the team server");
+    println!("  team                  - Show the hours of each team member [--from] [--to]");
+    println!("  server                - Serve logs for a team [--bind <address>] [--port <port>]");
+    println!("  self-update [--check] - Install the latest release from GitHub");
+}
+
+fn version() {
+    let version = env!("CARGO_PKG_VERSION");
+    println!("Jobclock v{}", version);
+}
+
+#[cfg(test)]
+fn persistent_folder() -> std::path::PathBuf {
+    let mut path = std::path::PathBuf::new();
+    path.push("tmp");
+    path
+}
+
+/// The folder data is kept in: the local application data folder on Windows,
+/// where the temp folder gets cleaned up, and the temp folder elsewhere.
+#[cfg(not(test))]
+fn data_home() -> std::path::PathBuf {
+    if cfg!(windows) {
+        if let Some(dir) = std::env::var_os("LOCALAPPDATA").filter(|dir| !dir.is_empty()) {
+            return std::path::PathBuf::from(dir);
+        }
+    }
+    std::env::temp_dir()
+}
+
+#[cfg(not(test))]
+fn persistent_folder() -> std::path::PathBuf {
+    if let Some(folder) = DATA_DIR.get().and_then(|folder| folder.clone()) {
+        return folder;
+    }
+    let mut path = match std::env::var_os("JOBCLOCK_DATA_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => data_home().join("jobclock"),
+    };
+    if let Some(profile) = profile() {
+        path.push("profiles");
+        path.push(profile);
+    }
+    path
+}
+
+/// The profile selected with `--profile` or `JOBCLOCK_PROFILE`. Each profile
+/// has its own data and config folders.
+static PROFILE: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+
+/// The session file as it was last loaded or saved.
+static LOADED_SESSION: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+/// Data folder read by `inspect` instead of the user's own.
+static DATA_DIR: std::sync::OnceLock<Option<std::path::PathBuf>> = std::sync::OnceLock::new();
+
+/// Minutes after `begin` that `cancel` discards a session without `--force`,
+/// unless `cancel_within_minutes` is configured.
+const CANCEL_WITHIN_MINUTES: i64 = 5;
+
+/// Commands `eod` runs unless `eod` is configured, followed by `push` when a
+/// team server is configured.
+const EOD_STEPS: [&str; 3] = ["git", "end", "standup --copy"];
+
+/// Commands that only read data, and so can be run by `inspect`.
+const INSPECT_COMMANDS: [&str; 10] = [
+    "status", "summary", "show", "timeline", "cal", "search", "stats", "retro", "report", "export",
+];
+
+/// `inspect --data-dir <path> <command>`: runs a read-only command on
+/// another data folder or a backup archive. The command works on a copy, so
+/// nothing it does can change the original.
+fn inspect(args: &mut cli::Args) {
+    let Some(data_dir) = args.value("--data-dir") else {
+        error::print(
+            ErrorCode::MissingArgument,
+            "Usage: inspect --data-dir <path> <command> [args]",
+        );
+        return;
+    };
+    let subcommand = args.shift().unwrap_or_default();
+    if !INSPECT_COMMANDS.contains(&subcommand.as_str()) {
+        error::print(
+            ErrorCode::InvalidArgument,
+            &format!(
+                "ERROR: inspect is read-only and supports: {}",
+                INSPECT_COMMANDS.join(", ")
+            ),
+        );
+        return;
+    }
+    let copy = match backup::snapshot(std::path::Path::new(&data_dir)) {
+        Ok(copy) => copy,
+        Err(e) => {
+            error::print(
+                ErrorCode::IoError,
+                &format!("ERROR: Cannot inspect {}: {}", data_dir, e),
+            );
+            return;
+        }
+    };
+    DATA_DIR.set(Some(copy.clone())).unwrap();
+
+    let mut session = if Session::exists() {
+        Session::load()
+    } else {
+        Session::new()
+    };
+    run(&mut session, &subcommand, args);
+    std::fs::remove_dir_all(copy).ok();
+}
+
+fn profile() -> Option<&'static str> {
+    PROFILE.get().and_then(|profile| profile.as_deref())
+}
+
+fn valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn format_duration(duration: chrono::Duration) -> String {
+    let total_seconds = duration.num_seconds();
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+    let seconds = total_seconds % 60;
+    format!("{}h {}m {}s", hours, minutes, seconds)
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Session {
+    tasks: Vec<Task>,
+    start_time: chrono::DateTime<chrono::Local>,
+    working: bool,
+    #[serde(default)]
+    goal: Option<String>,
+    /// Project given to new tasks, set from editor heartbeats.
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default)]
+    last_heartbeat: Option<chrono::DateTime<chrono::Local>>,
+    #[serde(default)]
+    timers: Vec<timer::Timer>,
+    #[serde(default)]
+    expenses: Vec<expense::Expense>,
+    /// End of the last pause between heartbeats in this session.
+    #[serde(default)]
+    resumed_at: Option<chrono::DateTime<chrono::Local>>,
+    #[serde(default)]
+    break_reminded_at: Option<chrono::DateTime<chrono::Local>>,
+    /// Samples of the active window per category, from `activity sample`.
+    #[serde(default)]
+    activity: std::collections::BTreeMap<String, u32>,
+    #[serde(default)]
+    activity_sampled_at: Option<chrono::DateTime<chrono::Local>>,
+    #[serde(default)]
+    attachments: Vec<attach::Attachment>,
+    #[serde(default)]
+    meetings: Vec<meeting::Meeting>,
+    /// Key/value pairs set with `meta set`, such as a ticket ID.
+    #[serde(default)]
+    metadata: std::collections::BTreeMap<String, String>,
+}
+
+impl Session {
+    fn new() -> Session {
+        Session {
+            tasks: vec![],
+            start_time: chrono::Local::now(),
+            working: false,
+            goal: None,
+            project: None,
+            last_heartbeat: None,
+            timers: vec![],
+            expenses: vec![],
+            resumed_at: None,
+            break_reminded_at: None,
+            activity: std::collections::BTreeMap::new(),
+            activity_sampled_at: None,
+            attachments: vec![],
+            meetings: vec![],
+            metadata: std::collections::BTreeMap::new(),
+        }
+    }
+
+    fn get_tasks_clone_sorted(&self) -> Vec<Task> {
+        let mut tasks = self.tasks.clone();
+        tasks.sort_by_key(|task| task.created_at);
+        tasks
+    }
+
+    /// Asks whether each long stretch without a new task before `end_time`
+    /// was a break, and adds a non-billable "Break" task covering the start of
+    /// the stretch for each one that was.
+    fn ask_breaks(
+        &mut self,
+        idle_gaps: &compliance::IdleGaps,
+        end_time: chrono::DateTime<chrono::Local>,
+    ) {
+        let threshold = chrono::Duration::try_minutes(idle_gaps.after_minutes).unwrap();
+        for (start, end) in compliance::idle_gaps(self.start_time, end_time, &self.tasks, threshold)
+        {
+            let question = format!(
+                "No tasks between {} and {} ({}). Was it a break?",
+                start.format("%H:%M"),
+                end.format("%H:%M"),
+                format_duration(end - start)
+            );
+            if cli::ask_yes_no(&question) != Some(true) {
+                continue;
+            }
+            let minutes = cli::ask("Minutes of break (empty for all of it):").unwrap_or_default();
+            let length = match minutes.parse::<i64>() {
+                Ok(minutes) if minutes > 0 => chrono::Duration::try_minutes(minutes)
+                    .unwrap()
+                    .min(end - start),
+                _ => end - start,
+            };
+            let mut task = Task::new("Break", start + length);
+            task.billable = false;
+            self.tasks.push(task);
+        }
+    }
+
+    /// Whether the session has been open for more than 24 hours, which
+    /// usually means it was forgotten.
+    fn is_stale(&self, now: chrono::DateTime<chrono::Local>) -> bool {
+        self.working && now - self.start_time > chrono::Duration::try_hours(24).unwrap()
+    }
+
+    /// The logged session to resume instead of beginning a new one: the last
+    /// one, when it ended less than `resume_within_minutes` ago.
+    fn resumable(&self, config: &config::Config) -> Option<log::LogEntry> {
+        let minutes = config
+            .resume_within_minutes
+            .filter(|minutes| *minutes > 0)?;
+        let last = log::last()?;
+        let since = chrono::Local::now() - last.end_time;
+        let resumable = since >= chrono::Duration::zero()
+            && since < chrono::Duration::try_minutes(minutes).unwrap()
+            && last.approval.is_none();
+        resumable.then_some(last)
+    }
+
+    /// Takes `entry` out of the log and continues it as the running session,
+    /// so the pause since it ended counts as work. The session is saved
+    /// before the entry is removed, so a failure in between never loses
+    /// the entry; should the removal fail, the session is put back.
+    fn resume(
+        &mut self,
+        entry: log::LogEntry,
+        goal: Option<String>,
+        project: Option<String>,
+    ) -> Result<(), String> {
+        let previous = serde_json::to_string(&self).unwrap();
+        let id = entry.id;
+        self.start_time = entry.start_time;
+        self.goal = goal.or(entry.goal);
+        self.project = project;
+        self.last_heartbeat = None;
+        self.timers = entry.timers;
+        self.expenses = entry.expenses;
+        self.resumed_at = Some(chrono::Local::now());
+        self.break_reminded_at = None;
+        self.activity = entry.activity;
+        self.activity_sampled_at = None;
+        self.attachments = entry.attachments;
+        self.meetings = entry.meetings;
+        self.metadata = entry.metadata;
+        // Unfinished tasks carried over are the tasks of the entry itself.
+        self.tasks = entry.tasks;
+        self.working = true;
+        self.save();
+
+        let mut entries = log::load();
+        entries.retain(|existing| existing.id != id);
+        if let Err(e) = log::save(&entries) {
+            *self = serde_json::from_str(&previous).unwrap();
+            self.save();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn begin(&mut self, goal: Option<String>, project: Option<String>, new: bool) {
+        let config = config::Config::current();
+        let resumable = if self.working || new {
+            None
+        } else {
+            self.resumable(&config)
+        };
+        if let Some(last) = resumable {
+            let question = format!(
+                "The last job session ended {} ago, at {}. Resume it?",
+                format_duration(chrono::Local::now() - last.end_time),
+                last.end_time.format("%H:%M:%S")
+            );
+            if cli::confirm(&question) {
+                let id = last.id;
+                match self.resume(last, goal.clone(), project.clone()) {
+                    Ok(()) => {
+                        cli::info(&format!("Job session {} resumed", id));
+                        return;
+                    }
+                    Err(e) => println!("WARNING: Cannot resume job session {}: {}", id, e),
+                }
+            }
+        }
+        if self.is_stale(chrono::Local::now()) && config.require_end_same_day {
+            error::print(
+                ErrorCode::SessionAlreadyStarted,
+                &format!(
+                    "Job session started at {} is still open\n\
+                     End it with 'end --at <YYYY-MM-DD HH:MM>' before starting a new one",
+                    self.start_time.format("%d-%m-%Y %H:%M:%S")
+                ),
+            );
+        } else if self.working {
+            error::print(
+                ErrorCode::SessionAlreadyStarted,
+                "Job session already started",
+            );
+        } else {
+            cli::info("Job session started");
+            if let Some(goal) = &goal {
+                cli::info(&format!("Goal: {}", goal));
+            }
+            if let Some(project) = &project {
+                cli::info(&format!("Project: {}", project));
+            }
+            self.start_time = chrono::Local::now();
+            self.goal = goal;
+            self.project = project;
+            self.last_heartbeat = None;
+            self.timers.clear();
+            self.expenses.clear();
+            self.resumed_at = None;
+            self.break_reminded_at = None;
+            self.activity.clear();
+            self.activity_sampled_at = None;
+            self.attachments.clear();
+            self.meetings.clear();
+            self.tasks.retain(|task| task.carried_over);
+            for task in &mut self.tasks {
+                task.created_at = self.start_time;
+            }
+            if !self.tasks.is_empty() {
+                cli::info(&format!(
+                    "{} unfinished tasks carried over",
+                    self.tasks.len()
+                ));
+            }
+            self.working = true;
+        }
+    }
+
+    /// Ends the session now, or at `at` for a session that was left open.
+    fn end(
+        &mut self,
+        at: Option<chrono::DateTime<chrono::Local>>,
+        ai_summary: Option<String>,
+        copy: bool,
+        receipt: bool,
+    ) {
+        if self.working {
+            let now = chrono::Local::now();
+            let end_time = at.unwrap_or(now);
+            if end_time <= self.start_time || end_time > now {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!(
+                        "End time must be between {} and now",
+                        self.start_time.format("%d-%m-%Y %H:%M:%S")
+                    ),
+                );
+                return;
+            }
+            let config = config::Config::current();
+            if let Some(idle_gaps) = &config.idle_gaps {
+                self.ask_breaks(idle_gaps, end_time);
+            }
+            let goal_met = match &self.goal {
+                Some(goal) => cli::ask_yes_no(&format!("Goal: {}\nGoal met?", goal)),
+                None => None,
+            };
+            let mut timers = self.timers.clone();
+            for timer in &mut timers {
+                timer.stop(end_time);
+            }
+            let mut meetings = self.meetings.clone();
+            for meeting in meetings.iter_mut().filter(|meeting| meeting.end.is_none()) {
+                meeting.end = Some(end_time);
+            }
+            // Carried over tasks were logged with the session they were added in.
+            let mut tasks: Vec<Task> = self
+                .get_tasks_clone_sorted()
+                .into_iter()
+                .filter(|task| !task.carried_over)
+                .collect();
+            for task in &mut tasks {
+                if let (Some(started_at), None) = (task.started_at, task.ended_at) {
+                    task.ended_at = Some(end_time.max(started_at));
+                }
+            }
+            let entry = log::LogEntry {
+                start_time: self.start_time,
+                end_time,
+                tasks,
+                goal: self.goal.clone(),
+                goal_met,
+                timers,
+                expenses: self.expenses.clone(),
+                summary: ai_summary,
+                activity: self.activity.clone(),
+                attachments: self.attachments.clone(),
+                meetings,
+                metadata: self.metadata.clone(),
+                ..Default::default()
+            };
+            if let Err(e) = log::append(entry.clone()) {
+                error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+                return;
+            }
+
+            if receipt {
+                print!("{}", render::receipt(&entry, render::RECEIPT_WIDTH));
+            } else if !cli::quiet() {
+                println!("Job session ended");
+                print!("{}", render::session(&entry, &config.summary));
+            }
+            if copy {
+                copy_summary(&entry, &config.summary);
+            }
+
+            self.tasks = self.unfinished_tasks();
+            self.clear();
+            if !self.tasks.is_empty() && !cli::quiet() {
+                println!(
+                    "{} unfinished tasks will be carried over to the next job session",
+                    self.tasks.len()
+                );
+            }
+        } else {
+            error::print(ErrorCode::NoActiveSession, "No job session to end");
+        }
+    }
+
+    /// Stops the session and drops everything recorded in it except the tasks.
+    fn clear(&mut self) {
+        self.working = false;
+        self.goal = None;
+        self.project = None;
+        self.last_heartbeat = None;
+        self.timers.clear();
+        self.expenses.clear();
+        self.activity.clear();
+        self.activity_sampled_at = None;
+        self.attachments.clear();
+        self.meetings.clear();
+        self.metadata.clear();
+    }
+
+    /// Discards the running session without logging it, for a session begun
+    /// by mistake. Only tasks carried over from the session before are kept.
+    /// A session older than `grace` is only discarded with `force`.
+    fn cancel(
+        &mut self,
+        grace: chrono::Duration,
+        force: bool,
+        now: chrono::DateTime<chrono::Local>,
+    ) {
+        if !self.working {
+            error::print(ErrorCode::NoActiveSession, "No job session to cancel");
+            return;
+        }
+        let elapsed = now - self.start_time;
+        if elapsed > grace && !force {
+            error::print(
+                ErrorCode::CommandFailed,
+                &format!(
+                    "ERROR: The job session started {} ago, more than {} minutes ago; \
+                     use 'cancel --force' to discard it anyway, or 'end' to log it",
+                    format_duration(elapsed),
+                    grace.num_minutes()
+                ),
+            );
+            return;
+        }
+        self.tasks.retain(|task| task.carried_over);
+        for task in &mut self.tasks {
+            task.started_at = None;
+            task.ended_at = None;
+        }
+        self.clear();
+        cli::info(&format!(
+            "Job session started at {} cancelled, nothing was logged",
+            self.start_time.format("%d-%m-%Y %H:%M:%S")
+        ));
+    }
+
+    /// Adds a task. Without an explicit `project`, the project comes from the
+    /// categorization rules, the current directory or the session, in that
+    /// order.
+    fn task(&mut self, name: &str, project: Option<String>) {
+        if !self.working {
+            error::print(ErrorCode::NoActiveSession, "No job session started");
+        } else {
+            if name.is_empty() {
+                error::print(ErrorCode::MissingArgument, "Task name is required");
+                return;
+            }
+            let task = self.new_task(name, project, chrono::Local::now());
+            self.add_task(task);
+            cli::info(&format!("Task '{}' added to job session", name));
+        }
+    }
+
+    /// A done task created at `now`, with its project.
+    fn new_task(
+        &self,
+        name: &str,
+        project: Option<String>,
+        now: chrono::DateTime<chrono::Local>,
+    ) -> Task {
+        let mut task = Task::new(name, now);
+        task.project = project;
+        let config = config::Config::current();
+        rules::categorize(&config.rules, &mut task);
+        let folder = std::env::current_dir().unwrap();
+        if task.project.is_none() {
+            task.project =
+                rules::detect_project(&config.paths, &folder).or_else(|| self.project.clone());
+        }
+        if config.branch_tags {
+            if let Some(branch) = git::current_branch(&folder) {
+                let tag = format!("{}{}", git::BRANCH_TAG_PREFIX, branch);
+                if !task.tags.contains(&tag) {
+                    task.tags.push(tag);
+                }
+            }
+        }
+        task
+    }
+
+    /// Starts a timed task, stopping the one running before it, so its time
+    /// is measured from now until `task stop`.
+    fn start_task(&mut self, name: &str, project: Option<String>) {
+        if !self.working {
+            error::print(ErrorCode::NoActiveSession, "No job session started");
+            return;
+        }
+        if name.is_empty() {
+            error::print(ErrorCode::MissingArgument, "Task name is required");
+            return;
+        }
+        let now = chrono::Local::now();
+        self.stop_running_task(now);
+        let mut task = self.new_task(name, project, now);
+        task.state = TaskState::InProgress;
+        task.started_at = Some(now);
+        self.add_task(task);
+        cli::info(&format!("Task '{}' started", name));
+    }
+
+    /// Stops the running timed task at `at`, returning its name.
+    fn stop_running_task(&mut self, at: chrono::DateTime<chrono::Local>) -> Option<String> {
+        let task = self
+            .tasks
+            .iter_mut()
+            .find(|task| task.started_at.is_some() && task.ended_at.is_none())?;
+        task.ended_at = Some(at.max(task.started_at.unwrap()));
+        task.state = TaskState::Done;
+        Some(task.name.clone())
+    }
+
+    fn stop_task(&mut self) {
+        if !self.working {
+            error::print(ErrorCode::NoActiveSession, "No job session started");
+            return;
+        }
+        match self.stop_running_task(chrono::Local::now()) {
+            Some(name) => cli::info(&format!("Task '{}' stopped", name)),
+            None => error::print(ErrorCode::CommandFailed, "No task is running"),
+        }
+    }
+
+    /// Tasks still in progress, marked as carried over to the next session.
+    fn unfinished_tasks(&self) -> Vec<Task> {
+        self.get_tasks_clone_sorted()
+            .into_iter()
+            .filter(|task| task.state == TaskState::InProgress)
+            .map(|task| Task {
+                carried_over: true,
+                started_at: None,
+                ended_at: None,
+                ..task
+            })
+            .collect()
+    }
+
+    /// Sets the state of the task at the 1-based `index` in the order shown
+    /// by `status`.
+    fn set_task_state(&mut self, index: usize, state: TaskState) {
+        if !self.working {
+            error::print(ErrorCode::NoActiveSession, "No job session started");
+            return;
+        }
+        self.tasks.sort_by_key(|task| task.created_at);
+        let Some(task) = index.checked_sub(1).and_then(|i| self.tasks.get_mut(i)) else {
+            error::print(
+                ErrorCode::InvalidArgument,
+                &format!("No task with index {}", index),
+            );
+            return;
+        };
+        task.state = state;
+        match state {
+            TaskState::Done => cli::info(&format!("Task '{}' marked as done", task.name)),
+            TaskState::InProgress => {
+                cli::info(&format!("Task '{}' marked as in progress", task.name))
+            }
+        }
+    }
+
+    fn move_task(&mut self, index: usize, project: String) {
+        if !self.working {
+            error::print(ErrorCode::NoActiveSession, "No job session started");
+            return;
+        }
+        self.tasks.sort_by_key(|task| task.created_at);
+        let Some(task) = index.checked_sub(1).and_then(|i| self.tasks.get_mut(i)) else {
+            error::print(
+                ErrorCode::InvalidArgument,
+                &format!("No task with index {}", index),
+            );
+            return;
+        };
+        cli::info(&format!(
+            "Task '{}' moved to project {}",
+            task.name, project
+        ));
+        task.project = Some(project);
+    }
+
+    fn comment_task(&mut self, index: usize, comment: &str) {
+        if !self.working {
+            error::print(ErrorCode::NoActiveSession, "No job session started");
+            return;
+        }
+        self.tasks.sort_by_key(|task| task.created_at);
+        let Some(task) = index.checked_sub(1).and_then(|i| self.tasks.get_mut(i)) else {
+            error::print(
+                ErrorCode::InvalidArgument,
+                &format!("No task with index {}", index),
+            );
+            return;
+        };
+        task.comments.push(comment.to_string());
+        cli::info(&format!("Comment added to task '{}'", task.name));
+    }
+
+    /// Records that an editor is working on `file`. The project comes from
+    /// `project` or the configured path rules, and becomes the project of
+    /// tasks added afterwards.
+    fn heartbeat(&mut self, file: &str, project: Option<String>) {
+        let config = config::Config::current();
+        let path = std::env::current_dir().unwrap().join(file);
+        let project = project.or_else(|| rules::project_for_path(&config.paths, &path));
+
+        if !self.working {
+            if !config.heartbeat_auto_start {
+                error::print(ErrorCode::NoActiveSession, "No job session started");
+                return;
+            }
+            self.begin(None, None, true);
+        }
+        let now = chrono::Local::now();
+        let pause = config.break_reminder.unwrap_or_default().pause_minutes;
+        if self
+            .last_heartbeat
+            .is_some_and(|last| now - last >= chrono::Duration::try_minutes(pause).unwrap())
+        {
+            self.resumed_at = Some(now);
+        }
+        self.last_heartbeat = Some(now);
+        if project.is_some() && project != self.project {
+            cli::info(&format!("Project: {}", project.as_deref().unwrap()));
+            self.project = project;
+        }
+    }
+
+    /// Adds an expense to the running session, or logs it on its own when no
+    /// session is running. The project is detected like for tasks.
+    fn expense(
+        &mut self,
+        amount: f64,
+        description: &str,
+        project: Option<String>,
+        currency: Option<String>,
+    ) {
+        let config = config::Config::current();
+        let project = project.or_else(|| {
+            rules::detect_project(&config.paths, &std::env::current_dir().unwrap())
+                .or_else(|| self.project.clone())
+        });
+        let currency = currency.or_else(|| currency::project_currency(&config, project.as_deref()));
+        let amount_label =
+            currency::format(amount, currency.as_deref().or(config.currency.as_deref()));
+        let now = chrono::Local::now();
+        let expense = expense::Expense {
+            date: now,
+            amount,
+            description: description.to_string(),
+            project,
+            currency,
+        };
+
+        if self.working {
+            self.expenses.push(expense);
+            cli::info(&format!(
+                "Expense '{}' of {} added to job session",
+                description, amount_label
+            ));
+            return;
+        }
+        let entry = log::LogEntry {
+            start_time: now,
+            end_time: now,
+            expenses: vec![expense],
+            ..Default::default()
+        };
+        match log::append(entry) {
+            Ok(_) => cli::info(&format!(
+                "Expense '{}' of {} logged",
+                description, amount_label
+            )),
+            Err(e) => error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e)),
+        }
+    }
+
+    /// Adds a task, recording it in the journal first so it is not lost if
+    /// the process dies before the session is saved.
+    fn add_task(&mut self, task: Task) {
+        journal::record(&journal::Operation::AddTask(task.clone()));
+        self.tasks.push(task);
+    }
+
+    /// The session file as it was last loaded or saved, and as it is now,
+    /// if it was replaced since, as by a sync service.
+    fn changed_on_disk() -> Option<(String, String)> {
+        let loaded = LOADED_SESSION.lock().unwrap().clone()?;
+        let current = log::load_session()?;
+        (loaded != current).then_some((loaded, current))
+    }
+
+    /// Keeps the tasks added and removed in a session file that was
+    /// replaced since it was loaded, as when a sync service brought in the
+    /// session of another machine.
+    fn merge_changes_on_disk(&mut self) {
+        let Some((loaded, current)) = Session::changed_on_disk() else {
+            return;
+        };
+        let (Ok(base), Ok(theirs)) = (
+            serde_json::from_str::<Session>(&loaded),
+            serde_json::from_str::<Session>(&current),
+        ) else {
+            return;
+        };
+        self.tasks = storage::merge(&base.tasks, &self.tasks, &theirs.tasks, |task| {
+            (task.name.clone(), task.created_at)
+        });
+        cli::info("Merged the tasks changed on another machine into the session");
+    }
+
+    /// Whether a session was saved before.
+    fn exists() -> bool {
+        log::load_session().is_some()
+    }
+
+    /// Saves the session, in the storage of the log, and empties the
+    /// journal. With synced storage, changes made to the file since it was
+    /// loaded are merged in first.
+    fn save(&mut self) {
+        if config::Config::current().storage == storage::StorageMode::Synced {
+            self.merge_changes_on_disk();
+        }
+        let data = serde_json::to_string(&self).unwrap();
+        log::save_session(&data).unwrap();
+        journal::clear();
+        *LOADED_SESSION.lock().unwrap() = Some(data);
+    }
+
+    /// Loads the session and replays the operations in the journal that did
+    /// not make it into the saved session.
+    fn load() -> Session {
+        let data = log::load_session().unwrap();
+        let mut session: Session = serde_json::from_str(&data).unwrap();
+        *LOADED_SESSION.lock().unwrap() = Some(data);
+
+        let mut recovered = 0;
+        for operation in journal::pending() {
+            match operation {
+                journal::Operation::AddTask(task) => {
+                    if !session.tasks.iter().any(|existing| {
+                        existing.name == task.name && existing.created_at == task.created_at
+                    }) {
+                        session.tasks.push(task);
+                        recovered += 1;
+                    }
+                }
+            }
+        }
+        if recovered > 0 {
+            println!("Recovered {} tasks that were not saved", recovered);
+            session.save();
+        }
+        session
+    }
+
+    fn status(&self, verbose: bool) {
+        if let Some(profile) = profile() {
+            println!("Profile: {}", profile);
+        }
+        let now = chrono::Local::now();
+        let config = config::Config::current();
+        let weeks = week::Weeks::from_config(&config);
+        let first = weeks.first_day(now.date_naive());
+        let info = render::StatusInfo {
+            now,
+            weeks,
+            entries: log::load_range(Some(first), Some(now.date_naive())),
+            last: if self.working { None } else { log::last() },
+            without_break: self.continuous_work().map(|(_, worked, due)| (worked, due)),
+            verbose,
+            colors: color::ProjectColors::for_terminal(&config),
+        };
+        print!("{}", render::status(self, &info));
+
+        if budget::configured(&config) {
+            let mut entries = log::load();
+            if self.working {
+                entries.push(log::LogEntry {
+                    start_time: self.start_time,
+                    end_time: now,
+                    tasks: self.get_tasks_clone_sorted(),
+                    ..Default::default()
+                });
+            }
+            budget::print_budgets(&config, &entries, &project::archived());
+        }
+    }
+
+    /// A prose summary of the task titles from the configured endpoint.
+    #[cfg(feature = "ai-summary")]
+    fn ai_summary(&self) -> Result<String, String> {
+        let config = config::Config::current()
+            .ai_summary
+            .ok_or("AI summaries are not enabled, add ai_summary to the config")?;
+        if self.tasks.is_empty() {
+            return Err("No tasks added".to_string());
+        }
+        let tasks = self
+            .get_tasks_clone_sorted()
+            .into_iter()
+            .map(|task| task.name)
+            .collect::<Vec<String>>();
+        ai::generate(&config, &tasks)
+    }
+
+    #[cfg(not(feature = "ai-summary"))]
+    fn ai_summary(&self) -> Result<String, String> {
+        Err("This build does not include AI summaries (cargo feature ai-summary)".to_string())
+    }
+
+    /// Adds the recurring tasks whose time has passed since the session
+    /// started, returning whether any were added.
+    fn add_recurring_tasks(&mut self, now: chrono::DateTime<chrono::Local>) -> bool {
+        if !self.working {
+            return false;
+        }
+        let recurring = config::Config::current().recurring;
+        let mut added = false;
+        for task in recurring::due(&recurring, self.start_time, now) {
+            if !self.tasks.iter().any(|existing| {
+                existing.recurring
+                    && existing.name == task.name
+                    && existing.created_at == task.created_at
+            }) {
+                cli::info(&format!(
+                    "Recurring task '{}' added to job session",
+                    task.name
+                ));
+                self.add_task(task);
+                added = true;
+            }
+        }
+        added
+    }
+
+    /// The tasks as numbered by `task done`, with their state and their
+    /// labels in the color of their project.
+    fn task_lines(&self, colors: &color::ProjectColors) -> Vec<String> {
+        self.get_tasks_clone_sorted()
+            .iter()
+            .enumerate()
+            .map(|(index, task)| {
+                format!(
+                    "{}. [{}] {} - {}{}{}{}",
+                    index + 1,
+                    if task.state == TaskState::Done {
+                        "x"
+                    } else {
+                        " "
+                    },
+                    task.created_at.format("%d-%m-%Y %H:%M:%S"),
+                    task.name,
+                    colors.paint(task.project.as_deref(), &task.labels()),
+                    if task.carried_over {
+                        " (carried over)"
+                    } else {
+                        ""
+                    },
+                    match (task.started_at, task.ended_at) {
+                        (Some(_), None) => " (running)".to_string(),
+                        (Some(started_at), Some(ended_at)) => {
+                            format!(" ({})", format_duration(ended_at - started_at))
+                        }
+                        _ => String::new(),
+                    }
+                )
+            })
+            .collect()
+    }
+
+    /// Time since the session started, zero when none is running.
+    fn elapsed(&self, now: chrono::DateTime<chrono::Local>) -> chrono::Duration {
+        if self.working {
+            now - self.start_time
+        } else {
+            chrono::Duration::zero()
+        }
+    }
+
+    /// The state and elapsed time on one line, for `status --short`.
+    fn short_status(&self, now: chrono::DateTime<chrono::Local>) -> String {
+        if self.working {
+            format!("working {}", format_duration(self.elapsed(now)))
+        } else {
+            "not working".to_string()
+        }
+    }
+
+    /// The start of the current stretch of work without a pause, the time
+    /// worked since and whether a break is due, when a break reminder is
+    /// configured. Sessions separated by gaps shorter than a pause count as
+    /// one stretch, and so do heartbeats.
+    fn continuous_work(&self) -> Option<(chrono::DateTime<chrono::Local>, chrono::Duration, bool)> {
+        let reminder = config::Config::current().break_reminder?;
+        if !self.working {
+            return None;
+        }
+        let pause = chrono::Duration::try_minutes(reminder.pause_minutes).unwrap();
+        let day = self.start_time.date_naive();
+        let entries = log::load_range(day.pred_opt(), Some(day));
+        let since = compliance::continuous_since(&entries, self.start_time, pause);
+        let since = self.resumed_at.map_or(since, |resumed| since.max(resumed));
+        let worked = chrono::Local::now() - since;
+        let due = worked >= chrono::Duration::try_minutes(reminder.after_minutes).unwrap();
+        Some((since, worked, due))
+    }
+
+    /// Records the category of the active window, unless the last sample is
+    /// more recent than the configured interval. Returns whether a sample
+    /// was taken.
+    fn sample_activity(
+        &mut self,
+        sampling: &activity::ActivitySampling,
+        now: chrono::DateTime<chrono::Local>,
+    ) -> Result<bool, String> {
+        let interval = chrono::Duration::try_minutes(sampling.every_minutes).unwrap();
+        if self
+            .activity_sampled_at
+            .is_some_and(|at| now - at < interval)
+        {
+            return Ok(false);
+        }
+        let category = activity::categorize(sampling, &activity::active_window()?);
+        *self.activity.entry(category).or_insert(0) += 1;
+        self.activity_sampled_at = Some(now);
+        Ok(true)
+    }
+
+    /// Prints a reminder when a break is due, and shows it as a desktop
+    /// notification once per stretch of work.
+    fn remind_break(&mut self) {
+        let Some((since, worked, true)) = self.continuous_work() else {
+            return;
+        };
+        let message = format!("{} worked without a break", format_duration(worked));
+        println!("Time for a break: {}", message);
+        if self.break_reminded_at.is_none_or(|at| at < since) {
+            notify::send("Time for a break", &message);
+            self.break_reminded_at = Some(chrono::Local::now());
+        }
+    }
+
+    fn extract_from_git(&mut self, all_authors: bool, with_stats: bool) {
+        if !self.working {
+            error::print(ErrorCode::NoActiveSession, "No job session started");
+            return;
+        }
+
+        let config = config::Config::current();
+        // Only the commits since the session began are read from git.
+        let since = format!("--since={}", self.start_time.format("%Y-%m-%dT%H:%M:%S%:z"));
+        let repositories = git::commits_by_repository(
+            &git::repositories(&config),
+            &[since],
+            all_authors,
+            with_stats,
+        );
+        git::print_repository_counts(&repositories, all_authors);
+
+        let mut count = 0;
+        for repository in repositories {
+            for commit in repository.commits {
+                // Commits added by `watch` or the post-commit hook are
+                // already tasks.
+                if commit.date > self.start_time
+                    && !self.tasks.iter().any(|task| task.name == commit.title)
+                {
+                    let mut task = Task::new(&commit.title, commit.date);
+                    task.stats = commit.stats;
+                    rules::categorize(&config.rules, &mut task);
+                    self.add_task(task);
+                    count += 1;
+                }
+            }
+        }
+
+        cli::info(&format!("Extracted {} tasks from git commits", count));
+    }
+}
+
+/// Runs jobclock with the command line arguments and exits with the status
+/// of the command.
+pub fn main() {
+    start();
+    std::process::exit(error::status());
+}
+
+/// Runs the command given on the command line. Errors are printed with
+/// `error::print`, which sets the exit status.
+fn start() {
+    let mut args = cli::Args::new(std::env::args().skip(1).collect());
+    error::set_json(args.flag("--json"));
+    cli::set_quiet(args.flag("--quiet"));
+    cli::set_yes(args.flag("--yes"));
+
+    let profile = args.value("--profile").or_else(|| {
+        std::env::var("JOBCLOCK_PROFILE")
+            .ok()
+            .filter(|profile| !profile.is_empty())
+    });
+    if let Some(profile) = &profile {
+        if !valid_profile_name(profile) {
+            error::print(
+                ErrorCode::InvalidArgument,
+                &format!("ERROR: Invalid profile name: {}", profile),
+            );
+            return;
+        }
+    }
+    PROFILE.set(profile).unwrap();
+
+    let config = match config::Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            error::print(ErrorCode::InvalidArgument, &format!("ERROR: {}", e));
+            return;
+        }
+    };
+    if let Err(e) = args.expand_alias(&config.aliases) {
+        error::print(ErrorCode::InvalidArgument, &format!("ERROR: {}", e));
+        return;
+    }
+    let subcommand = args.shift().unwrap_or("".to_string());
+    if subcommand == "inspect" {
+        inspect(&mut args);
+        return;
+    }
+    sync::pull();
+    queue::retry(chrono::Local::now());
+
+    let mut session = Session::new();
+    if Session::exists() {
+        session = Session::load();
+    } else {
+        session.save();
+    }
+
+    if subcommand.is_empty() {
+        error::print(ErrorCode::UnknownCommand, "ERROR: No subcommand found");
+        if !error::json() {
+            usage();
+        }
+        return;
+    }
+
+    if subcommand == "interactive" {
+        interactive(&mut session);
+        return;
+    }
+
+    if session.add_recurring_tasks(chrono::Local::now()) {
+        session.save();
+    }
+    if run(&mut session, &subcommand, &mut args) {
+        session.save();
+    }
+    sync::commit(&subcommand);
+}
+
+/// Places the summary of `entry` on the clipboard, followed by its AI
+/// summary if it has one.
+fn copy_summary(entry: &log::LogEntry, format: &summary::SummaryFormat) {
+    let mut lines = if entry.tasks.is_empty() {
+        vec![]
+    } else {
+        summary::summary_lines(entry.start_time, &entry.tasks, format)
+    };
+    lines.extend(entry.summary.clone());
+    if lines.is_empty() {
+        println!("No summary to copy");
+        return;
+    }
+    match clipboard::copy(&lines.join("\n")) {
+        Ok(()) => cli::info("Summary copied to the clipboard"),
+        Err(e) => error::print(
+            ErrorCode::CommandFailed,
+            &format!("ERROR: Cannot copy the summary: {}", e),
+        ),
+    }
+}
+
+/// Runs `subcommand` on `session`, returning whether the session changed and
+/// should be saved.
+fn run(session: &mut Session, subcommand: &str, args: &mut cli::Args) -> bool {
+    match subcommand {
+        "begin" => {
+            let goal = args.value("-m").or_else(|| args.value("--goal"));
+            let project = args.value("--project").or_else(|| {
+                let config = config::Config::current();
+                rules::detect_project(&config.paths, &std::env::current_dir().unwrap())
+                    .or(config.project)
+            });
+            let new = args.flag("--new");
+            session.begin(goal.filter(|goal| !goal.is_empty()), project, new);
+        }
+        "cancel" => {
+            let minutes = config::Config::current()
+                .cancel_within_minutes
+                .unwrap_or(CANCEL_WITHIN_MINUTES);
+            session.cancel(
+                chrono::Duration::try_minutes(minutes).unwrap_or_default(),
+                args.flag("--force"),
+                chrono::Local::now(),
+            );
+        }
+        "end" => {
+            let at = match args.value("--at") {
+                Some(at) => match cli::parse_time(&at, chrono::Local::now().date_naive()) {
+                    Some(at) => Some(at),
+                    None => {
+                        error::print(
+                            ErrorCode::InvalidArgument,
+                            &format!("Invalid time: {} (expected YYYY-MM-DD HH:MM or HH:MM)", at),
+                        );
+                        return false;
+                    }
+                },
+                None => None,
+            };
+            let ai_summary = if args.flag("--ai-summary") && session.working {
+                match session.ai_summary() {
+                    Ok(summary) => Some(summary),
+                    Err(e) => {
+                        error::print(
+                            ErrorCode::IoError,
+                            &format!("ERROR: Cannot write the summary: {}", e),
+                        );
+                        return false;
+                    }
+                }
+            } else {
+                None
+            };
+            session.end(at, ai_summary, args.flag("--copy"), args.flag("--receipt"));
+        }
+        "task" => {
+            let project = args.value("--project");
+            let to_project = args.value("--to-project");
+            let logged = args.value("--session");
+            let text = args.text();
+            let mut words = text.splitn(2, ' ');
+            match (words.next(), words.next().map(|index| index.parse())) {
+                (Some("done"), Some(Ok(index))) => session.set_task_state(index, TaskState::Done),
+                (Some("undone"), Some(Ok(index))) => {
+                    session.set_task_state(index, TaskState::InProgress)
+                }
+                (Some("start"), _) => {
+                    session.start_task(text["start".len()..].trim(), project);
+                    session.remind_break();
+                }
+                (Some(action @ ("done" | "undone")), _) => {
+                    error::print(
+                        ErrorCode::MissingArgument,
+                        &format!("Usage: task {} <index>", action),
+                    );
+                    return false;
+                }
+                (Some("stop"), None) => session.stop_task(),
+                (Some("stop"), Some(_)) => {
+                    error::print(ErrorCode::MissingArgument, "Usage: task stop");
+                    return false;
+                }
+                (Some("comment"), _) => {
+                    let rest = text["comment".len()..].trim();
+                    let (index, comment) = rest.split_once(' ').unwrap_or((rest, ""));
+                    let (Ok(index), false) = (index.parse(), comment.trim().is_empty()) else {
+                        error::print(
+                            ErrorCode::MissingArgument,
+                            "Usage: task comment <index> <text> [--session <id>]",
+                        );
+                        return false;
+                    };
+                    match logged {
+                        Some(id) => {
+                            edit::comment_logged_task(&id, index, comment.trim());
+                            return false;
+                        }
+                        None => session.comment_task(index, comment.trim()),
+                    }
+                }
+                (Some("move"), Some(Ok(index))) => match to_project {
+                    Some(project) => session.move_task(index, project),
+                    None => {
+                        error::print(
+                            ErrorCode::MissingArgument,
+                            "Usage: task move <index> --to-project <name>",
+                        );
+                        return false;
+                    }
+                },
+                _ => {
+                    session.task(text.as_str(), project);
+                    session.remind_break();
+                }
+            }
+        }
+        "help" => {
+            usage();
+            return false;
+        }
+        "version" => {
+            version();
+            return false;
+        }
+        "status" => {
+            if args.flag("--short") {
+                println!("{}", session.short_status(chrono::Local::now()));
+            } else if args.flag("--time-only") {
+                println!("{}", format_duration(session.elapsed(chrono::Local::now())));
+            } else if args.flag("--tasks-only") {
+                if session.working {
+                    let colors = color::ProjectColors::for_terminal(&config::Config::current());
+                    for line in session.task_lines(&colors) {
+                        println!("{}", line);
+                    }
+                }
+            } else {
+                session.status(args.flag("--verbose"));
+            }
+            return false;
+        }
+        "git" => {
+            let all_authors = args.flag("--all-authors");
+            let with_stats = args.flag("--stats");
+            let since = args.value("--since");
+            let until = args.value("--until");
+            if since.is_some() || until.is_some() {
+                git::extract_range(since, until, all_authors, with_stats);
+                return false;
+            }
+            session.extract_from_git(all_authors, with_stats);
+        }
+        "timer" => {
+            if !session.working {
+                error::print(ErrorCode::NoActiveSession, "No job session started");
+                return false;
+            }
+            let now = chrono::Local::now();
+            let action = args.shift().unwrap_or_default();
+            let name = args.text();
+            match action.as_str() {
+                "start" | "stop" if name.is_empty() => {
+                    error::print(ErrorCode::MissingArgument, "Timer name is required");
+                    return false;
+                }
+                "start" => timer::start(&mut session.timers, &name, now),
+                "stop" => timer::stop(&mut session.timers, &name, now),
+                "" | "list" => {
+                    if session.timers.is_empty() {
+                        println!("No timers started");
+                    }
+                    print!("{}", timer::render_timers(&session.timers, now));
+                    return false;
+                }
+                _ => {
+                    error::print(
+                        ErrorCode::InvalidArgument,
+                        &format!("Unknown timer action: {}", action),
+                    );
+                    return false;
+                }
+            }
+        }
+        "meeting" => {
+            if !session.working {
+                error::print(ErrorCode::NoActiveSession, "No job session started");
+                return false;
+            }
+            let now = chrono::Local::now();
+            match args.shift().unwrap_or_default().as_str() {
+                "start" => {
+                    let name = args.text();
+                    if name.is_empty() {
+                        error::print(ErrorCode::MissingArgument, "Meeting name is required");
+                        return false;
+                    }
+                    meeting::start(&mut session.meetings, &name, now);
+                }
+                "end" => meeting::end(&mut session.meetings, now),
+                "" | "list" => {
+                    if session.meetings.is_empty() {
+                        println!("No meetings in this job session");
+                    }
+                    print!("{}", meeting::render_meetings(&session.meetings, now));
+                    return false;
+                }
+                action => {
+                    error::print(
+                        ErrorCode::InvalidArgument,
+                        &format!("Unknown meeting action: {}", action),
+                    );
+                    return false;
+                }
+            }
+        }
+        "expense" => {
+            if args.shift().as_deref() != Some("add") {
+                error::print(
+                    ErrorCode::MissingArgument,
+                    "Usage: expense add <amount> <description> [--project <name>]",
+                );
+                return false;
+            }
+            let project = args.value("--project");
+            let currency = args.value("--currency");
+            let amount = args.shift().unwrap_or_default();
+            let Some(amount) = expense::parse_amount(&amount) else {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("Invalid amount: {}", amount),
+                );
+                return false;
+            };
+            let description = args.text();
+            if description.is_empty() {
+                error::print(
+                    ErrorCode::MissingArgument,
+                    "Expense description is required",
+                );
+                return false;
+            }
+            session.expense(amount, &description, project, currency);
+        }
+        "heartbeat" => {
+            let project = args.value("--project");
+            let file = args.text();
+            if file.is_empty() {
+                error::print(ErrorCode::MissingArgument, "File is required");
+                return false;
+            }
+            session.heartbeat(&file, project);
+            session.remind_break();
+        }
+        "activity" => {
+            if args.shift().as_deref() != Some("sample") {
+                error::print(ErrorCode::MissingArgument, "Usage: activity sample");
+                return false;
+            }
+            let Some(sampling) = config::Config::current().activity else {
+                error::print(
+                    ErrorCode::CommandFailed,
+                    "Activity sampling is not enabled in the config",
+                );
+                return false;
+            };
+            if !session.working {
+                return false;
+            }
+            match session.sample_activity(&sampling, chrono::Local::now()) {
+                Ok(sampled) => return sampled,
+                Err(e) => {
+                    error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+                    return false;
+                }
+            }
+        }
+        "report" => {
+            report::report(args);
+            return false;
+        }
+        "close-month" => {
+            timesheet::close_month(args);
+            return false;
+        }
+        "backup" => {
+            backup::backup(args.text().as_str());
+            return false;
+        }
+        "restore" => {
+            backup::restore(args.text().as_str());
+            return false;
+        }
+        "push" => {
+            team::push();
+            return false;
+        }
+        "pull" => {
+            team::pull();
+            return false;
+        }
+        "team" => {
+            team::team(args);
+            return false;
+        }
+        "server" => {
+            #[cfg(feature = "server")]
+            server::serve(args);
+            #[cfg(not(feature = "server"))]
+            error::print(
+                ErrorCode::CommandFailed,
+                "This build does not include the team server (cargo feature server)",
+            );
+            return false;
+        }
+        "tag" => return tags::tag(args, &mut session.tasks),
+        "rewrite" => return rewrite::rewrite(args, &mut session.tasks),
+        "attach" => return attach::attach(args, session.working, &mut session.attachments),
+        "meta" => return meta::meta(args, session.working, &mut session.metadata),
+        "merge-logs" => {
+            merge::merge_logs(args);
+            return false;
+        }
+        "sync" => {
+            sync::sync(args);
+            return false;
+        }
+        "self-update" => {
+            update::self_update(args);
+            return false;
+        }
+        "move" => {
+            edit::move_session(args);
+            return false;
+        }
+        "split" => {
+            edit::split(args);
+            return false;
+        }
+        "apply" => {
+            apply::apply(args.text().as_str());
+            return false;
+        }
+        "timeline" => {
+            let running = if session.working {
+                Some(log::LogEntry {
+                    start_time: session.start_time,
+                    end_time: chrono::Local::now(),
+                    tasks: session.get_tasks_clone_sorted(),
+                    ..Default::default()
+                })
+            } else {
+                None
+            };
+            timeline::timeline(args.text().as_str(), running);
+            return false;
+        }
+        "_complete" => {
+            complete::complete(args.text().as_str(), &session.tasks);
+            return false;
+        }
+        "goal" => {
+            target::goal(args);
+            return false;
+        }
+        "project" => {
+            project::project(args);
+            return false;
+        }
+        "timebank" => {
+            timebank::timebank(args);
+            return false;
+        }
+        "forecast" => {
+            forecast::forecast(args);
+            return false;
+        }
+        "day" => {
+            daytype::day(args);
+            return false;
+        }
+        "install-autostart" => {
+            autostart::install_autostart(args.flag("--remove"));
+            return false;
+        }
+        "watch" => {
+            #[cfg(feature = "daemon")]
+            watch::watch(args);
+            #[cfg(not(feature = "daemon"))]
+            error::print(
+                ErrorCode::CommandFailed,
+                "This build does not include the commit watcher (cargo feature daemon)",
+            );
+            return false;
+        }
+        "doctor" => {
+            doctor::doctor(args);
+            return false;
+        }
+        "install-hook" => {
+            hook::install_hook(args.flag("--remove"));
+            return false;
+        }
+        "_post-commit" => {
+            let folder = std::env::current_dir().unwrap();
+            let Some(title) = git::last_commit_title(&folder) else {
+                return false;
+            };
+            if !session.working {
+                let project = rules::detect_project(&config::Config::current().paths, &folder);
+                session.begin(None, project, true);
+                if !session.working {
+                    return false;
+                }
+            }
+            if session.tasks.iter().any(|task| task.name == title) {
+                return false;
+            }
+            session.task(&title, None);
+        }
+        "submit" => {
+            approval::submit(args);
+            return false;
+        }
+        "approve" => {
+            approval::approve(args);
+            return false;
+        }
+        "export" => {
+            export::export(args);
+            return false;
+        }
+        "cal" => {
+            calendar::calendar(args.text().as_str());
+            return false;
+        }
+        "summary" => {
+            if !session.working {
+                error::print(ErrorCode::NoActiveSession, "No job session started");
+                return false;
+            }
+            let mut format = config::Config::current().summary;
+            if let Some(style) = args.value("--style") {
+                match style.parse() {
+                    Ok(style) => format.style = style,
+                    Err(e) => {
+                        error::print(ErrorCode::InvalidArgument, &format!("ERROR: {}", e));
+                        return false;
+                    }
+                }
+            }
+            if let Some(language) = args.value("--language") {
+                if let Err(e) = summary::language(&language) {
+                    error::print(ErrorCode::InvalidArgument, &format!("ERROR: {}", e));
+                    return false;
+                }
+                format.language = language;
+            }
+            print!(
+                "{}",
+                summary::render_summary(session.start_time, &session.tasks, &format)
+            );
+            return false;
+        }
+        "tw" => match args.shift().unwrap_or_default().as_str() {
+            "" | "pull" => {
+                taskwarrior::print_pending();
+                return false;
+            }
+            "add" => {
+                if !session.working {
+                    error::print(ErrorCode::NoActiveSession, "No job session started");
+                    return false;
+                }
+                let id = args.text();
+                let task = match taskwarrior::pending() {
+                    Ok(tasks) => tasks.into_iter().find(|task| task.id.to_string() == id),
+                    Err(e) => {
+                        error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+                        return false;
+                    }
+                };
+                let Some(task) = task else {
+                    error::print(
+                        ErrorCode::InvalidArgument,
+                        &format!("No pending Taskwarrior task with id {}", id),
+                    );
+                    return false;
+                };
+                if session
+                    .tasks
+                    .iter()
+                    .any(|existing| existing.taskwarrior_uuid.as_deref() == Some(&task.uuid))
+                {
+                    error::print(
+                        ErrorCode::CommandFailed,
+                        &format!("Task '{}' is already in the job session", task.description),
+                    );
+                    return false;
+                }
+                session.add_task(task.to_task(chrono::Local::now()));
+                cli::info(&format!("Task '{}' added to job session", task.description));
+            }
+            "push" => {
+                taskwarrior::push();
+                return false;
+            }
+            action => {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("Unknown tw action: {}", action),
+                );
+                return false;
+            }
+        },
+        "search" => {
+            search::search(args.text().as_str());
+            return false;
+        }
+        "show" => {
+            show::show(args);
+            return false;
+        }
+        "stats" => {
+            stats::stats(args);
+            return false;
+        }
+        "quick" => {
+            quick::quick(args, session.working.then_some(session.start_time));
+            return false;
+        }
+        "retro" => {
+            retro::retro(args);
+            return false;
+        }
+        "standup" => {
+            standup::standup(args, session.goal.as_deref(), &session.tasks);
+            return false;
+        }
+        "eod" => {
+            eod(session);
+            return false;
+        }
+        "check" => {
+            check::check(args, session.working.then_some(session.start_time));
+            return false;
+        }
+        _ => {
+            error::print(
+                ErrorCode::UnknownCommand,
+                &format!("ERROR: Invalid command entered: {}", subcommand),
+            );
+            if !error::json() {
+                usage();
+            }
+            return false;
+        }
+    }
+
+    true
+}
+
+/// The commands `eod` runs: the configured ones, or the default routine.
+fn eod_steps(config: &config::Config) -> Vec<String> {
+    if let Some(steps) = &config.eod {
+        return steps.clone();
+    }
+    let mut steps = EOD_STEPS.map(str::to_string).to_vec();
+    if config.team.is_some() {
+        steps.push("push".to_string());
+    }
+    steps
+}
+
+/// `eod`: runs the end-of-day routine, by default extracting the day's git
+/// commits, ending the session, copying a standup update to the clipboard
+/// and pushing the log to the team server. The steps are checked before
+/// any of them runs, and the ones after a step that fails, or after `end`
+/// when the session does not end, are skipped.
+fn eod(session: &mut Session) {
+    if !session.working {
+        error::print(ErrorCode::NoActiveSession, "No job session to end");
+        return;
+    }
+    let config = config::Config::current();
+    let mut steps = vec![];
+    for step in eod_steps(&config) {
+        let mut args = match cli::split_words(&step).and_then(|words| {
+            let mut args = cli::Args::new(words);
+            args.expand_alias(&config.aliases)?;
+            Ok(args)
+        }) {
+            Ok(args) => args,
+            Err(e) => {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("ERROR: Invalid eod step '{}': {}", step, e),
+                );
+                return;
+            }
+        };
+        let Some(subcommand) = args.shift() else {
+            continue;
+        };
+        if subcommand == "eod" || subcommand == "interactive" {
+            error::print(
+                ErrorCode::InvalidArgument,
+                &format!("ERROR: {} cannot be an eod step", subcommand),
+            );
+            return;
+        }
+        steps.push((step, subcommand, args));
+    }
+
+    for (step, subcommand, mut args) in steps {
+        cli::info(&format!("==> {}", step));
+        if run(session, &subcommand, &mut args) {
+            session.save();
+        }
+        sync::commit(&subcommand);
+        if error::status() != 0 {
+            error::print(
+                ErrorCode::CommandFailed,
+                &format!(
+                    "ERROR: The step '{}' failed, skipping the remaining steps",
+                    step
+                ),
+            );
+            return;
+        }
+        if subcommand == "end" && session.working {
+            error::print(
+                ErrorCode::CommandFailed,
+                "ERROR: The job session did not end, skipping the remaining steps",
+            );
+            return;
+        }
+    }
+}
+
+/// Reads commands from a prompt until `exit` or end of input, keeping the
+/// session in memory and saving it after every command that changes it.
+fn interactive(session: &mut Session) {
+    use std::io::Write;
+
+    println!("JobClock interactive mode, type 'help' for commands and 'exit' to quit");
+    loop {
+        match profile() {
+            Some(profile) => print!("jobclock ({})> ", profile),
+            None => print!("jobclock> "),
+        }
+        std::io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line).unwrap() == 0 {
+            println!();
+            break;
+        }
+        let mut args = match cli::split_words(&line).and_then(|words| {
+            let mut args = cli::Args::new(words);
+            args.expand_alias(&config::Config::load()?.aliases)?;
+            Ok(args)
+        }) {
+            Ok(args) => args,
+            Err(e) => {
+                error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+                continue;
+            }
+        };
+        let Some(subcommand) = args.shift() else {
+            continue;
+        };
+
+        error::reset();
+        match subcommand.as_str() {
+            "exit" | "quit" => break,
+            "interactive" => println!("Already in interactive mode"),
+            _ => {
+                // A sync service may have replaced the session file since
+                // the last command.
+                if config::Config::current().storage == storage::StorageMode::Synced
+                    && Session::changed_on_disk().is_some()
+                {
+                    *session = Session::load();
+                }
+                if session.add_recurring_tasks(chrono::Local::now()) {
+                    session.save();
+                }
+                if run(session, &subcommand, &mut args) {
+                    session.save();
+                }
+                sync::commit(&subcommand);
+                // A restore replaces the session file underneath us.
+                if subcommand == "restore" && Session::exists() {
+                    *session = Session::load();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_begin() {
+        let mut session = Session::new();
+        if Session::exists() {
+            session = Session::load();
+        } else {
+            session.save();
+        }
+        session.begin(None, None, true);
+        assert!(session.working);
+        session.task("Test", None);
+
+        for task in &session.tasks {
+            assert_eq!(task.name, "Test");
+        }
+
+        session.end(None, None, false, false);
+        assert!(!session.working);
+        assert_eq!(session.tasks.len(), 0);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let minutes = |minutes| chrono::Duration::try_minutes(minutes).unwrap();
+        let mut session = Session::new();
+        session.working = true;
+        let mut carried = Task::new("Carried", session.start_time);
+        carried.carried_over = true;
+        session.tasks = vec![carried, Task::new("Typo", session.start_time)];
+        session.goal = Some("Oops".to_string());
+
+        session.cancel(minutes(5), false, session.start_time + minutes(10));
+        assert!(session.working);
+        session.cancel(minutes(5), true, session.start_time + minutes(10));
+        assert!(!session.working);
+        assert_eq!(session.goal, None);
+        assert_eq!(session.tasks.len(), 1);
+        assert_eq!(session.tasks[0].name, "Carried");
+    }
+
+    #[test]
+    fn test_eod_steps() {
+        let mut config = config::Config::default();
+        assert_eq!(eod_steps(&config), ["git", "end", "standup --copy"]);
+        config.team = Some(team::TeamServer::default());
+        assert_eq!(eod_steps(&config).last().unwrap(), "push");
+        config.eod = Some(vec!["end --receipt".to_string()]);
+        assert_eq!(eod_steps(&config), ["end --receipt"]);
+    }
+
+    #[test]
+    fn test_unfinished_tasks_carried_over() {
+        let mut session = Session::new();
+        session.working = true;
+        session.tasks = vec![Task::new("Finished", chrono::Local::now())];
+        session.task("Unfinished", None);
+        assert!(session.unfinished_tasks().is_empty());
+        session.set_task_state(2, TaskState::InProgress);
+
+        let unfinished = session.unfinished_tasks();
+        assert_eq!(unfinished.len(), 1);
+        assert_eq!(unfinished[0].name, "Unfinished");
+        assert!(unfinished[0].carried_over);
+    }
+
+    #[test]
+    fn test_short_status() {
+        let mut session = Session::new();
+        let now = session.start_time + chrono::Duration::try_minutes(95).unwrap();
+        assert_eq!(session.short_status(now), "not working");
+        assert_eq!(session.elapsed(now), chrono::Duration::zero());
+
+        session.working = true;
+        session.tasks = vec![Task::new("Review", session.start_time)];
+        assert_eq!(session.short_status(now), "working 1h 35m 0s");
+        assert!(session.task_lines(&color::ProjectColors::default())[0].starts_with("1. [x] "));
+    }
+}
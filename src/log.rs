@@ -0,0 +1,680 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    approval::Approval,
+    attach::Attachment,
+    error::{self, ErrorCode},
+    expense::Expense,
+    meeting::Meeting,
+    storage,
+    storage::Storage,
+    timer::Timer,
+    timesheet, Task,
+};
+
+/// A finished job session, appended to the log when the session ends.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+pub struct LogEntry {
+    pub id: u64,
+    pub start_time: chrono::DateTime<chrono::Local>,
+    pub end_time: chrono::DateTime<chrono::Local>,
+    pub tasks: Vec<Task>,
+    #[serde(default)]
+    pub goal: Option<String>,
+    #[serde(default)]
+    pub goal_met: Option<bool>,
+    #[serde(default)]
+    pub timers: Vec<Timer>,
+    #[serde(default)]
+    pub expenses: Vec<Expense>,
+    #[serde(default)]
+    pub approval: Option<Approval>,
+    /// Prose summary written with `end --ai-summary`.
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// Samples of the active window per category, from `activity sample`.
+    #[serde(default)]
+    pub activity: std::collections::BTreeMap<String, u32>,
+    /// Files and URLs linked with `attach`.
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub meetings: Vec<Meeting>,
+    /// Key/value pairs set with `meta set`, such as a ticket ID.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl LogEntry {
+    pub fn duration(&self) -> chrono::Duration {
+        self.end_time - self.start_time
+    }
+}
+
+/// The log is rotated by month: entries are stored in `log/YYYY-MM.json` by
+/// the month they started in, and `log/index.json` lists the months so that
+/// reading a period only has to parse the months it covers.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+struct IndexEntry {
+    month: String,
+    sessions: usize,
+    last_id: u64,
+}
+
+pub fn month_of(time: &chrono::DateTime<chrono::Local>) -> String {
+    time.format("%Y-%m").to_string()
+}
+
+/// The log kept as JSON files in a data folder.
+pub struct JsonFiles {
+    folder: std::path::PathBuf,
+    /// Why a part of the log could not be read at the last load. The log is
+    /// not written until it loads whole again, as writing it would drop the
+    /// sessions that were left out.
+    unreadable: Option<String>,
+}
+
+impl JsonFiles {
+    pub fn new(folder: std::path::PathBuf) -> JsonFiles {
+        JsonFiles {
+            folder,
+            unreadable: None,
+        }
+    }
+
+    fn log_folder(&self) -> std::path::PathBuf {
+        self.folder.join("log")
+    }
+
+    fn index_file(&self) -> std::path::PathBuf {
+        self.log_folder().join("index.json")
+    }
+
+    fn month_file(&self, month: &str) -> std::path::PathBuf {
+        self.log_folder().join(format!("{}.json", month))
+    }
+
+    fn session_file(&self) -> std::path::PathBuf {
+        self.folder.join("session.json")
+    }
+
+    /// The journal holds one operation per line and is emptied every time
+    /// the session is saved.
+    fn journal_file(&self) -> std::path::PathBuf {
+        self.folder.join("journal.jsonl")
+    }
+
+    /// The single-file log used before rotation was introduced.
+    fn legacy_log_file(&self) -> std::path::PathBuf {
+        self.folder.join("log.json")
+    }
+
+    /// Splits a legacy `log.json` into monthly files.
+    fn migrate(&self) -> std::io::Result<()> {
+        if !self.legacy_log_file().exists() || self.index_file().exists() {
+            return Ok(());
+        }
+        let entries: Vec<LogEntry> = read_json(&self.legacy_log_file())?;
+        self.write_all(&entries)?;
+        std::fs::remove_file(self.legacy_log_file())
+    }
+
+    fn read_index(&self) -> std::io::Result<Vec<IndexEntry>> {
+        read_json(&self.index_file())
+    }
+
+    fn load_index(&self) -> std::io::Result<Vec<IndexEntry>> {
+        self.migrate()?;
+        self.read_index()
+    }
+
+    fn save_index(&self, index: &[IndexEntry]) -> std::io::Result<()> {
+        let data = serde_json::to_string(index).unwrap();
+        replace_file(&self.index_file(), data)
+    }
+
+    fn read_month(&self, month: &str) -> std::io::Result<Vec<LogEntry>> {
+        read_json(&self.month_file(month))
+    }
+
+    /// Prints that a part of the log cannot be read, which is then left
+    /// out of what is loaded.
+    fn report(&mut self, e: std::io::Error) {
+        error::print(
+            ErrorCode::IoError,
+            &format!("ERROR: Cannot read the log: {}", e),
+        );
+        self.unreadable = Some(e.to_string());
+    }
+
+    /// The entries of the months in the index that `keep` accepts.
+    fn read_months(&mut self, keep: impl Fn(&str) -> bool) -> Vec<LogEntry> {
+        let index = match self.load_index() {
+            Ok(index) => index,
+            Err(e) => {
+                self.report(e);
+                return vec![];
+            }
+        };
+        let mut entries = vec![];
+        for index in index.iter().filter(|index| keep(&index.month)) {
+            match self.read_month(&index.month) {
+                Ok(month) => entries.extend(month),
+                Err(e) => self.report(e),
+            }
+        }
+        entries
+    }
+
+    fn write_month(&self, month: &str, entries: &[LogEntry]) -> std::io::Result<()> {
+        storage::create_folder(&self.log_folder())?;
+        let data = serde_json::to_string(entries).unwrap();
+        replace_file(&self.month_file(month), data)
+    }
+
+    /// Every month is replaced before the index, and months no longer in
+    /// the log are only removed once the new index is in place, so the log
+    /// on disk stays whole if writing stops partway.
+    fn write_all(&self, entries: &[LogEntry]) -> std::io::Result<()> {
+        if let Some(e) = &self.unreadable {
+            return Err(std::io::Error::other(format!(
+                "The log is not saved while a part of it cannot be read: {}",
+                e
+            )));
+        }
+        let old = self.read_index()?;
+        storage::create_folder(&self.log_folder())?;
+        let mut index = vec![];
+        for (month, entries) in group_by_month(entries) {
+            self.write_month(&month, &entries)?;
+            index.push(IndexEntry {
+                sessions: entries.len(),
+                last_id: entries.iter().map(|entry| entry.id).max().unwrap_or(0),
+                month,
+            });
+        }
+        self.save_index(&index)?;
+
+        for old in old {
+            if !index.iter().any(|index| index.month == old.month) {
+                std::fs::remove_file(self.month_file(&old.month)).ok();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Storage for JsonFiles {
+    fn load(&mut self) -> Vec<LogEntry> {
+        self.unreadable = None;
+        self.read_months(|_| true)
+    }
+
+    fn load_month(&mut self, month: &str) -> Vec<LogEntry> {
+        match self.migrate().and_then(|_| self.read_month(month)) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.report(e);
+                vec![]
+            }
+        }
+    }
+
+    /// Reads only the months in the index that overlap the period.
+    fn load_range(
+        &mut self,
+        from: Option<chrono::NaiveDate>,
+        to: Option<chrono::NaiveDate>,
+    ) -> Vec<LogEntry> {
+        let from = from.map(|date| date.format("%Y-%m").to_string());
+        let to = to.map(|date| date.format("%Y-%m").to_string());
+        self.read_months(|month| {
+            from.as_deref().is_none_or(|from| month >= from)
+                && to.as_deref().is_none_or(|to| month <= to)
+        })
+    }
+
+    /// Reads months from the most recent one until a month with sessions is
+    /// found.
+    fn last(&mut self) -> Option<LogEntry> {
+        let index = match self.load_index() {
+            Ok(index) => index,
+            Err(e) => {
+                self.report(e);
+                return None;
+            }
+        };
+        index
+            .iter()
+            .rev()
+            .find_map(|index| match self.read_month(&index.month) {
+                Ok(entries) => entries.into_iter().max_by_key(|entry| entry.end_time),
+                Err(e) => {
+                    self.report(e);
+                    None
+                }
+            })
+    }
+
+    /// Only the month the session started in is read and written.
+    fn append(&mut self, mut entry: LogEntry) -> Result<u64, String> {
+        let mut index = self.load_index().map_err(|e| e.to_string())?;
+        entry.id = index.iter().map(|month| month.last_id).max().unwrap_or(0) + 1;
+        let id = entry.id;
+        let month = month_of(&entry.start_time);
+
+        let mut entries = self.read_month(&month).map_err(|e| e.to_string())?;
+        entries.push(entry);
+        self.write_month(&month, &entries)
+            .map_err(|e| e.to_string())?;
+
+        match index.iter_mut().find(|index| index.month == month) {
+            Some(index) => {
+                index.sessions = entries.len();
+                index.last_id = id;
+            }
+            None => {
+                index.push(IndexEntry {
+                    month,
+                    sessions: entries.len(),
+                    last_id: id,
+                });
+                index.sort_by(|a, b| a.month.cmp(&b.month));
+            }
+        }
+        self.save_index(&index).map_err(|e| e.to_string())?;
+        Ok(id)
+    }
+
+    fn save(&mut self, entries: &[LogEntry]) -> Result<(), String> {
+        self.migrate()
+            .and_then(|_| self.write_all(entries))
+            .map_err(|e| e.to_string())
+    }
+
+    fn load_session(&mut self) -> Option<String> {
+        std::fs::read_to_string(self.session_file()).ok()
+    }
+
+    fn save_session(&mut self, data: &str) -> Result<(), String> {
+        storage::create_folder(&self.folder)
+            .and_then(|_| replace_file(&self.session_file(), data))
+            .map_err(|e| e.to_string())
+    }
+
+    fn load_journal(&mut self) -> String {
+        std::fs::read_to_string(self.journal_file()).unwrap_or_default()
+    }
+
+    /// Flushes the line to disk, so it survives the process being killed.
+    fn append_journal(&mut self, line: &str) -> Result<(), String> {
+        let append = || -> std::io::Result<()> {
+            storage::create_folder(&self.folder)?;
+            let mut file = storage::private_file()
+                .append(true)
+                .open(self.journal_file())?;
+            std::io::Write::write_all(&mut file, format!("{}\n", line).as_bytes())?;
+            file.sync_all()
+        };
+        append().map_err(|e| e.to_string())
+    }
+
+    fn clear_journal(&mut self) {
+        std::fs::remove_file(self.journal_file()).ok();
+    }
+}
+
+/// Writes a temporary file that then replaces `file`, so a crash never
+/// leaves it half written.
+fn replace_file(file: &std::path::Path, data: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let temporary = file.with_extension("json.tmp");
+    let mut open = storage::private_file().truncate(true).open(&temporary)?;
+    std::io::Write::write_all(&mut open, data.as_ref())?;
+    open.sync_all()?;
+    std::fs::rename(&temporary, file)
+}
+
+/// Reads the JSON data file `file`, or the default value when there is none.
+fn read_json<T: serde::de::DeserializeOwned + Default>(
+    file: &std::path::Path,
+) -> std::io::Result<T> {
+    if !file.exists() {
+        return Ok(T::default());
+    }
+    std::fs::read_to_string(file)
+        .and_then(|data| serde_json::from_str(&data).map_err(std::io::Error::from))
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", file.display(), e)))
+}
+
+/// Whether `name` is a month, `YYYY-MM`, as month files are named.
+fn is_month(name: &str) -> bool {
+    name.len() == 7
+        && name.char_indices().all(|(i, c)| match i {
+            4 => c == '-',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+/// Entries are matched by id and start, so two sessions appended with the
+/// same id on different machines are both kept.
+fn entry_key(entry: &LogEntry) -> (u64, chrono::DateTime<chrono::Local>) {
+    (entry.id, entry.start_time)
+}
+
+/// Gives each entry with the id of an entry before it the next free id.
+fn unique_ids(entries: &mut [LogEntry]) {
+    let mut next = entries.iter().map(|entry| entry.id).max().unwrap_or(0) + 1;
+    let mut seen = std::collections::BTreeSet::new();
+    for entry in entries {
+        if !seen.insert(entry.id) {
+            entry.id = next;
+            seen.insert(next);
+            next += 1;
+        }
+    }
+}
+
+/// The log kept as JSON files in a folder that a sync service such as
+/// Dropbox or iCloud Drive may change at any time. Conflicted copies left
+/// by the service are merged back into the log, and a log that changed on
+/// disk since it was loaded is merged with the changes instead of being
+/// overwritten.
+pub struct SyncedFiles {
+    files: JsonFiles,
+    /// The log as it was last loaded, to tell our changes from theirs.
+    loaded: Option<Vec<LogEntry>>,
+}
+
+impl SyncedFiles {
+    pub fn new(folder: std::path::PathBuf) -> SyncedFiles {
+        SyncedFiles {
+            files: JsonFiles::new(folder),
+            loaded: None,
+        }
+    }
+
+    /// Rebuilds the index when it no longer matches the month files, as
+    /// when a month file was replaced after the index was written, and
+    /// merges conflicted copies of month files, such as `2024-06 (conflicted
+    /// copy).json` or `2024-06 2.json`, into their months.
+    fn repair(&mut self) -> std::io::Result<()> {
+        let Ok(folder) = std::fs::read_dir(self.files.log_folder()) else {
+            return Ok(());
+        };
+        let mut months = vec![];
+        let mut copies = vec![];
+        for file in folder.flatten() {
+            let path = file.path();
+            let Some(name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+            else {
+                continue;
+            };
+            if is_month(name) {
+                months.push(name.to_string());
+            } else if name != "index" {
+                if name.starts_with("index") {
+                    std::fs::remove_file(&path).ok();
+                } else if name.get(..7).is_some_and(is_month) {
+                    copies.push(path);
+                }
+            }
+        }
+        months.sort();
+
+        let modified = |file: &std::path::Path| {
+            std::fs::metadata(file)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        };
+        let indexed = modified(&self.files.index_file());
+        // An index that cannot be read is rebuilt from the month files.
+        let index = self.files.read_index().unwrap_or_default();
+        if !copies.is_empty()
+            || index.len() != months.len()
+            || index
+                .iter()
+                .zip(&months)
+                .any(|(index, month)| index.month != *month)
+            || months
+                .iter()
+                .any(|month| modified(&self.files.month_file(month)) > indexed)
+        {
+            self.rebuild_index(&months)?;
+        }
+        if copies.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = self.files.load();
+        for copy in copies {
+            let Some(theirs) = std::fs::read_to_string(&copy)
+                .ok()
+                .and_then(|data| serde_json::from_str::<Vec<LogEntry>>(&data).ok())
+            else {
+                println!(
+                    "WARNING: Cannot read the conflicted copy {}",
+                    copy.display()
+                );
+                continue;
+            };
+            // Without the version both copies came from, nothing counts as
+            // removed, and the sessions only in the copy are added.
+            let known = entries
+                .iter()
+                .map(entry_key)
+                .collect::<std::collections::BTreeSet<_>>();
+            entries.extend(
+                theirs
+                    .into_iter()
+                    .filter(|entry| !known.contains(&entry_key(entry))),
+            );
+            std::fs::remove_file(&copy).ok();
+            crate::cli::info(&format!("Merged {} into the log", copy.display()));
+        }
+        unique_ids(&mut entries);
+        self.files.write_all(&entries)
+    }
+
+    /// Repairs the folder before the log is read from it, or prints why it
+    /// cannot be repaired and reads it as it is.
+    fn repair_or_report(&mut self) {
+        if let Err(e) = self.repair() {
+            error::print(
+                ErrorCode::IoError,
+                &format!("ERROR: Cannot repair the log: {}", e),
+            );
+        }
+    }
+
+    fn rebuild_index(&self, months: &[String]) -> std::io::Result<()> {
+        let index = months
+            .iter()
+            .map(|month| {
+                let entries = self.files.read_month(month).unwrap_or_default();
+                IndexEntry {
+                    month: month.clone(),
+                    sessions: entries.len(),
+                    last_id: entries.iter().map(|entry| entry.id).max().unwrap_or(0),
+                }
+            })
+            .collect::<Vec<IndexEntry>>();
+        self.files.save_index(&index)
+    }
+}
+
+impl Storage for SyncedFiles {
+    fn load(&mut self) -> Vec<LogEntry> {
+        self.repair_or_report();
+        let entries = self.files.load();
+        self.loaded = Some(entries.clone());
+        entries
+    }
+
+    fn load_month(&mut self, month: &str) -> Vec<LogEntry> {
+        self.repair_or_report();
+        self.files.load_month(month)
+    }
+
+    fn load_range(
+        &mut self,
+        from: Option<chrono::NaiveDate>,
+        to: Option<chrono::NaiveDate>,
+    ) -> Vec<LogEntry> {
+        self.repair_or_report();
+        self.files.load_range(from, to)
+    }
+
+    fn last(&mut self) -> Option<LogEntry> {
+        self.repair_or_report();
+        self.files.last()
+    }
+
+    /// The month is read right before it is written, so a file replaced by
+    /// the sync service is appended to rather than overwritten.
+    fn append(&mut self, entry: LogEntry) -> Result<u64, String> {
+        self.repair().map_err(|e| e.to_string())?;
+        self.files.append(entry)
+    }
+
+    fn load_session(&mut self) -> Option<String> {
+        self.files.load_session()
+    }
+
+    fn save_session(&mut self, data: &str) -> Result<(), String> {
+        self.files.save_session(data)
+    }
+
+    fn load_journal(&mut self) -> String {
+        self.files.load_journal()
+    }
+
+    fn append_journal(&mut self, line: &str) -> Result<(), String> {
+        self.files.append_journal(line)
+    }
+
+    fn clear_journal(&mut self) {
+        self.files.clear_journal()
+    }
+
+    fn save(&mut self, entries: &[LogEntry]) -> Result<(), String> {
+        self.repair().map_err(|e| e.to_string())?;
+        let mut merged = match &self.loaded {
+            Some(loaded) => storage::merge(loaded, entries, &self.files.load(), entry_key),
+            None => entries.to_vec(),
+        };
+        unique_ids(&mut merged);
+        self.files.save(&merged)?;
+        self.loaded = Some(merged);
+        Ok(())
+    }
+}
+
+fn group_by_month(entries: &[LogEntry]) -> BTreeMap<String, Vec<LogEntry>> {
+    let mut months: BTreeMap<String, Vec<LogEntry>> = BTreeMap::new();
+    for entry in entries {
+        months
+            .entry(month_of(&entry.start_time))
+            .or_default()
+            .push(entry.clone());
+    }
+    months
+}
+
+thread_local! {
+    static STORAGE: std::cell::RefCell<Option<Box<dyn Storage>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// The storage the log and session are read from and written to: the data
+/// folder, or memory in unit tests, so every test thread starts empty.
+#[cfg(not(test))]
+fn default_storage() -> Box<dyn Storage> {
+    match crate::config::Config::current().storage {
+        storage::StorageMode::Local => Box::new(JsonFiles::new(crate::persistent_folder())),
+        storage::StorageMode::Synced => Box::new(SyncedFiles::new(crate::persistent_folder())),
+    }
+}
+
+#[cfg(test)]
+fn default_storage() -> Box<dyn Storage> {
+    Box::new(crate::storage::Memory::default())
+}
+
+fn with_storage<T>(f: impl FnOnce(&mut dyn Storage) -> T) -> T {
+    STORAGE.with(|storage| {
+        let mut storage = storage.borrow_mut();
+        f(storage.get_or_insert_with(default_storage).as_mut())
+    })
+}
+
+/// The running session as it was saved, as JSON, if one was saved.
+pub fn load_session() -> Option<String> {
+    with_storage(|storage| storage.load_session())
+}
+
+pub fn save_session(data: &str) -> Result<(), String> {
+    with_storage(|storage| storage.save_session(data))
+}
+
+pub fn load_journal() -> String {
+    with_storage(|storage| storage.load_journal())
+}
+
+pub fn append_journal(line: &str) -> Result<(), String> {
+    with_storage(|storage| storage.append_journal(line))
+}
+
+pub fn clear_journal() {
+    with_storage(|storage| storage.clear_journal())
+}
+
+/// Loads every entry in the log, ordered by month.
+pub fn load() -> Vec<LogEntry> {
+    with_storage(|storage| storage.load())
+}
+
+/// The session that ended last.
+pub fn last() -> Option<LogEntry> {
+    with_storage(|storage| storage.last())
+}
+
+/// Loads the entries that started in `month` (`YYYY-MM`).
+pub fn load_month(month: &str) -> Vec<LogEntry> {
+    with_storage(|storage| storage.load_month(month))
+}
+
+/// Loads the entries of every month overlapping `from..=to`. Entries outside
+/// the period but in the same months are included, so callers still filter
+/// by date.
+pub fn load_range(from: Option<chrono::NaiveDate>, to: Option<chrono::NaiveDate>) -> Vec<LogEntry> {
+    with_storage(|storage| storage.load_range(from, to))
+}
+
+/// Appends a session to the log, assigning it the next free id, unless its
+/// month is closed.
+pub fn append(entry: LogEntry) -> Result<u64, String> {
+    with_storage(|storage| {
+        let month = month_of(&entry.start_time);
+        if let Some(closed) = timesheet::load()
+            .into_iter()
+            .find(|closed| closed.month == month)
+        {
+            let mut entries = storage.load_month(&month);
+            entries.push(entry.clone());
+            timesheet::check_unchanged(&closed, &entries)?;
+        }
+        storage.append(entry)
+    })
+}
+
+/// Rewrites the whole log, refusing any change to the entries of a closed
+/// month.
+pub fn save(entries: &[LogEntry]) -> Result<(), String> {
+    for closed in timesheet::load() {
+        timesheet::check_unchanged(&closed, entries)?;
+    }
+    with_storage(|storage| storage.save(entries))
+}
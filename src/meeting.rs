@@ -0,0 +1,185 @@
+use crate::{
+    cli,
+    error::{self, ErrorCode},
+    format_duration,
+    report::Day,
+};
+
+/// Time in a meeting within a session, counted apart from the tasks.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+pub struct Meeting {
+    pub name: String,
+    pub start: chrono::DateTime<chrono::Local>,
+    /// When the meeting ended, or `None` while it is running.
+    pub end: Option<chrono::DateTime<chrono::Local>>,
+}
+
+impl Meeting {
+    pub fn duration(&self, now: chrono::DateTime<chrono::Local>) -> chrono::Duration {
+        (self.end.unwrap_or(now) - self.start).max(chrono::Duration::zero())
+    }
+}
+
+/// Starts a meeting called `name`, unless one is already running.
+pub fn start(meetings: &mut Vec<Meeting>, name: &str, now: chrono::DateTime<chrono::Local>) {
+    if let Some(running) = meetings.iter().find(|meeting| meeting.end.is_none()) {
+        error::print(
+            ErrorCode::CommandFailed,
+            &format!(
+                "Meeting '{}' is still running, end it with 'meeting end'",
+                running.name
+            ),
+        );
+        return;
+    }
+    meetings.push(Meeting {
+        name: name.to_string(),
+        start: now,
+        end: None,
+    });
+    cli::info(&format!("Meeting '{}' started", name));
+}
+
+/// Ends the running meeting.
+pub fn end(meetings: &mut [Meeting], now: chrono::DateTime<chrono::Local>) {
+    match meetings.iter_mut().find(|meeting| meeting.end.is_none()) {
+        Some(meeting) => {
+            meeting.end = Some(now);
+            cli::info(&format!(
+                "Meeting '{}' ended after {}",
+                meeting.name,
+                format_duration(meeting.duration(now))
+            ));
+        }
+        None => error::print(ErrorCode::CommandFailed, "No meeting is running"),
+    }
+}
+
+/// The meetings of a session with their times, if it has any.
+pub fn render_meetings(meetings: &[Meeting], now: chrono::DateTime<chrono::Local>) -> String {
+    let mut text = String::new();
+    if meetings.is_empty() {
+        return text;
+    }
+    text.push_str("Meetings:\n");
+    for meeting in meetings {
+        let duration = format_duration(meeting.duration(now));
+        text.push_str(&match meeting.end {
+            Some(end) => format!(
+                "  {} - {}-{} ({})\n",
+                meeting.name,
+                meeting.start.format("%H:%M"),
+                end.format("%H:%M"),
+                duration
+            ),
+            None => format!(
+                "  {} - from {} ({}, running)\n",
+                meeting.name,
+                meeting.start.format("%H:%M"),
+                duration
+            ),
+        });
+    }
+    text
+}
+
+/// Time in meetings per meeting name over all sessions in `days`, largest
+/// first.
+pub fn totals(days: &[Day]) -> Vec<(String, chrono::Duration)> {
+    let mut totals: Vec<(String, chrono::Duration)> = vec![];
+    for session in days.iter().flat_map(|day| &day.sessions) {
+        for meeting in &session.meetings {
+            let duration = meeting.duration(session.end_time);
+            match totals.iter_mut().find(|(name, _)| *name == meeting.name) {
+                Some((_, total)) => *total += duration,
+                None => totals.push((meeting.name.clone(), duration)),
+            }
+        }
+    }
+    totals.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+    totals
+}
+
+/// The meeting time in `days` and its share of `total`, unless `total` is
+/// under a second, with the time per meeting name, if there were any
+/// meetings.
+pub fn render_totals(days: &[Day], total: chrono::Duration) -> String {
+    let totals = totals(days);
+    let mut text = String::new();
+    if totals.is_empty() {
+        return text;
+    }
+    let meetings = totals
+        .iter()
+        .fold(chrono::Duration::zero(), |sum, (_, duration)| {
+            sum + *duration
+        });
+    text.push_str(&format!("Meetings: {}", format_duration(meetings)));
+    if total.num_seconds() != 0 {
+        text.push_str(&format!(
+            " ({:.0}% of total)",
+            meetings.num_seconds() as f64 / total.num_seconds() as f64 * 100.0
+        ));
+    }
+    text.push('\n');
+    for (name, duration) in totals {
+        text.push_str(&format!("  {} - {}\n", name, format_duration(duration)));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{log::LogEntry, report};
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_totals() {
+        let at = |day, hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, day, hour, minute, 0)
+                .unwrap()
+        };
+        let meeting = |name: &str, start, end| Meeting {
+            name: name.to_string(),
+            start,
+            end,
+        };
+        let entry = |day, meetings| LogEntry {
+            start_time: at(day, 8, 0),
+            end_time: at(day, 16, 0),
+            meetings,
+            ..Default::default()
+        };
+        // A meeting left running counts until the session ended.
+        let days = report::days(&[
+            entry(
+                3,
+                vec![
+                    meeting("standup", at(3, 9, 0), Some(at(3, 9, 15))),
+                    meeting("sprint planning", at(3, 13, 0), Some(at(3, 14, 30))),
+                ],
+            ),
+            entry(4, vec![meeting("standup", at(4, 15, 45), None)]),
+        ]);
+        let minutes = |minutes| chrono::Duration::try_minutes(minutes).unwrap();
+        assert_eq!(
+            totals(&days),
+            vec![
+                ("sprint planning".to_string(), minutes(90)),
+                ("standup".to_string(), minutes(30)),
+            ]
+        );
+        assert_eq!(
+            render_totals(&days, minutes(15 * 60)),
+            "Meetings: 2h 0m 0s (13% of total)\n  \
+             sprint planning - 1h 30m 0s\n  \
+             standup - 0h 30m 0s\n"
+        );
+        assert!(
+            render_totals(&days, chrono::Duration::try_milliseconds(500).unwrap())
+                .starts_with("Meetings: 2h 0m 0s\n")
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{
+    cli::{self, Args},
+    error::{self, ErrorCode},
+    log,
+    log::LogEntry,
+};
+
+/// The result of merging another machine's log into this one.
+struct Merge {
+    entries: Vec<LogEntry>,
+    added: usize,
+    /// Sessions the same on both sides but with tasks only one side has.
+    combined: usize,
+    /// Sessions of the other log that overlap a different local session,
+    /// with the local session they overlap.
+    conflicts: Vec<(LogEntry, LogEntry)>,
+}
+
+/// Whether `a` and `b` are the same session: one that both machines have,
+/// possibly under different ids.
+fn same_session(a: &LogEntry, b: &LogEntry) -> bool {
+    a.start_time == b.start_time && a.end_time == b.end_time
+}
+
+/// Adds the tasks and expenses of `other` that `entry` does not have,
+/// returning whether any were added.
+fn combine(entry: &mut LogEntry, other: &LogEntry) -> bool {
+    let mut changed = false;
+    for task in &other.tasks {
+        if !entry
+            .tasks
+            .iter()
+            .any(|existing| existing.name == task.name && existing.created_at == task.created_at)
+        {
+            entry.tasks.push(task.clone());
+            changed = true;
+        }
+    }
+    for expense in &other.expenses {
+        if !entry.expenses.iter().any(|existing| {
+            existing.date == expense.date && existing.description == expense.description
+        }) {
+            entry.expenses.push(expense.clone());
+            changed = true;
+        }
+    }
+    entry.tasks.sort_by_key(|task| task.created_at);
+    changed
+}
+
+/// Merges `other` into `local`. Sessions with the same start and end are
+/// the same session, sessions that overlap a local one are conflicts left
+/// out of the result, and the rest are added, renumbered when their id is
+/// taken.
+fn merge(local: &[LogEntry], other: &[LogEntry]) -> Merge {
+    let mut merge = Merge {
+        entries: local.to_vec(),
+        added: 0,
+        combined: 0,
+        conflicts: vec![],
+    };
+    let mut next_id = local.iter().map(|entry| entry.id).max().unwrap_or(0) + 1;
+    for entry in other {
+        if let Some(existing) = merge
+            .entries
+            .iter_mut()
+            .find(|existing| same_session(existing, entry))
+        {
+            if combine(existing, entry) {
+                merge.combined += 1;
+            }
+            continue;
+        }
+        if let Some(existing) = merge.entries.iter().find(|existing| {
+            existing.start_time < entry.end_time && entry.start_time < existing.end_time
+        }) {
+            merge.conflicts.push((entry.clone(), existing.clone()));
+            continue;
+        }
+        let mut entry = entry.clone();
+        if merge.entries.iter().any(|existing| existing.id == entry.id) {
+            entry.id = next_id;
+        }
+        next_id = next_id.max(entry.id + 1);
+        merge.entries.push(entry);
+        merge.added += 1;
+    }
+    merge.entries.sort_by_key(|entry| entry.start_time);
+    merge
+}
+
+/// Reads another log: a JSON list of sessions, such as the `log.json` of an
+/// older version or the output of `export`, or a data folder with monthly
+/// log files.
+fn read(path: &std::path::Path) -> Result<Vec<LogEntry>, String> {
+    let parse = |file: &std::path::Path| -> Result<Vec<LogEntry>, String> {
+        let data = std::fs::read_to_string(file)
+            .map_err(|e| format!("Cannot read {}: {}", file.display(), e))?;
+        serde_json::from_str(&data).map_err(|e| format!("Invalid log {}: {}", file.display(), e))
+    };
+    if !path.is_dir() {
+        return parse(path);
+    }
+    let folder = path.join("log");
+    let Ok(files) = std::fs::read_dir(&folder) else {
+        return parse(&path.join("log.json"));
+    };
+    let mut files = files
+        .filter_map(|file| file.ok())
+        .map(|file| file.path())
+        .filter(|file| {
+            file.extension()
+                .is_some_and(|extension| extension == "json")
+                && file.file_name().is_some_and(|name| name != "index.json")
+        })
+        .collect::<Vec<std::path::PathBuf>>();
+    files.sort();
+    let mut entries = vec![];
+    for file in files {
+        entries.extend(parse(&file)?);
+    }
+    Ok(entries)
+}
+
+/// `merge-logs <file|folder> [--dry-run]`: merges the log of another
+/// machine into this one, listing the sessions that conflict.
+pub fn merge_logs(args: &mut Args) {
+    let dry_run = args.flag("--dry-run");
+    let path = args.text();
+    if path.is_empty() {
+        error::print(
+            ErrorCode::MissingArgument,
+            "Usage: merge-logs <other-log.json|data-folder> [--dry-run]",
+        );
+        return;
+    }
+    let other = match read(std::path::Path::new(&path)) {
+        Ok(other) => other,
+        Err(e) => {
+            error::print(ErrorCode::IoError, &format!("ERROR: {}", e));
+            return;
+        }
+    };
+
+    let merge = merge(&log::load(), &other);
+    for (entry, existing) in &merge.conflicts {
+        println!(
+            "CONFLICT: {} - {} overlaps session {} ({} - {})",
+            entry.start_time.format("%d-%m-%Y %H:%M:%S"),
+            entry.end_time.format("%d-%m-%Y %H:%M:%S"),
+            existing.id,
+            existing.start_time.format("%d-%m-%Y %H:%M:%S"),
+            existing.end_time.format("%d-%m-%Y %H:%M:%S")
+        );
+    }
+    let summary = format!(
+        "{} sessions added, {} completed with tasks from {}, {} conflicts left out",
+        merge.added,
+        merge.combined,
+        path,
+        merge.conflicts.len()
+    );
+    if dry_run {
+        println!("{} (dry run, nothing written)", summary);
+        return;
+    }
+    if merge.added + merge.combined == 0 {
+        println!("Nothing to merge from {}", path);
+        return;
+    }
+    match log::save(&merge.entries) {
+        Ok(()) => cli::info(&summary),
+        Err(e) => error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Task;
+
+    #[test]
+    fn test_merge() {
+        use chrono::TimeZone;
+        let at = |day, hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, day, hour, 0, 0)
+                .unwrap()
+        };
+        let entry = |id, day, start, end| LogEntry {
+            id,
+            start_time: at(day, start),
+            end_time: at(day, end),
+            ..Default::default()
+        };
+        let local = vec![entry(1, 11, 9, 12), entry(2, 12, 9, 12)];
+        let mut shared = entry(7, 11, 9, 12);
+        shared.tasks.push(Task::new("Review", at(11, 10)));
+        let other = vec![
+            shared,
+            entry(2, 13, 9, 12),
+            entry(3, 12, 11, 14),
+            entry(4, 14, 9, 12),
+        ];
+
+        let merge = merge(&local, &other);
+        assert_eq!((merge.added, merge.combined), (2, 1));
+        assert_eq!(merge.entries[0].tasks[0].name, "Review");
+        assert_eq!(
+            merge
+                .entries
+                .iter()
+                .map(|entry| entry.id)
+                .collect::<Vec<u64>>(),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!((merge.conflicts[0].0.id, merge.conflicts[0].1.id), (3, 2));
+    }
+}
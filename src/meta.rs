@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    cli::{self, Args},
+    error::{self, ErrorCode},
+};
+
+/// Whether `key` can name a metadata field: letters, digits, `-`, `_` and
+/// `.`, so it can be used as is by tools reading the export.
+fn valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// The metadata of a session, if it has any.
+pub fn render_metadata(metadata: &BTreeMap<String, String>) -> String {
+    let mut text = String::new();
+    if metadata.is_empty() {
+        return text;
+    }
+    text.push_str("Metadata:\n");
+    for (key, value) in metadata {
+        text.push_str(&format!("  {}: {}\n", key, value));
+    }
+    text
+}
+
+/// `meta set <key> <value>`, `meta unset <key>` and `meta list`: keeps
+/// key/value pairs such as a ticket ID or sprint number on the running
+/// session, which are logged with it when it ends. Returns whether the
+/// session changed.
+pub fn meta(args: &mut Args, working: bool, metadata: &mut BTreeMap<String, String>) -> bool {
+    let action = args.shift().unwrap_or("list".to_string());
+    if action == "list" {
+        match render_metadata(metadata).as_str() {
+            "" => println!("No metadata set"),
+            text => print!("{}", text),
+        }
+        return false;
+    }
+    if !working {
+        error::print(ErrorCode::NoActiveSession, "No job session started");
+        return false;
+    }
+    let key = args.shift().unwrap_or_default();
+    match action.as_str() {
+        "set" => {
+            let value = args.text();
+            if key.is_empty() || value.is_empty() {
+                error::print(ErrorCode::MissingArgument, "Usage: meta set <key> <value>");
+                return false;
+            }
+            if !valid_key(&key) {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!(
+                        "Invalid key: {} (expected letters, digits, '-', '_' or '.')",
+                        key
+                    ),
+                );
+                return false;
+            }
+            cli::info(&format!("Set {} to '{}' on the job session", key, value));
+            metadata.insert(key, value);
+            true
+        }
+        "unset" => {
+            if metadata.remove(&key).is_none() {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("No metadata named '{}'", key),
+                );
+                return false;
+            }
+            cli::info(&format!("Removed {} from the job session", key));
+            true
+        }
+        _ => {
+            error::print(
+                ErrorCode::InvalidArgument,
+                "Usage: meta [set <key> <value>|unset <key>|list]",
+            );
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata() {
+        assert!(valid_key("ticket"));
+        assert!(valid_key("sprint.number"));
+        assert!(!valid_key(""));
+        assert!(!valid_key("ticket id"));
+
+        let metadata = BTreeMap::from([
+            ("sprint".to_string(), "42".to_string()),
+            ("ticket".to_string(), "JIRA-123".to_string()),
+        ]);
+        assert_eq!(
+            render_metadata(&metadata),
+            "Metadata:\n  sprint: 42\n  ticket: JIRA-123\n"
+        );
+        assert_eq!(render_metadata(&BTreeMap::new()), "");
+    }
+}
@@ -0,0 +1,32 @@
+use std::process::Command;
+
+use crate::process;
+
+/// Shows a desktop notification with `notify-send` on Linux, AppleScript on
+/// macOS or a message box on Windows. Failures are ignored, as the message
+/// is also printed.
+pub fn send(title: &str, message: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification \"{}\" with title \"{}\"",
+            message.replace('"', "'"),
+            title.replace('"', "'")
+        ));
+        command
+    } else if cfg!(windows) {
+        let mut command = Command::new("msg");
+        command.arg("*").arg(format!("{}: {}", title, message));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg(title).arg(message);
+        command
+    };
+    process::status(
+        command
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null()),
+    )
+    .ok();
+}
@@ -0,0 +1,41 @@
+//! Running other programs, such as git, curl and gpg. Builds without the
+//! `process` feature, like the core built for wasm32-wasi, cannot start
+//! programs: every call fails as unsupported, and the commands that need a
+//! program report the error instead of running it.
+
+use std::process::{Child, Command, ExitStatus, Output};
+
+#[cfg(not(feature = "process"))]
+fn unsupported(command: &Command) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!(
+            "{} cannot be run, jobclock was built without the process feature",
+            command.get_program().to_string_lossy()
+        ),
+    )
+}
+
+/// Runs `command` and collects its output, like `Command::output`.
+pub fn output(command: &mut Command) -> std::io::Result<Output> {
+    #[cfg(feature = "process")]
+    return command.output();
+    #[cfg(not(feature = "process"))]
+    Err(unsupported(command))
+}
+
+/// Starts `command`, like `Command::spawn`.
+pub fn spawn(command: &mut Command) -> std::io::Result<Child> {
+    #[cfg(feature = "process")]
+    return command.spawn();
+    #[cfg(not(feature = "process"))]
+    Err(unsupported(command))
+}
+
+/// Runs `command` and waits for it to exit, like `Command::status`.
+pub fn status(command: &mut Command) -> std::io::Result<ExitStatus> {
+    #[cfg(feature = "process")]
+    return command.status();
+    #[cfg(not(feature = "process"))]
+    Err(unsupported(command))
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    cli::{self, Args},
+    error::{self, ErrorCode},
+    log::LogEntry,
+    persistent_folder, storage,
+};
+
+/// Finished projects, archived with `project archive`.
+fn archived_file() -> std::path::PathBuf {
+    let mut path = persistent_folder();
+    path.push("archived.json");
+    path
+}
+
+pub fn archived() -> BTreeSet<String> {
+    if !archived_file().exists() {
+        return BTreeSet::new();
+    }
+    let data = std::fs::read_to_string(archived_file()).unwrap();
+    serde_json::from_str(&data).unwrap()
+}
+
+fn save(archived: &BTreeSet<String>) {
+    if !persistent_folder().exists() {
+        storage::create_folder(&persistent_folder()).unwrap();
+    }
+    let data = serde_json::to_string(archived).unwrap();
+    storage::write(&archived_file(), data).unwrap();
+}
+
+/// Whether `entry` was spent only on archived projects. Sessions without
+/// tasks, or with tasks of other projects, are kept in reports.
+pub fn only_archived(archived: &BTreeSet<String>, entry: &LogEntry) -> bool {
+    !entry.tasks.is_empty()
+        && entry.tasks.iter().all(|task| {
+            task.project
+                .as_ref()
+                .is_some_and(|project| archived.contains(project))
+        })
+}
+
+/// `project archive <name>`, `project unarchive <name>` and `project list`.
+pub fn project(args: &mut Args) {
+    let action = args.shift().unwrap_or_default();
+    let name = args.text();
+    let mut archived = archived();
+
+    match action.as_str() {
+        "archive" | "unarchive" if name.is_empty() => error::print(
+            ErrorCode::MissingArgument,
+            &format!("Usage: project {} <name>", action),
+        ),
+        "archive" => {
+            if archived.insert(name.clone()) {
+                save(&archived);
+                cli::info(&format!(
+                    "Project {} archived, use --include-archived to report on it",
+                    name
+                ));
+            } else {
+                println!("Project {} is already archived", name);
+            }
+        }
+        "unarchive" => {
+            if archived.remove(&name) {
+                save(&archived);
+                cli::info(&format!("Project {} unarchived", name));
+            } else {
+                println!("Project {} is not archived", name);
+            }
+        }
+        "list" | "" => {
+            if archived.is_empty() {
+                println!("No archived projects");
+            }
+            for project in archived {
+                println!("  {}", project);
+            }
+        }
+        _ => error::print(
+            ErrorCode::InvalidArgument,
+            &format!("Unknown project action: {}", action),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Task;
+
+    #[test]
+    fn test_only_archived() {
+        let archived = BTreeSet::from(["acme".to_string()]);
+        let task = |project: Option<&str>| {
+            let mut task = Task::new("Work", chrono::Local::now());
+            task.project = project.map(|project| project.to_string());
+            task
+        };
+        let entry = |tasks| LogEntry {
+            tasks,
+            ..Default::default()
+        };
+        assert!(only_archived(
+            &archived,
+            &entry(vec![task(Some("acme")), task(Some("acme"))])
+        ));
+        assert!(!only_archived(
+            &archived,
+            &entry(vec![task(Some("acme")), task(None)])
+        ));
+        assert!(!only_archived(
+            &archived,
+            &entry(vec![task(Some("globex"))])
+        ));
+        assert!(!only_archived(&archived, &entry(vec![])));
+    }
+}
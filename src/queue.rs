@@ -0,0 +1,197 @@
+use crate::{cli, persistent_folder, storage, team};
+
+/// Wait before the first retry of a failed action, doubled after every
+/// further failure.
+const FIRST_DELAY_MINUTES: i64 = 1;
+/// Longest wait between two retries.
+const MAX_DELAY_MINUTES: i64 = 6 * 60;
+
+/// Something sent to another service that can be tried again later.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Upload the log to the team server.
+    Push,
+}
+
+impl Action {
+    fn description(&self) -> &'static str {
+        match self {
+            Action::Push => "Push the log to the team server",
+        }
+    }
+
+    /// Performs the action, returning a message to show when it succeeds.
+    fn run(&self) -> Result<String, String> {
+        match self {
+            Action::Push => team::push_log()
+                .map(|count| format!("Pushed {} job sessions to the team server", count)),
+        }
+    }
+}
+
+/// An action that failed, waiting to be tried again.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+pub struct Pending {
+    pub action: Action,
+    pub queued_at: chrono::DateTime<chrono::Local>,
+    pub attempts: u32,
+    pub next_attempt: chrono::DateTime<chrono::Local>,
+    /// Why the last attempt failed.
+    pub error: String,
+}
+
+impl Pending {
+    /// Records a failed attempt and schedules the next one, waiting twice
+    /// as long as after the attempt before.
+    fn failed(&mut self, error: &str, now: chrono::DateTime<chrono::Local>) {
+        self.attempts += 1;
+        self.error = error.to_string();
+        let minutes = FIRST_DELAY_MINUTES
+            .saturating_mul(1 << self.attempts.saturating_sub(1).min(30))
+            .min(MAX_DELAY_MINUTES);
+        self.next_attempt = now + chrono::Duration::try_minutes(minutes).unwrap();
+    }
+}
+
+fn queue_file() -> std::path::PathBuf {
+    let mut path = persistent_folder();
+    path.push("queue.json");
+    path
+}
+
+pub fn load() -> Vec<Pending> {
+    if !queue_file().exists() {
+        return vec![];
+    }
+    let data = std::fs::read_to_string(queue_file()).unwrap();
+    serde_json::from_str(&data).unwrap()
+}
+
+fn save(queue: &[Pending]) {
+    if queue.is_empty() {
+        if queue_file().exists() {
+            std::fs::remove_file(queue_file()).unwrap();
+        }
+        return;
+    }
+    if !persistent_folder().exists() {
+        storage::create_folder(&persistent_folder()).unwrap();
+    }
+    let data = serde_json::to_string(queue).unwrap();
+    storage::write(&queue_file(), data).unwrap();
+}
+
+/// Adds `action` to `queue` after it failed with `error`. An action that is
+/// already queued is not queued twice, since doing it once is enough.
+fn enqueue(
+    queue: &mut Vec<Pending>,
+    action: Action,
+    error: &str,
+    now: chrono::DateTime<chrono::Local>,
+) {
+    let index = match queue.iter().position(|pending| pending.action == action) {
+        Some(index) => index,
+        None => {
+            queue.push(Pending {
+                action,
+                queued_at: now,
+                attempts: 0,
+                next_attempt: now,
+                error: String::new(),
+            });
+            queue.len() - 1
+        }
+    };
+    queue[index].failed(error, now);
+}
+
+/// Queues `action`, which failed with `error`, to be tried again later.
+pub fn add(action: Action, error: &str, now: chrono::DateTime<chrono::Local>) {
+    let mut queue = load();
+    enqueue(&mut queue, action, error, now);
+    save(&queue);
+}
+
+/// Tries the queued actions that are due again, keeping the ones that fail
+/// for later. Runs before every command, so the queue empties on the first
+/// command after the services can be reached again.
+pub fn retry(now: chrono::DateTime<chrono::Local>) {
+    let mut queue = load();
+    if !queue.iter().any(|pending| pending.next_attempt <= now) {
+        return;
+    }
+    queue.retain_mut(|pending| {
+        if pending.next_attempt > now {
+            return true;
+        }
+        match pending.action.run() {
+            Ok(message) => {
+                cli::info(&format!(
+                    "{} (queued at {})",
+                    message,
+                    pending.queued_at.format("%d-%m-%Y %H:%M:%S")
+                ));
+                false
+            }
+            Err(e) => {
+                pending.failed(&e, now);
+                true
+            }
+        }
+    });
+    save(&queue);
+}
+
+/// Prints the queued actions, with when they are tried next and why they
+/// failed last.
+pub fn status() {
+    let queue = load();
+    if queue.is_empty() {
+        println!("Nothing is waiting to be sent");
+        return;
+    }
+    println!("Pending:");
+    for pending in &queue {
+        println!(
+            "  {} - queued at {}, {} attempts, next at {}",
+            pending.action.description(),
+            pending.queued_at.format("%d-%m-%Y %H:%M:%S"),
+            pending.attempts,
+            pending.next_attempt.format("%d-%m-%Y %H:%M:%S")
+        );
+        println!("    Last error: {}", pending.error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_enqueue_backoff() {
+        let at = |minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, 3, 9, minute, 0)
+                .unwrap()
+        };
+        let mut queue = vec![];
+        enqueue(&mut queue, Action::Push, "Could not resolve host", at(0));
+        assert_eq!(queue[0].next_attempt, at(1));
+        enqueue(&mut queue, Action::Push, "Connection refused", at(1));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue[0].queued_at, at(0));
+        assert_eq!(queue[0].attempts, 2);
+        assert_eq!(queue[0].next_attempt, at(3));
+        assert_eq!(queue[0].error, "Connection refused");
+
+        for _ in 0..40 {
+            queue[0].failed("Connection refused", at(0));
+        }
+        assert_eq!(
+            queue[0].next_attempt - at(0),
+            chrono::Duration::try_hours(6).unwrap()
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{
+    cli::{self, Args},
+    config::Config,
+    error::{self, ErrorCode},
+    format_duration, log,
+    log::LogEntry,
+    rules, Task,
+};
+
+/// How far back a quick session may start, so a mistyped `--ago` cannot log
+/// a session in another century.
+const MAX_DAYS_BACK: i64 = 366;
+
+/// Whether a session of `duration` ending `ago` before now starts within
+/// `MAX_DAYS_BACK` days.
+fn within_reach(duration: chrono::Duration, ago: chrono::Duration) -> bool {
+    duration
+        .checked_add(&ago)
+        .is_some_and(|back| back <= chrono::Duration::try_days(MAX_DAYS_BACK).unwrap())
+}
+
+/// A closed session of `duration` with one task called `name`, ending `ago`
+/// before `now`. The project is `project`, or comes from the categorization
+/// rules, the current directory or the config, like for tasks.
+fn quick_entry(
+    config: &Config,
+    name: &str,
+    duration: chrono::Duration,
+    ago: chrono::Duration,
+    project: Option<String>,
+    now: chrono::DateTime<chrono::Local>,
+) -> LogEntry {
+    let end_time = now - ago;
+    let mut task = Task::new(name, end_time);
+    rules::categorize(&config.rules, &mut task);
+    if project.is_some() {
+        task.project = project;
+    }
+    if task.project.is_none() {
+        task.project = rules::detect_project(&config.paths, &std::env::current_dir().unwrap())
+            .or_else(|| config.project.clone());
+    }
+    LogEntry {
+        start_time: end_time - duration,
+        end_time,
+        tasks: vec![task],
+        ..Default::default()
+    }
+}
+
+/// `quick <duration> <task> [--project <name>] [--ago <duration>]`: logs a
+/// finished session in one command, ending now or `--ago` before now.
+/// `running_since` is the start of the running session, which the logged
+/// session may not overlap.
+pub fn quick(args: &mut Args, running_since: Option<chrono::DateTime<chrono::Local>>) {
+    let project = args.value("--project");
+    let ago = match args.value("--ago") {
+        Some(ago) => match cli::parse_duration(&ago) {
+            Some(ago) if ago >= chrono::Duration::zero() => ago,
+            _ => {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!(
+                        "Invalid duration for --ago: {} (expected like 2h or 1h30m)",
+                        ago
+                    ),
+                );
+                return;
+            }
+        },
+        None => chrono::Duration::zero(),
+    };
+    let duration = args.shift().unwrap_or_default();
+    let name = args.text();
+    if duration.is_empty() || name.is_empty() {
+        error::print(
+            ErrorCode::MissingArgument,
+            "Usage: quick <duration> <task> [--project <name>] [--ago <duration>]",
+        );
+        return;
+    }
+    let Some(duration) =
+        cli::parse_duration(&duration).filter(|duration| *duration > chrono::Duration::zero())
+    else {
+        error::print(
+            ErrorCode::InvalidArgument,
+            &format!(
+                "Invalid duration: {} (expected like 1h30m or 90m)",
+                duration
+            ),
+        );
+        return;
+    };
+    if !within_reach(duration, ago) {
+        error::print(
+            ErrorCode::InvalidArgument,
+            &format!(
+                "ERROR: The session would start more than {} days ago",
+                MAX_DAYS_BACK
+            ),
+        );
+        return;
+    }
+
+    let entry = quick_entry(
+        &Config::current(),
+        &name,
+        duration,
+        ago,
+        project,
+        chrono::Local::now(),
+    );
+    if running_since.is_some_and(|start| start < entry.end_time) {
+        error::print(
+            ErrorCode::CommandFailed,
+            "ERROR: The session overlaps the running job session",
+        );
+        return;
+    }
+    if let Some(existing) = log::load_range(
+        Some(entry.start_time.date_naive()),
+        Some(entry.end_time.date_naive()),
+    )
+    .iter()
+    .find(|existing| existing.start_time < entry.end_time && entry.start_time < existing.end_time)
+    {
+        error::print(
+            ErrorCode::CommandFailed,
+            &format!("ERROR: The session overlaps session {}", existing.id),
+        );
+        return;
+    }
+
+    let (start, end) = (entry.start_time, entry.end_time);
+    match log::append(entry) {
+        Ok(id) => cli::info(&format!(
+            "Job session {} logged from {} to {} ({})",
+            id,
+            start.format("%d-%m-%Y %H:%M"),
+            end.format("%H:%M"),
+            format_duration(end - start)
+        )),
+        Err(e) => error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_quick_entry() {
+        let at = |hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, 3, hour, minute, 0)
+                .unwrap()
+        };
+        let minutes = |minutes| chrono::Duration::try_minutes(minutes).unwrap();
+        let entry = quick_entry(
+            &Config::default(),
+            "incident response",
+            minutes(90),
+            minutes(120),
+            Some("ops".to_string()),
+            at(16, 0),
+        );
+        assert_eq!((entry.start_time, entry.end_time), (at(12, 30), at(14, 0)));
+        assert_eq!(entry.tasks.len(), 1);
+        assert_eq!(entry.tasks[0].created_at, at(14, 0));
+        assert_eq!(entry.tasks[0].project.as_deref(), Some("ops"));
+    }
+
+    #[test]
+    fn test_within_reach() {
+        let hours = |hours| chrono::Duration::try_hours(hours).unwrap();
+        assert!(within_reach(hours(2), hours(24 * 365)));
+        assert!(!within_reach(hours(1), hours(100_000_000)));
+        assert!(!within_reach(hours(24 * 400), hours(0)));
+        assert!(!within_reach(
+            chrono::Duration::try_milliseconds(i64::MAX).unwrap(),
+            hours(1)
+        ));
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::Datelike;
+
+use crate::Task;
+
+/// A task added to the running session every time its scheduled time passes,
+/// such as a daily standup.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct RecurringTask {
+    pub name: String,
+    /// Time of day, `HH:MM`.
+    pub at: String,
+    /// `daily`, `weekdays`, `weekends` or a list of days such as `mon,thu`.
+    #[serde(default = "default_days")]
+    pub days: String,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+fn default_days() -> String {
+    "weekdays".to_string()
+}
+
+/// Whether a task scheduled on `days` runs on `weekday`.
+pub fn runs_on(days: &str, weekday: chrono::Weekday) -> bool {
+    let weekend = matches!(weekday, chrono::Weekday::Sat | chrono::Weekday::Sun);
+    match days.trim().to_lowercase().as_str() {
+        "daily" => true,
+        "weekdays" => !weekend,
+        "weekends" => weekend,
+        days => days
+            .split(',')
+            .filter_map(|day| day.trim().parse::<chrono::Weekday>().ok())
+            .any(|day| day == weekday),
+    }
+}
+
+/// The recurring tasks scheduled from `from` up to `to`, created at their
+/// scheduled time and marked as recurring.
+pub fn due(
+    recurring: &[RecurringTask],
+    from: chrono::DateTime<chrono::Local>,
+    to: chrono::DateTime<chrono::Local>,
+) -> Vec<Task> {
+    let mut tasks = vec![];
+    let mut date = from.date_naive();
+    while date <= to.date_naive() {
+        for task in recurring {
+            if !runs_on(&task.days, date.weekday()) {
+                continue;
+            }
+            let Ok(time) = chrono::NaiveTime::parse_from_str(&task.at, "%H:%M") else {
+                continue;
+            };
+            let Some(at) = date
+                .and_time(time)
+                .and_local_timezone(chrono::Local)
+                .earliest()
+            else {
+                continue;
+            };
+            if at >= from && at <= to {
+                let mut due = Task::new(&task.name, at);
+                due.project = task.project.clone();
+                due.tags = task.tags.clone();
+                due.recurring = true;
+                tasks.push(due);
+            }
+        }
+        date = date.succ_opt().unwrap();
+    }
+    tasks.sort_by_key(|task| task.created_at);
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_due() {
+        let standup = RecurringTask {
+            name: "standup".to_string(),
+            at: "09:15".to_string(),
+            days: default_days(),
+            project: None,
+            tags: vec![],
+        };
+        let review = RecurringTask {
+            name: "review".to_string(),
+            at: "16:00".to_string(),
+            days: "fri, sat".to_string(),
+            ..standup.clone()
+        };
+        // Friday 15 March 2024 08:00 until Saturday 12:00.
+        let from = chrono::Local
+            .with_ymd_and_hms(2024, 3, 15, 8, 0, 0)
+            .unwrap();
+        let to = chrono::Local
+            .with_ymd_and_hms(2024, 3, 16, 12, 0, 0)
+            .unwrap();
+
+        let tasks = due(&[standup, review], from, to);
+        let names = tasks
+            .iter()
+            .map(|task| task.name.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(names, vec!["standup", "review"]);
+        assert!(tasks.iter().all(|task| task.recurring));
+        assert_eq!(
+            tasks[0].created_at,
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 15, 9, 15, 0)
+                .unwrap()
+        );
+    }
+}
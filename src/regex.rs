@@ -0,0 +1,559 @@
+//! A small regular expression engine, matching with a Pike VM so that
+//! matching takes time in proportion to the length of the text.
+//!
+//! Supports literals, `.`, character classes (`[a-z]`, `[^0-9]`), the escapes
+//! `\d \w \s \D \W \S`, groups, alternation, the quantifiers `* + ? {n} {n,}
+//! {n,m}` (with lazy `?` variants), the anchors `^` and `$`, and a leading
+//! `(?i)` for case-insensitive matching. This covers what categorization
+//! rules need without pulling in a dependency.
+
+/// The largest count in `{n,m}`.
+const MAX_REPEAT: usize = 1000;
+
+/// The most instructions a pattern may compile to. A counted repeat is
+/// compiled to a copy of what it repeats per count, and every character of
+/// the text may run every instruction.
+const MAX_PROGRAM: usize = 10_000;
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Char(char),
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            ClassItem::Char(x) => x == c,
+            ClassItem::Range(from, to) => from <= c && c <= to,
+            ClassItem::Digit(negated) => c.is_ascii_digit() != negated,
+            ClassItem::Word(negated) => (c.is_alphanumeric() || c == '_') != negated,
+            ClassItem::Space(negated) => c.is_whitespace() != negated,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    Group(Box<Node>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat(Box<Node>, usize, Option<usize>, bool),
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn parse_alternate(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.parse_concat()?];
+        while self.chars.peek() == Some(&'|') {
+            self.chars.next();
+            branches.push(self.parse_concat()?);
+        }
+        if branches.len() == 1 {
+            Ok(branches.pop().unwrap())
+        } else {
+            Ok(Node::Alternate(branches))
+        }
+    }
+
+    fn parse_concat(&mut self) -> Result<Node, String> {
+        let mut items = vec![];
+        while let Some(&c) = self.chars.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            items.push(self.parse_quantifier(atom)?);
+        }
+        match items.len() {
+            0 => Ok(Node::Empty),
+            1 => Ok(items.pop().unwrap()),
+            _ => Ok(Node::Concat(items)),
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, String> {
+        match self.chars.next().unwrap() {
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '(' => {
+                let inner = self.parse_alternate()?;
+                if self.chars.next() != Some(')') {
+                    return Err("missing closing parenthesis".to_string());
+                }
+                Ok(Node::Group(Box::new(inner)))
+            }
+            '[' => self.parse_class(),
+            '\\' => match self.parse_escape()? {
+                ClassItem::Char(c) => Ok(Node::Char(c)),
+                item => Ok(Node::Class(vec![item], false)),
+            },
+            c @ ('*' | '+' | '?') => Err(format!("nothing to repeat before '{}'", c)),
+            c => Ok(Node::Char(c)),
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<ClassItem, String> {
+        match self.chars.next() {
+            Some('d') => Ok(ClassItem::Digit(false)),
+            Some('D') => Ok(ClassItem::Digit(true)),
+            Some('w') => Ok(ClassItem::Word(false)),
+            Some('W') => Ok(ClassItem::Word(true)),
+            Some('s') => Ok(ClassItem::Space(false)),
+            Some('S') => Ok(ClassItem::Space(true)),
+            Some('n') => Ok(ClassItem::Char('\n')),
+            Some('t') => Ok(ClassItem::Char('\t')),
+            Some(c) => Ok(ClassItem::Char(c)),
+            None => Err("trailing backslash".to_string()),
+        }
+    }
+
+    fn parse_class(&mut self) -> Result<Node, String> {
+        let mut items = vec![];
+        let negated = self.chars.peek() == Some(&'^');
+        if negated {
+            self.chars.next();
+        }
+        loop {
+            let item = match self.chars.next() {
+                None => return Err("missing closing bracket".to_string()),
+                Some(']') if !items.is_empty() => break,
+                Some('\\') => self.parse_escape()?,
+                Some(c) => ClassItem::Char(c),
+            };
+            if let ClassItem::Char(from) = item {
+                let mut lookahead = self.chars.clone();
+                if lookahead.next() == Some('-') && lookahead.peek().is_some_and(|&c| c != ']') {
+                    self.chars.next();
+                    let to = self.chars.next().unwrap();
+                    if to < from {
+                        return Err(format!("invalid range {}-{}", from, to));
+                    }
+                    items.push(ClassItem::Range(from, to));
+                    continue;
+                }
+            }
+            items.push(item);
+        }
+        Ok(Node::Class(items, negated))
+    }
+
+    /// Reads a repetition count, if there is one.
+    fn parse_number(&mut self) -> Result<Option<usize>, String> {
+        let mut digits = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            digits.push(c);
+            self.chars.next();
+        }
+        if digits.is_empty() {
+            return Ok(None);
+        }
+        match digits.parse() {
+            Ok(count) if count <= MAX_REPEAT => Ok(Some(count)),
+            _ => Err("invalid repetition count".to_string()),
+        }
+    }
+
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node, String> {
+        let (min, max) = match self.chars.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                let mut lookahead = self.chars.clone();
+                lookahead.next();
+                if !lookahead.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    return Ok(atom);
+                }
+                self.chars.next();
+                let min = self.parse_number()?.unwrap_or_default();
+                let max = if self.chars.peek() == Some(&',') {
+                    self.chars.next();
+                    self.parse_number()?
+                } else {
+                    Some(min)
+                };
+                if self.chars.next() != Some('}') {
+                    return Err("missing closing brace".to_string());
+                }
+                if max.is_some_and(|max| max < min) {
+                    return Err("invalid repetition range".to_string());
+                }
+                let lazy = self.chars.peek() == Some(&'?');
+                if lazy {
+                    self.chars.next();
+                }
+                return Ok(Node::Repeat(Box::new(atom), min, max, !lazy));
+            }
+            _ => return Ok(atom),
+        };
+        if matches!(atom, Node::Start | Node::End) {
+            return Err("nothing to repeat".to_string());
+        }
+        self.chars.next();
+        let lazy = self.chars.peek() == Some(&'?');
+        if lazy {
+            self.chars.next();
+        }
+        Ok(Node::Repeat(Box::new(atom), min, max, !lazy))
+    }
+}
+
+/// An instruction of a compiled pattern.
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    /// Continues at both instructions, preferring the first.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn emit(&mut self, inst: Inst) -> Result<usize, String> {
+        if self.program.len() >= MAX_PROGRAM {
+            return Err("pattern is too large".to_string());
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    /// Points the split or jump at `at` to `to`, in place of its
+    /// placeholder target.
+    fn patch(&mut self, at: usize, to: usize) {
+        match &mut self.program[at] {
+            Inst::Split(first, _) if *first == usize::MAX => *first = to,
+            Inst::Split(_, second) => *second = to,
+            Inst::Jump(target) => *target = to,
+            _ => unreachable!(),
+        }
+    }
+
+    /// A split that prefers the next instruction when `greedy`, with the
+    /// other target left to patch.
+    fn emit_split(&mut self, greedy: bool) -> Result<usize, String> {
+        let next = self.program.len() + 1;
+        if greedy {
+            self.emit(Inst::Split(next, usize::MAX))
+        } else {
+            self.emit(Inst::Split(usize::MAX, next))
+        }
+    }
+
+    fn compile(&mut self, node: &Node) -> Result<(), String> {
+        match node {
+            Node::Empty => {}
+            Node::Char(c) => {
+                self.emit(Inst::Char(*c))?;
+            }
+            Node::Any => {
+                self.emit(Inst::Any)?;
+            }
+            Node::Class(items, negated) => {
+                self.emit(Inst::Class(items.clone(), *negated))?;
+            }
+            Node::Start => {
+                self.emit(Inst::Start)?;
+            }
+            Node::End => {
+                self.emit(Inst::End)?;
+            }
+            Node::Group(inner) => self.compile(inner)?,
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.compile(node)?;
+                }
+            }
+            Node::Alternate(branches) => {
+                let mut jumps = vec![];
+                for (i, branch) in branches.iter().enumerate() {
+                    if i + 1 == branches.len() {
+                        self.compile(branch)?;
+                        break;
+                    }
+                    let split = self.emit_split(true)?;
+                    self.compile(branch)?;
+                    jumps.push(self.emit(Inst::Jump(usize::MAX))?);
+                    self.patch(split, self.program.len());
+                }
+                for jump in jumps {
+                    self.patch(jump, self.program.len());
+                }
+            }
+            Node::Repeat(inner, min, max, greedy) => {
+                for _ in 0..*min {
+                    self.compile(inner)?;
+                }
+                match max {
+                    None => {
+                        let split = self.emit_split(*greedy)?;
+                        self.compile(inner)?;
+                        self.emit(Inst::Jump(split))?;
+                        self.patch(split, self.program.len());
+                    }
+                    Some(max) => {
+                        let mut splits = vec![];
+                        for _ in *min..*max {
+                            splits.push(self.emit_split(*greedy)?);
+                            self.compile(inner)?;
+                        }
+                        for split in splits {
+                            self.patch(split, self.program.len());
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The threads of the matcher at one position in the text, by priority:
+/// the instruction each waits at and where its match started.
+struct Threads {
+    list: Vec<(usize, usize)>,
+    added: Vec<bool>,
+}
+
+impl Threads {
+    fn new(size: usize) -> Threads {
+        Threads {
+            list: vec![],
+            added: vec![false; size],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+        self.added.fill(false);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Vec<Inst>,
+    case_insensitive: bool,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let (pattern, case_insensitive) = match pattern.strip_prefix("(?i)") {
+            Some(rest) => (rest, true),
+            None => (pattern, false),
+        };
+        let mut parser = Parser {
+            chars: pattern.chars().peekable(),
+        };
+        let node = parser.parse_alternate()?;
+        if parser.chars.next().is_some() {
+            return Err("unmatched closing parenthesis".to_string());
+        }
+        let mut compiler = Compiler { program: vec![] };
+        compiler.compile(&node)?;
+        compiler.emit(Inst::Match)?;
+        Ok(Regex {
+            program: compiler.program,
+            case_insensitive,
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find(text).is_some()
+    }
+
+    /// Returns the byte range of the leftmost match in `text`.
+    pub fn find(&self, text: &str) -> Option<(usize, usize)> {
+        self.find_at(text, 0)
+    }
+
+    /// Replaces every non-overlapping match in `text` with `replacement`.
+    pub fn replace_all(&self, text: &str, replacement: &str) -> String {
+        let mut result = String::new();
+        let mut position = 0;
+        while let Some((start, end)) = self.find_at(text, position) {
+            result.push_str(&text[position..start]);
+            result.push_str(replacement);
+            if end > start {
+                position = end;
+                continue;
+            }
+            // An empty match replaces nothing, so move on by one character.
+            match text[end..].chars().next() {
+                Some(c) => {
+                    result.push(c);
+                    position = end + c.len_utf8();
+                }
+                None => return result,
+            }
+        }
+        result.push_str(&text[position..]);
+        result
+    }
+
+    /// Runs the threads of every match starting at or after `start` in
+    /// step, one character at a time, so a pattern never makes the text be
+    /// read more than once. Threads are kept in the order a backtracking
+    /// matcher would try them, which picks the same match.
+    fn find_at(&self, text: &str, start: usize) -> Option<(usize, usize)> {
+        let offsets = text
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(text.len()))
+            .collect::<Vec<usize>>();
+        let chars = text.chars().collect::<Vec<char>>();
+        let first = offsets.iter().position(|&offset| offset >= start)?;
+
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut found = None;
+        for position in first..offsets.len() {
+            if found.is_none() {
+                self.add_thread(&mut current, 0, position, position, &chars);
+            } else if current.list.is_empty() {
+                break;
+            }
+            for &(pc, begin) in &current.list {
+                let consumed = chars
+                    .get(position)
+                    .is_some_and(|&c| match &self.program[pc] {
+                        Inst::Char(expected) => self.char_matches(*expected, c),
+                        Inst::Any => c != '\n',
+                        Inst::Class(items, negated) => self.class_matches(items, *negated, c),
+                        _ => false,
+                    });
+                if consumed {
+                    self.add_thread(&mut next, pc + 1, position + 1, begin, &chars);
+                } else if let Inst::Match = self.program[pc] {
+                    // The threads after this one would only match in a way
+                    // a backtracking matcher never gets to.
+                    found = Some((begin, position));
+                    break;
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+        found.map(|(begin, end)| (offsets[begin], offsets[end]))
+    }
+
+    /// Adds a thread at `pc`, following splits, jumps and anchors until the
+    /// threads wait at an instruction that reads a character or matches.
+    fn add_thread(
+        &self,
+        threads: &mut Threads,
+        pc: usize,
+        position: usize,
+        begin: usize,
+        text: &[char],
+    ) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if threads.added[pc] {
+                continue;
+            }
+            threads.added[pc] = true;
+            match self.program[pc] {
+                Inst::Split(first, second) => {
+                    stack.push(second);
+                    stack.push(first);
+                }
+                Inst::Jump(target) => stack.push(target),
+                Inst::Start if position == 0 => stack.push(pc + 1),
+                Inst::End if position == text.len() => stack.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                _ => threads.list.push((pc, begin)),
+            }
+        }
+    }
+
+    fn char_matches(&self, expected: char, actual: char) -> bool {
+        if self.case_insensitive {
+            expected.to_lowercase().eq(actual.to_lowercase())
+        } else {
+            expected == actual
+        }
+    }
+
+    fn class_matches(&self, items: &[ClassItem], negated: bool, c: char) -> bool {
+        let matches = |c: char| items.iter().any(|item| item.matches(c));
+        let found = if self.case_insensitive {
+            matches(c) || c.to_lowercase().any(matches) || c.to_uppercase().any(matches)
+        } else {
+            matches(c)
+        };
+        found != negated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regex_matching() {
+        let regex = Regex::new(r"JIRA-\d+").unwrap();
+        assert!(regex.is_match("Fix login (JIRA-123)"));
+        assert!(!regex.is_match("Fix login (JIRA-)"));
+        assert_eq!(regex.find("see JIRA-42 and JIRA-7"), Some((4, 11)));
+
+        let regex = Regex::new(r"(?i)^(fix|bug)[: ]+\w").unwrap();
+        assert!(regex.is_match("Fix: parser"));
+        assert!(regex.is_match("BUG crash"));
+        assert!(!regex.is_match("a fix"));
+
+        assert!(Regex::new(r"colou?r").unwrap().is_match("color"));
+        assert!(Regex::new(r"^[a-c]{2,3}$").unwrap().is_match("abc"));
+        assert!(!Regex::new(r"^[a-c]{2,3}$").unwrap().is_match("abcd"));
+        let regex = Regex::new("(?i)colou?r").unwrap();
+        assert_eq!(
+            regex.replace_all("Colour or color", "shade"),
+            "shade or shade"
+        );
+        assert_eq!(Regex::new("x*").unwrap().replace_all("ab", "-"), "-a-b-");
+        assert!(Regex::new("a{99999999999999999999}").is_err());
+        assert!(Regex::new("a{2,1001}").is_err());
+        assert!(Regex::new("((a?){100}){100}").is_err());
+        let regex = Regex::new("^a{1000}$").unwrap();
+        assert!(regex.is_match(&"a".repeat(1000)));
+        assert!(!regex.is_match(&"a".repeat(999)));
+        assert!(Regex::new("(a").is_err());
+        assert!(Regex::new("*a").is_err());
+    }
+
+    #[test]
+    fn test_regex_long_text() {
+        let text = format!("{}x", "a".repeat(100_000));
+        assert_eq!(Regex::new(".*x").unwrap().find(&text), Some((0, 100_001)));
+        assert_eq!(Regex::new("a+?").unwrap().find(&text), Some((0, 1)));
+        assert!(!Regex::new("(a+)+b").unwrap().is_match(&text));
+        assert_eq!(
+            Regex::new("(a|ab)(c|bcd)").unwrap().find("abcd"),
+            Some((0, 4))
+        );
+        assert!(Regex::new("(a*)*$").unwrap().is_match("aab"));
+    }
+}
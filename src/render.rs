@@ -0,0 +1,595 @@
+use crate::{
+    activity, attach,
+    color::ProjectColors,
+    config::Config,
+    currency::{self, Converter},
+    expense, format_duration,
+    log::LogEntry,
+    meeting, meta,
+    report::{self, Day},
+    summary,
+    table::{Style, Table},
+    timer,
+    week::Weeks,
+    Session,
+};
+
+/// The timeline, total time, summary, timers, meetings, expenses and goal of a
+/// session, as shown when it ends.
+pub fn session(entry: &LogEntry, format: &summary::SummaryFormat) -> String {
+    let mut text = "Timeline:\n".to_string();
+    text.push_str(&format!(
+        "  {} - Begin job session\n",
+        entry.start_time.format("%d-%m-%Y %H:%M:%S")
+    ));
+    for task in &entry.tasks {
+        text.push_str(&format!(
+            "  {} - Task: {}{}\n",
+            task.created_at.format("%d-%m-%Y %H:%M:%S"),
+            task.name,
+            task.labels()
+        ));
+        for comment in &task.comments {
+            text.push_str(&format!("                        > {}\n", comment));
+        }
+    }
+    text.push_str(&format!(
+        "  {} - End job session\n",
+        entry.end_time.format("%d-%m-%Y %H:%M:%S")
+    ));
+
+    let duration = entry.duration();
+    text.push_str(&format!("Total time: {}\n", format_duration(duration)));
+
+    text.push_str(&summary::render_summary(
+        entry.start_time,
+        &entry.tasks,
+        format,
+    ));
+    if let Some(summary) = &entry.summary {
+        text.push_str(&format!("{}\n", summary));
+    }
+    text.push_str(&timer::render_timers(&entry.timers, entry.end_time));
+    text.push_str(&meeting::render_meetings(&entry.meetings, entry.end_time));
+    text.push_str(&attach::render_attachments(&entry.attachments));
+    text.push_str(&meta::render_metadata(&entry.metadata));
+    if !entry.expenses.is_empty() {
+        let total = entry
+            .expenses
+            .iter()
+            .map(|expense| expense.amount)
+            .sum::<f64>();
+        text.push_str(&format!("Expenses: {:.2}\n", total));
+    }
+
+    let hours = duration.num_seconds() as f64 / 3600.0;
+    text.push_str(&format!("Hours: {:.2}\n", hours));
+
+    if let Some(goal) = &entry.goal {
+        let met = match entry.goal_met {
+            Some(true) => "met",
+            Some(false) => "not met",
+            None => "not answered",
+        };
+        text.push_str(&format!("Goal: {} ({})\n", goal, met));
+    }
+    text
+}
+
+/// Characters per line of a receipt, as printed by 58 mm thermal printers.
+pub const RECEIPT_WIDTH: usize = 40;
+
+/// `left` and `right` on one receipt line of `width`, with `left` wrapped
+/// onto lines of its own when both do not fit.
+fn receipt_line(left: &str, right: &str, width: usize) -> String {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in left.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    let gap = width.saturating_sub(line.chars().count() + right.chars().count());
+    if gap == 0 && !right.is_empty() {
+        lines.push(std::mem::take(&mut line));
+    }
+    let gap = width.saturating_sub(line.chars().count() + right.chars().count());
+    lines.push(
+        format!("{}{}{}", line, " ".repeat(gap), right)
+            .trim_end()
+            .to_string(),
+    );
+    lines.join("\n") + "\n"
+}
+
+/// `text` centered on a receipt line of `width`.
+fn receipt_center(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(text.chars().count()) / 2;
+    format!("{}{}\n", " ".repeat(padding), text)
+}
+
+/// A session as a narrow receipt for thermal printers: the date and times,
+/// a line per task with its time, the time after the last task, the total
+/// and the goal.
+pub fn receipt(entry: &LogEntry, width: usize) -> String {
+    let double = "=".repeat(width) + "\n";
+    let single = "-".repeat(width) + "\n";
+    let mut text = double.clone();
+    text.push_str(&receipt_center("JOBCLOCK", width));
+    text.push_str(&receipt_center("Time receipt", width));
+    text.push_str(&double);
+    text.push_str(&receipt_line(
+        "Date",
+        &entry.start_time.format("%d-%m-%Y").to_string(),
+        width,
+    ));
+    text.push_str(&receipt_line(
+        "Start",
+        &entry.start_time.format("%H:%M:%S").to_string(),
+        width,
+    ));
+    text.push_str(&receipt_line(
+        "End",
+        &entry.end_time.format("%H:%M:%S").to_string(),
+        width,
+    ));
+    text.push_str(&single);
+    let mut logged = chrono::Duration::zero();
+    for (task, duration) in summary::task_durations(entry.start_time, &entry.tasks) {
+        text.push_str(&receipt_line(&task.name, &format_duration(duration), width));
+        if let Some(project) = &task.project {
+            text.push_str(&format!("  [{}]\n", project));
+        }
+        logged += duration;
+    }
+    if logged < entry.duration() {
+        text.push_str(&receipt_line(
+            "Unlogged work",
+            &format_duration(entry.duration() - logged),
+            width,
+        ));
+    }
+    text.push_str(&single);
+    text.push_str(&receipt_line(
+        "TOTAL",
+        &format_duration(entry.duration()),
+        width,
+    ));
+    text.push_str(&receipt_line(
+        "Hours",
+        &format!("{:.2}", entry.duration().num_seconds() as f64 / 3600.0),
+        width,
+    ));
+    if let Some(goal) = &entry.goal {
+        let met = match entry.goal_met {
+            Some(true) => "met",
+            Some(false) => "not met",
+            None => "not answered",
+        };
+        text.push_str(&single);
+        text.push_str(&receipt_line(
+            &format!("Goal: {} ({})", goal, met),
+            "",
+            width,
+        ));
+    }
+    text.push_str(&double);
+    text.push_str(&receipt_center("Thank you for your work!", width));
+    text
+}
+
+/// What `status` shows besides the session itself, read before rendering so
+/// the output only depends on its arguments.
+pub struct StatusInfo {
+    pub now: chrono::DateTime<chrono::Local>,
+    pub weeks: Weeks,
+    /// The sessions logged so far this week.
+    pub entries: Vec<LogEntry>,
+    /// The session that ended last.
+    pub last: Option<LogEntry>,
+    /// The time worked without a break and whether a break is due, when
+    /// break reminders are configured.
+    pub without_break: Option<(chrono::Duration, bool)>,
+    pub verbose: bool,
+    pub colors: ProjectColors,
+}
+
+/// The state of `session` and the hours worked this week.
+pub fn status(session: &Session, info: &StatusInfo) -> String {
+    let mut text = String::new();
+    if session.working {
+        text.push_str(&format!(
+            "Job session started at {}\n",
+            session.start_time.format("%d-%m-%Y %H:%M:%S")
+        ));
+        if session.is_stale(info.now) {
+            text.push_str("WARNING: This job session has been open for more than 24 hours\n");
+        }
+        if let Some(goal) = &session.goal {
+            text.push_str(&format!("Goal: {}\n", goal));
+        }
+        if let Some(project) = &session.project {
+            text.push_str(&format!(
+                "Project: {}\n",
+                info.colors.paint(Some(project), project)
+            ));
+        }
+        if let Some(heartbeat) = session.last_heartbeat {
+            text.push_str(&format!(
+                "Last heartbeat: {}\n",
+                heartbeat.format("%d-%m-%Y %H:%M:%S")
+            ));
+        }
+
+        text.push_str("Tasks:\n");
+        if session.tasks.is_empty() {
+            text.push_str("  No tasks added\n");
+        }
+        let tasks = session.get_tasks_clone_sorted();
+        for (task, line) in tasks.iter().zip(session.task_lines(&info.colors)) {
+            text.push_str(&format!("  {}\n", line));
+            if info.verbose {
+                for comment in &task.comments {
+                    text.push_str(&format!("     > {}\n", comment));
+                }
+            }
+        }
+
+        text.push_str(&timer::render_timers(&session.timers, info.now));
+        text.push_str(&meeting::render_meetings(&session.meetings, info.now));
+        text.push_str(&attach::render_attachments(&session.attachments));
+        text.push_str(&meta::render_metadata(&session.metadata));
+        if info.verbose {
+            if let Some(hints) = activity::hints(&session.activity) {
+                text.push_str(&format!("Activity: {}\n", hints));
+            }
+        }
+
+        let duration = info.now - session.start_time;
+        text.push_str(&format!("Total time: {}\n", format_duration(duration)));
+        if let Some((worked, due)) = info.without_break {
+            text.push_str(&format!("Without a break: {}\n", format_duration(worked)));
+            if due {
+                text.push_str("WARNING: Time for a break\n");
+            }
+        }
+    } else {
+        text.push_str("No job session started\n");
+        if let Some(last) = &info.last {
+            text.push_str(&format!(
+                "Last session ended {} ({})\n",
+                last.end_time.format("%d-%m-%Y %H:%M:%S"),
+                format_duration(last.duration())
+            ));
+        }
+        let today = report::days(&info.entries)
+            .iter()
+            .find(|day| day.date == info.now.date_naive())
+            .map(|day| day.worked())
+            .unwrap_or(chrono::Duration::zero());
+        text.push_str(&format!("Today: {}\n", format_duration(today)));
+        if !session.tasks.is_empty() {
+            text.push_str("Unfinished tasks to carry over:\n");
+            for task in &session.tasks {
+                text.push_str(&format!("  [ ] {}{}\n", task.name, task.labels()));
+            }
+        }
+    }
+
+    let running_since = session.working.then_some(session.start_time);
+    text.push_str(&report::week_summary(
+        &info.entries,
+        running_since,
+        info.now,
+        info.weeks,
+    ));
+    text.push('\n');
+    text
+}
+
+/// How `report` shows the logged days.
+pub struct ReportOptions {
+    pub style: Style,
+    /// Combine tasks with the same name and project.
+    pub merge: bool,
+    /// Show every task with the lines touched in it.
+    pub verbose: bool,
+    pub colors: ProjectColors,
+}
+
+/// The lines `report --copy` puts on the clipboard: `header`, the tasks and
+/// the total time.
+pub fn report_summary(header: &str, days: &[Day], options: &ReportOptions) -> Vec<String> {
+    let mut lines = vec![header.to_string()];
+    let tasks = report::report_tasks(days, options.merge);
+    if !tasks.is_empty() {
+        lines.push("Tasks:".to_string());
+        let mut table = Table::new(&["Task", "Project", "Time", "Times"])
+            .truncate(0, 50)
+            .right(2)
+            .right(3);
+        for task in tasks {
+            table.row(vec![
+                format!(
+                    "{}{}",
+                    task.name,
+                    if task.recurring { " (recurring)" } else { "" }
+                ),
+                options.colors.paint(
+                    task.project.as_deref(),
+                    task.project.as_deref().unwrap_or_default(),
+                ),
+                format_duration(task.duration),
+                task.count.to_string(),
+            ]);
+        }
+        lines.extend(table.render(options.style));
+    }
+    let total = days
+        .iter()
+        .fold(chrono::Duration::zero(), |total, day| total + day.worked());
+    lines.push(format!("Total time: {}", format_duration(total)));
+    lines.push(format!("Hours: {:.2}", total.num_seconds() as f64 / 3600.0));
+    lines
+}
+
+/// The body of `report` for `days`: the time and earnings per day, the
+/// tasks, the totals, the meetings, the expenses and the activity.
+pub fn report(
+    header: &str,
+    days: &[Day],
+    config: &Config,
+    options: &ReportOptions,
+) -> Result<String, String> {
+    let converter = Converter::new(config);
+    let mut earned = 0.0;
+    let mut lines_touched = 0;
+    let mut day_table = match config.rates {
+        Some(_) => Table::new(&["Day", "Time", "Earned"]).right(1).right(2),
+        None => Table::new(&["Day", "Time"]).right(1),
+    };
+    let mut task_stats = Table::new(&["Day", "At", "Lines touched", "Task"])
+        .right(2)
+        .truncate(3, 60);
+    let mut comments = vec![];
+    for day in days {
+        let mut row = vec![
+            day.date.format("%a %d-%m-%Y").to_string(),
+            format_duration(day.worked()),
+        ];
+        if let Some(rates) = &config.rates {
+            let mut day_earned = 0.0;
+            for (project, amount) in day
+                .sessions
+                .iter()
+                .flat_map(|session| rates.session(session))
+            {
+                let currency = currency::project_currency(config, project.as_deref());
+                day_earned += converter.to_home(amount, currency.as_deref())?;
+            }
+            earned += day_earned;
+            row.push(converter.format(day_earned));
+        }
+        day_table.row(row);
+        for task in day.sessions.iter().flat_map(|session| &session.tasks) {
+            let lines = task.stats.map(|stats| stats.lines_touched());
+            lines_touched += lines.unwrap_or(0);
+            task_stats.row(vec![
+                day.date.format("%d-%m-%Y").to_string(),
+                task.created_at.format("%H:%M:%S").to_string(),
+                lines.map_or("-".to_string(), |lines| lines.to_string()),
+                format!("{}{}", task.name, task.labels()),
+            ]);
+            for comment in &task.comments {
+                comments.push(format!(
+                    "  {} {} {}: {}",
+                    day.date.format("%d-%m-%Y"),
+                    task.created_at.format("%H:%M:%S"),
+                    task.name,
+                    comment
+                ));
+            }
+        }
+    }
+
+    let mut lines = vec![header.to_string()];
+    lines.extend(day_table.render(options.style));
+    if options.verbose && !task_stats.is_empty() {
+        lines.extend(task_stats.render(options.style));
+    }
+    if options.verbose && !comments.is_empty() {
+        lines.push("Comments:".to_string());
+        lines.extend(comments);
+    }
+    lines.extend(report_summary(header, days, options).into_iter().skip(1));
+    if config.rates.is_some() {
+        lines.push(format!("Earnings: {}", converter.format(earned)));
+    }
+    let branches = report::branch_totals(days);
+    if !branches.is_empty() {
+        lines.push("Branches:".to_string());
+        let mut table = Table::new(&["Branch", "Time"]).truncate(0, 50).right(1);
+        for (branch, total) in branches {
+            table.row(vec![branch, format_duration(total)]);
+        }
+        lines.extend(table.render(options.style));
+    }
+    let mut text = lines.join("\n") + "\n";
+    let total = days
+        .iter()
+        .fold(chrono::Duration::zero(), |total, day| total + day.worked());
+    text.push_str(&meeting::render_totals(days, total));
+
+    let (expenses_text, expenses) = expense::render_expenses(days, config)?;
+    text.push_str(&expenses_text);
+    if config.rates.is_some() && expenses > 0.0 {
+        text.push_str(&format!(
+            "Earnings and expenses: {}\n",
+            converter.format(earned + expenses)
+        ));
+    }
+    if options.verbose {
+        text.push_str(&format!("Lines touched: {}\n", lines_touched));
+    }
+    let mut samples = std::collections::BTreeMap::new();
+    for session in days.iter().flat_map(|day| &day.sessions) {
+        activity::add(&mut samples, &session.activity);
+    }
+    if let Some(hints) = activity::hints(&samples) {
+        text.push_str(&format!("Activity: {}\n", hints));
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Task;
+    use chrono::TimeZone;
+
+    /// Compares `actual` with `golden/<name>`. Run the tests with
+    /// `UPDATE_GOLDEN=1` to write the current output instead, after checking
+    /// that the change is wanted.
+    fn assert_golden(name: &str, actual: &str) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("golden")
+            .join(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            actual == expected,
+            "{} changed, run the tests with UPDATE_GOLDEN=1 to accept it\n\
+             --- expected\n{}--- actual\n{}",
+            path.display(),
+            expected,
+            actual
+        );
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> chrono::DateTime<chrono::Local> {
+        chrono::Local
+            .with_ymd_and_hms(2024, 6, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn entry(id: u64, day: u32, from: u32, to: u32) -> LogEntry {
+        let mut fix = Task::new("Fix the parser", at(day, from, 30));
+        fix.project = Some("acme".to_string());
+        fix.tags = vec!["bug".to_string(), "branch:fix/parser".to_string()];
+        fix.comments = vec!["Took longer due to flaky CI".to_string()];
+        LogEntry {
+            id,
+            start_time: at(day, from, 0),
+            end_time: at(day, to, 0),
+            tasks: vec![fix, Task::new("Review", at(day, to - 1, 0))],
+            goal: Some("Ship the parser".to_string()),
+            goal_met: Some(true),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_golden() {
+        let config = Config::default();
+        assert_golden("end.txt", &session(&entry(1, 3, 9, 12), &config.summary));
+        assert_golden("receipt.txt", &receipt(&entry(1, 3, 9, 12), RECEIPT_WIDTH));
+
+        let mut running = Session::new();
+        running.working = true;
+        running.start_time = at(5, 8, 0);
+        running.project = Some("acme".to_string());
+        running.tasks = vec![Task::new("Write docs", at(5, 8, 15))];
+        let info = StatusInfo {
+            now: at(5, 10, 45),
+            weeks: Weeks::default(),
+            entries: vec![entry(1, 3, 9, 12), entry(2, 4, 8, 16)],
+            last: Some(entry(2, 4, 8, 16)),
+            without_break: Some((chrono::Duration::try_minutes(150).unwrap(), true)),
+            verbose: false,
+            colors: ProjectColors::default(),
+        };
+        assert_golden("status.txt", &status(&running, &info));
+        assert_golden("status-idle.txt", &status(&Session::new(), &info));
+
+        let days = report::days(&[entry(1, 3, 9, 12), entry(2, 4, 8, 16)]);
+        for (name, style) in [
+            ("report.txt", Style::Aligned),
+            ("report-plain.txt", Style::Plain),
+        ] {
+            let options = ReportOptions {
+                style,
+                merge: true,
+                verbose: true,
+                colors: ProjectColors::default(),
+            };
+            let text = report(
+                "Report for 03-06-2024 - 04-06-2024",
+                &days,
+                &config,
+                &options,
+            );
+            assert_golden(name, &text.unwrap());
+        }
+    }
+
+    #[test]
+    fn test_receipt_line() {
+        assert_eq!(
+            receipt_line("Review", "1h 0m 0s", 20),
+            "Review      1h 0m 0s\n"
+        );
+        assert_eq!(
+            receipt_line("Fix the flaky parser tests", "1h 0m 0s", 20),
+            "Fix the flaky parser\ntests       1h 0m 0s\n"
+        );
+        assert_eq!(
+            receipt_line("Fix the flaky parser", "1h 0m 0s", 20),
+            "Fix the flaky parser\n            1h 0m 0s\n"
+        );
+    }
+
+    #[test]
+    fn test_report_totals() {
+        // Whatever the sessions, the report has one row per day and a total
+        // that adds up to the time of the sessions.
+        let mut seed = 7u64;
+        let mut next = |max: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % max
+        };
+        for _ in 0..50 {
+            let entries = (0..next(6) + 1)
+                .map(|id| {
+                    let day = next(20) as u32 + 1;
+                    let from = next(10) as u32 + 6;
+                    entry(id, day, from, from + next(6) as u32 + 2)
+                })
+                .collect::<Vec<LogEntry>>();
+            let days = report::days(&entries);
+            let total = entries
+                .iter()
+                .fold(chrono::Duration::zero(), |total, entry| {
+                    total + entry.duration()
+                });
+            let options = ReportOptions {
+                style: Style::Plain,
+                merge: false,
+                verbose: false,
+                colors: ProjectColors::default(),
+            };
+            let text = report("Report", &days, &Config::default(), &options).unwrap();
+            let lines = text.lines().collect::<Vec<&str>>();
+            assert_eq!(lines[2 + days.len()], "Tasks:");
+            assert!(lines.contains(&format!("Total time: {}", format_duration(total)).as_str()));
+        }
+    }
+}
@@ -0,0 +1,826 @@
+use crate::{
+    approval, budget,
+    cli::{self, Args},
+    clipboard,
+    color::ProjectColors,
+    compliance,
+    config::Config,
+    daytype,
+    error::{self, ErrorCode},
+    format_duration, git, html, log,
+    log::LogEntry,
+    project,
+    render::{self, ReportOptions},
+    rounding, summary,
+    table::{self, Table},
+    target, timebank, timesheet,
+    week::Weeks,
+};
+
+/// Logged work on a single calendar day.
+pub struct Day {
+    pub date: chrono::NaiveDate,
+    pub sessions: Vec<LogEntry>,
+}
+
+impl Day {
+    pub fn worked(&self) -> chrono::Duration {
+        self.sessions
+            .iter()
+            .map(|session| session.duration())
+            .fold(chrono::Duration::zero(), |total, duration| total + duration)
+    }
+
+    /// Time spent between consecutive sessions on this day.
+    pub fn breaks(&self) -> chrono::Duration {
+        self.sessions
+            .windows(2)
+            .map(|pair| pair[1].start_time - pair[0].end_time)
+            .filter(|gap| *gap > chrono::Duration::zero())
+            .fold(chrono::Duration::zero(), |total, gap| total + gap)
+    }
+}
+
+/// Groups log entries by the local date they started on, in date order.
+pub fn days(entries: &[LogEntry]) -> Vec<Day> {
+    let mut entries = entries.to_vec();
+    entries.sort_by_key(|entry| entry.start_time);
+
+    let mut days: Vec<Day> = vec![];
+    for entry in entries {
+        let date = entry.start_time.date_naive();
+        match days.last_mut() {
+            Some(day) if day.date == date => day.sessions.push(entry),
+            _ => days.push(Day {
+                date,
+                sessions: vec![entry],
+            }),
+        }
+    }
+    days
+}
+
+/// Time per project over all sessions in `days`, largest first, with tasks
+/// without a project last.
+pub fn project_totals(days: &[Day]) -> Vec<(Option<String>, chrono::Duration)> {
+    let mut totals: Vec<(Option<String>, chrono::Duration)> = vec![];
+    for session in days.iter().flat_map(|day| &day.sessions) {
+        for group in summary::group_by_project(session.start_time, &session.tasks) {
+            match totals.iter_mut().find(|(name, _)| *name == group.name) {
+                Some((_, total)) => *total += group.duration,
+                None => totals.push((group.name, group.duration)),
+            }
+        }
+    }
+    totals.sort_by_key(|(name, total)| (name.is_none(), std::cmp::Reverse(*total)));
+    totals
+}
+
+/// Time per tag over all sessions in `days`, largest first. A task with
+/// several tags is counted in each of them.
+pub fn tag_totals(days: &[Day]) -> Vec<(Option<String>, chrono::Duration)> {
+    let mut totals: Vec<(Option<String>, chrono::Duration)> = vec![];
+    for session in days.iter().flat_map(|day| &day.sessions) {
+        for group in summary::group_by_tag(session.start_time, &session.tasks) {
+            match totals.iter_mut().find(|(name, _)| *name == group.name) {
+                Some((_, total)) => *total += group.duration,
+                None => totals.push((group.name, group.duration)),
+            }
+        }
+    }
+    totals.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+    totals
+}
+
+/// Time per git branch over all sessions in `days`, largest first, from the
+/// branch tags added with `branch_tags`.
+pub fn branch_totals(days: &[Day]) -> Vec<(String, chrono::Duration)> {
+    tag_totals(days)
+        .into_iter()
+        .filter_map(|(tag, total)| {
+            let branch = tag?.strip_prefix(git::BRANCH_TAG_PREFIX)?.to_string();
+            Some((branch, total))
+        })
+        .collect()
+}
+
+/// The time of a project or tag in a period and in the period before it.
+pub struct Comparison {
+    pub name: Option<String>,
+    pub current: chrono::Duration,
+    pub previous: chrono::Duration,
+}
+
+impl Comparison {
+    /// The change in percent of the previous time, or `None` when there was
+    /// no previous time.
+    pub fn percent(&self) -> Option<f64> {
+        if self.previous.is_zero() {
+            return None;
+        }
+        let change = (self.current - self.previous).num_seconds() as f64;
+        Some(change / self.previous.num_seconds() as f64 * 100.0)
+    }
+}
+
+/// Pairs the totals of a period with those of the period before, keeping the
+/// order of `current` and adding names only found in `previous` after them.
+pub fn compare(
+    current: Vec<(Option<String>, chrono::Duration)>,
+    previous: Vec<(Option<String>, chrono::Duration)>,
+) -> Vec<Comparison> {
+    let mut comparisons = current
+        .into_iter()
+        .map(|(name, current)| Comparison {
+            name,
+            current,
+            previous: chrono::Duration::zero(),
+        })
+        .collect::<Vec<Comparison>>();
+    for (name, previous) in previous {
+        match comparisons
+            .iter_mut()
+            .find(|comparison| comparison.name == name)
+        {
+            Some(comparison) => comparison.previous = previous,
+            None => comparisons.push(Comparison {
+                name,
+                current: chrono::Duration::zero(),
+                previous,
+            }),
+        }
+    }
+    comparisons.sort_by_key(|comparison| comparison.name.is_none());
+    comparisons
+}
+
+/// Prints the time per project and per tag in `days` next to the time in
+/// `previous`, with the change in time and in percent.
+fn print_comparison(
+    days: &[Day],
+    previous: &[Day],
+    previous_from: chrono::NaiveDate,
+    previous_to: chrono::NaiveDate,
+) {
+    println!(
+        "Compared with {} - {}:",
+        previous_from.format("%d-%m-%Y"),
+        previous_to.format("%d-%m-%Y")
+    );
+    let colors = ProjectColors::for_terminal(&Config::current());
+    let sections = [
+        (
+            "Projects",
+            "No project",
+            compare(project_totals(days), project_totals(previous)),
+        ),
+        ("Tags", "", compare(tag_totals(days), tag_totals(previous))),
+    ];
+    for (title, unnamed, comparisons) in sections {
+        if comparisons.is_empty() {
+            continue;
+        }
+        println!("  {}:", title);
+        for comparison in comparisons {
+            let name = comparison.name.as_deref().unwrap_or(unnamed);
+            println!(
+                "    {} - {} (previous {}, {}, {})",
+                if title == "Projects" {
+                    colors.paint(comparison.name.as_deref(), name)
+                } else {
+                    name.to_string()
+                },
+                format_duration(comparison.current),
+                format_duration(comparison.previous),
+                rounding::signed_duration((comparison.current - comparison.previous).num_seconds()),
+                comparison
+                    .percent()
+                    .map_or("new".to_string(), |percent| format!("{:+.0}%", percent))
+            );
+        }
+    }
+}
+
+/// Two periods side by side, `first` and then `second`, each with a label
+/// and its days: the time per project and per tag in each, and the change
+/// from the first to the second.
+pub fn diff_lines(
+    first: (&str, &[Day]),
+    second: (&str, &[Day]),
+    style: table::Style,
+    colors: &ProjectColors,
+) -> Vec<String> {
+    let mut lines = vec![];
+    let change = |comparison: &Comparison| {
+        vec![
+            format_duration(comparison.previous),
+            format_duration(comparison.current),
+            rounding::signed_duration((comparison.current - comparison.previous).num_seconds()),
+            comparison
+                .percent()
+                .map_or("new".to_string(), |percent| format!("{:+.0}%", percent)),
+        ]
+    };
+    let total = |days: &[Day]| {
+        days.iter()
+            .fold(chrono::Duration::zero(), |total, day| total + day.worked())
+    };
+    let sections = [
+        (
+            "Projects",
+            "Project",
+            compare(project_totals(second.1), project_totals(first.1)),
+        ),
+        (
+            "Tags",
+            "Tag",
+            compare(tag_totals(second.1), tag_totals(first.1)),
+        ),
+    ];
+    for (title, header, comparisons) in sections {
+        if comparisons.is_empty() {
+            continue;
+        }
+        lines.push(format!("{}:", title));
+        let mut table = Table::new(&[header, first.0, second.0, "Change", "%"])
+            .truncate(0, 40)
+            .right(1)
+            .right(2)
+            .right(3)
+            .right(4);
+        for comparison in &comparisons {
+            let name = match (&comparison.name, title) {
+                (Some(name), "Projects") => colors.paint(Some(name), name),
+                (Some(name), _) => name.clone(),
+                (None, _) => "No project".to_string(),
+            };
+            table.row([vec![name], change(comparison)].concat());
+        }
+        if title == "Projects" {
+            let comparison = Comparison {
+                name: None,
+                current: total(second.1),
+                previous: total(first.1),
+            };
+            table.row([vec!["Total".to_string()], change(&comparison)].concat());
+        }
+        lines.extend(table.render(style));
+    }
+    lines
+}
+
+/// A period given to `report --diff`: `YYYY-MM-DD..YYYY-MM-DD`, a single
+/// day or a week such as `2024-W23`.
+fn parse_period(weeks: &Weeks, text: &str) -> Option<(chrono::NaiveDate, chrono::NaiveDate)> {
+    if let Some((from, to)) = text.split_once("..") {
+        let (from, to) = (parse_date(from)?, parse_date(to)?);
+        return (from <= to).then_some((from, to));
+    }
+    if let Some((year, week)) = text.split_once("-W") {
+        let first = weeks.nth(year.parse().ok()?, week.parse().ok()?)?;
+        return Some((first, first + chrono::Days::new(6)));
+    }
+    let date = parse_date(text)?;
+    Some((date, date))
+}
+
+/// `report --diff <first> <second>`: prints two periods side by side.
+fn print_diff(
+    weeks: &Weeks,
+    periods: &[String],
+    style: table::Style,
+    hidden: &std::collections::BTreeSet<String>,
+) {
+    let mut days = vec![];
+    for period in periods {
+        let Some((from, to)) = parse_period(weeks, period) else {
+            error::print(
+                ErrorCode::InvalidArgument,
+                &format!(
+                    "Invalid period for --diff: {} (expected YYYY-MM-DD..YYYY-MM-DD, YYYY-MM-DD or YYYY-Www)",
+                    period
+                ),
+            );
+            return;
+        };
+        let entries = log::load_range(Some(from), Some(to))
+            .into_iter()
+            .filter(|entry| !project::only_archived(hidden, entry))
+            .collect::<Vec<LogEntry>>();
+        days.push((
+            from,
+            to,
+            self::days(&entries)
+                .into_iter()
+                .filter(|day| day.date >= from && day.date <= to)
+                .collect::<Vec<Day>>(),
+        ));
+    }
+    println!(
+        "Diff of {} - {} and {} - {}",
+        days[0].0.format("%d-%m-%Y"),
+        days[0].1.format("%d-%m-%Y"),
+        days[1].0.format("%d-%m-%Y"),
+        days[1].1.format("%d-%m-%Y")
+    );
+    if days.iter().all(|(_, _, days)| days.is_empty()) {
+        println!("No job sessions logged in these periods");
+        return;
+    }
+    let colors = ProjectColors::for_terminal(&Config::current());
+    for line in diff_lines(
+        (&periods[0], &days[0].2),
+        (&periods[1], &days[1].2),
+        style,
+        &colors,
+    ) {
+        println!("{}", line);
+    }
+}
+
+/// One line with the hours per day so far this week, such as
+/// "This week: Mon 7.5h Tue 8.1h Wed (running) 3.2h". A running session
+/// started at `running_since` is counted on the day it started.
+pub fn week_summary(
+    entries: &[LogEntry],
+    running_since: Option<chrono::DateTime<chrono::Local>>,
+    now: chrono::DateTime<chrono::Local>,
+    weeks: Weeks,
+) -> String {
+    let today = now.date_naive();
+    let days = days(entries);
+
+    let mut line = "This week:".to_string();
+    for date in weeks
+        .first_day(today)
+        .iter_days()
+        .take_while(|date| *date <= today)
+    {
+        let mut worked = days
+            .iter()
+            .find(|day| day.date == date)
+            .map(|day| day.worked())
+            .unwrap_or(chrono::Duration::zero());
+        let running = running_since.filter(|start| start.date_naive() == date);
+        if let Some(start) = running {
+            worked += now - start;
+        }
+        line.push_str(&format!(
+            " {}{} {:.1}h",
+            date.format("%a"),
+            if running.is_some() { " (running)" } else { "" },
+            worked.num_seconds() as f64 / 3600.0
+        ));
+    }
+    line
+}
+
+/// A task name in a report, with the time spent on it and how many times it
+/// was logged.
+pub struct ReportTask {
+    pub name: String,
+    pub project: Option<String>,
+    pub duration: chrono::Duration,
+    pub count: usize,
+    pub recurring: bool,
+}
+
+/// The tasks logged on `days` with their durations, in the order they were
+/// logged. With `merge`, tasks with the same name (ignoring case) and project
+/// are combined into one.
+pub fn report_tasks(days: &[Day], merge: bool) -> Vec<ReportTask> {
+    let mut tasks: Vec<ReportTask> = vec![];
+    for session in days.iter().flat_map(|day| &day.sessions) {
+        for (task, duration) in summary::task_durations(session.start_time, &session.tasks) {
+            let existing = tasks.iter_mut().find(|existing| {
+                merge
+                    && existing.project == task.project
+                    && existing.name.trim().to_lowercase() == task.name.trim().to_lowercase()
+            });
+            match existing {
+                Some(existing) => {
+                    existing.duration += duration;
+                    existing.count += 1;
+                }
+                None => tasks.push(ReportTask {
+                    name: task.name.clone(),
+                    project: task.project.clone(),
+                    duration,
+                    count: 1,
+                    recurring: task.recurring,
+                }),
+            }
+        }
+    }
+    tasks
+}
+
+/// The first and last day of week `number` of `year`, or of the current
+/// week when no number is given, as for `--week [<number>] [--year <year>]`.
+/// The year defaults to the current one. Prints an error for an invalid year
+/// or week.
+pub fn week_period(
+    weeks: &Weeks,
+    number: Option<u32>,
+    year: Option<&str>,
+) -> Result<(chrono::NaiveDate, chrono::NaiveDate), ()> {
+    let today = chrono::Local::now().date_naive();
+    let first = match number {
+        None => weeks.first_day(today),
+        Some(number) => {
+            let year = match year.map(str::parse::<i32>) {
+                None => weeks.number(today).0,
+                Some(Ok(year)) => year,
+                Some(Err(_)) => {
+                    error::print(
+                        ErrorCode::InvalidArgument,
+                        &format!("Invalid year: {}", year.unwrap()),
+                    );
+                    return Err(());
+                }
+            };
+            let Some(first) = weeks.nth(year, number) else {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("ERROR: {} has no week {}", year, number),
+                );
+                return Err(());
+            };
+            first
+        }
+    };
+    Ok((first, first + chrono::Days::new(6)))
+}
+
+pub fn parse_date(date: &str) -> Option<chrono::NaiveDate> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+pub fn parse_date_option(args: &mut Args, name: &str) -> Result<Option<chrono::NaiveDate>, ()> {
+    match args.value(name) {
+        None => Ok(None),
+        Some(date) => match parse_date(&date) {
+            Some(date) => Ok(Some(date)),
+            None => {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("Invalid date for {}: {} (expected YYYY-MM-DD)", name, date),
+                );
+                Err(())
+            }
+        },
+    }
+}
+
+pub fn report(args: &mut Args) {
+    let check_breaks = args.flag("--breaks");
+    let check_budgets = args.flag("--check-budgets");
+    let rounding_diff = args.flag("--rounding-diff");
+    let verbose = args.flag("--verbose");
+    let merge = !args.flag("--no-merge");
+    let html_file = args.value("--html");
+    let compare_previous = args.flag("--compare-previous");
+    let copy = args.flag("--copy");
+    let include_archived = args.flag("--include-archived");
+    let style = table::Style::from_args(args);
+    let diff = args.values("--diff", 2);
+    let week = args.optional_number("--week");
+    let year = args.value("--year");
+    let weeks = Weeks::from_config(&Config::current());
+    let (mut from, mut to) = match week {
+        Some(number) => match week_period(&weeks, number, year.as_deref()) {
+            Ok((first, last)) => (Some(first), Some(last)),
+            Err(()) => return,
+        },
+        None => (None, None),
+    };
+    match parse_date_option(args, "--from") {
+        Ok(Some(date)) => from = Some(date),
+        Ok(None) => {}
+        Err(()) => return,
+    }
+    match parse_date_option(args, "--to") {
+        Ok(Some(date)) => to = Some(date),
+        Ok(None) => {}
+        Err(()) => return,
+    }
+    // Without a period, the current week is compared with the week before.
+    if compare_previous && from.is_none() && to.is_none() {
+        let first = weeks.first_day(chrono::Local::now().date_naive());
+        (from, to) = (Some(first), Some(first + chrono::Days::new(6)));
+    }
+
+    for month in timesheet::changed_months() {
+        println!(
+            "WARNING: Closed month {} has changed since it was closed",
+            month
+        );
+    }
+
+    // Sessions spent only on archived projects are left out by default.
+    let hidden = if include_archived {
+        std::collections::BTreeSet::new()
+    } else {
+        project::archived()
+    };
+    if let Some(periods) = diff {
+        if periods.len() < 2 {
+            error::print(
+                ErrorCode::MissingArgument,
+                "Usage: report --diff <period> <period>",
+            );
+            return;
+        }
+        print_diff(&weeks, &periods, style, &hidden);
+        return;
+    }
+    let entries = log::load_range(from, to)
+        .into_iter()
+        .filter(|entry| !project::only_archived(&hidden, entry))
+        .collect::<Vec<LogEntry>>();
+    let days = days(&entries)
+        .into_iter()
+        .filter(|day| from.is_none_or(|from| day.date >= from))
+        .filter(|day| to.is_none_or(|to| day.date <= to))
+        .collect::<Vec<Day>>();
+
+    if days.is_empty() {
+        println!("No job sessions logged in this period");
+        return;
+    }
+
+    let from = from.unwrap_or(days[0].date);
+    let to = to.unwrap_or(days[days.len() - 1].date);
+
+    let config = Config::current();
+    if let Some(file) = html_file {
+        let colors = ProjectColors::new(&config, false);
+        match std::fs::write(&file, html::render(&days, from, to, &colors)) {
+            Ok(()) => println!("HTML report written to {}", file),
+            Err(e) => error::print(
+                ErrorCode::IoError,
+                &format!("ERROR: Cannot write {}: {}", file, e),
+            ),
+        }
+        return;
+    }
+
+    let mut header = format!(
+        "Report for {} - {}",
+        from.format("%d-%m-%Y"),
+        to.format("%d-%m-%Y")
+    );
+    if week.is_some() {
+        header.push_str(&format!(" (week {})", weeks.label(from)));
+    }
+
+    let options = ReportOptions {
+        style,
+        merge,
+        verbose,
+        colors: ProjectColors::for_terminal(&config),
+    };
+    match render::report(&header, &days, &config, &options) {
+        Ok(text) => print!("{}", text),
+        Err(e) => {
+            error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+            return;
+        }
+    }
+    if let Some(timebank) = &config.timebank {
+        let withdrawals = timebank::load();
+        let day_types = daytype::load();
+        let all_days = self::days(&log::load());
+        let balance =
+            |until| timebank::balance(timebank, &all_days, &withdrawals, &day_types, until);
+        let taken = withdrawals
+            .iter()
+            .filter(|withdrawal| withdrawal.date >= from && withdrawal.date <= to)
+            .map(|withdrawal| withdrawal.hours)
+            .sum::<f64>();
+        println!(
+            "Time bank: {} at {}, {} in this period{}",
+            timebank::format_hours(balance(to)),
+            to.format("%d-%m-%Y"),
+            timebank::format_hours(balance(to) - balance(from - chrono::Days::new(1))),
+            if taken > 0.0 {
+                format!(" ({:.1}h time off taken)", taken)
+            } else {
+                String::new()
+            }
+        );
+    }
+    let (approved, submitted, open) = approval::counts(&days);
+    if approved + submitted > 0 {
+        println!(
+            "Approval: {} approved, {} submitted, {} not submitted",
+            approved, submitted, open
+        );
+    }
+
+    if let Some(rules) = &config.working_hours {
+        print!(
+            "{}",
+            compliance::render_working_hours(&days, rules, weeks, chrono::Local::now())
+        );
+    }
+
+    if week.is_some() {
+        let available =
+            daytype::available_share(&daytype::load(), from, to, &daytype::working_days(&config));
+        target::print_progress(&days, &hidden, available);
+    }
+
+    if check_breaks {
+        compliance::print_break_compliance(&days, &config.breaks);
+    }
+
+    if rounding_diff {
+        rounding::print_rounding_diff(config.rounding.as_ref(), &days);
+    }
+
+    if compare_previous {
+        let previous_to = from - chrono::Days::new(1);
+        let previous_from = previous_to - (to - from);
+        let previous = self::days(&log::load_range(Some(previous_from), Some(previous_to)))
+            .into_iter()
+            .filter(|day| day.date >= previous_from && day.date <= previous_to)
+            .collect::<Vec<Day>>();
+        print_comparison(&days, &previous, previous_from, previous_to);
+    }
+
+    if copy {
+        let options = ReportOptions {
+            style,
+            merge,
+            verbose,
+            colors: ProjectColors::default(),
+        };
+        let copied = render::report_summary(&header, &days, &options);
+        match clipboard::copy(&copied.join("\n")) {
+            Ok(()) => cli::info("Report copied to the clipboard"),
+            Err(e) => error::print(
+                ErrorCode::CommandFailed,
+                &format!("ERROR: Cannot copy the report: {}", e),
+            ),
+        }
+    }
+
+    if budget::configured(&config)
+        && budget::print_budgets(&config, &log::load(), &hidden)
+        && check_budgets
+    {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Task;
+    use chrono::TimeZone;
+
+    fn entry(id: u64, start: (u32, u32, u32), end: (u32, u32, u32)) -> LogEntry {
+        let at = |(day, hour, minute)| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, day, hour, minute, 0)
+                .unwrap()
+        };
+        LogEntry {
+            id,
+            start_time: at(start),
+            end_time: at(end),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_days() {
+        let entries = vec![
+            entry(1, (12, 12, 30), (12, 16, 0)),
+            entry(2, (12, 8, 0), (12, 12, 0)),
+            entry(3, (13, 9, 0), (13, 10, 0)),
+        ];
+        let days = days(&entries);
+        assert_eq!(days.len(), 2);
+        assert_eq!(
+            days[0].worked(),
+            chrono::Duration::try_minutes(450).unwrap()
+        );
+        assert_eq!(days[0].breaks(), chrono::Duration::try_minutes(30).unwrap());
+        assert_eq!(days[1].worked(), chrono::Duration::try_hours(1).unwrap());
+        assert_eq!(days[1].breaks(), chrono::Duration::zero());
+    }
+
+    #[test]
+    fn test_report_tasks() {
+        let at = |hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 12, hour, 0, 0)
+                .unwrap()
+        };
+        let mut session = entry(1, (12, 8, 0), (12, 12, 0));
+        session.tasks = vec![
+            Task::new("Code review", at(9)),
+            Task::new("Fix parser", at(10)),
+            Task::new("code review ", at(12)),
+        ];
+        let days = days(&[session]);
+
+        let merged = report_tasks(&days, true);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].name, "Code review");
+        assert_eq!(merged[0].count, 2);
+        assert_eq!(merged[0].duration, chrono::Duration::try_hours(3).unwrap());
+        assert_eq!(report_tasks(&days, false).len(), 3);
+    }
+
+    #[test]
+    fn test_compare() {
+        let hours = |hours| chrono::Duration::try_hours(hours).unwrap();
+        let name = |name: &str| Some(name.to_string());
+        let comparisons = compare(
+            vec![(name("acme"), hours(6)), (None, hours(1))],
+            vec![(name("acme"), hours(4)), (name("globex"), hours(2))],
+        );
+        let names = comparisons
+            .iter()
+            .map(|comparison| comparison.name.as_deref())
+            .collect::<Vec<Option<&str>>>();
+        assert_eq!(names, vec![Some("acme"), Some("globex"), None]);
+        assert_eq!(comparisons[0].percent(), Some(50.0));
+        assert_eq!(comparisons[1].current, chrono::Duration::zero());
+        assert_eq!(comparisons[1].percent(), Some(-100.0));
+        assert_eq!(comparisons[2].percent(), None);
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let at = |day, hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, day, hour, 0, 0)
+                .unwrap()
+        };
+        let session = |day, project: &str, tag: &str| {
+            let mut task = Task::new("Build", at(day, 12));
+            task.project = Some(project.to_string());
+            task.tags = vec![tag.to_string()];
+            let mut session = entry(1, (day, 8, 0), (day, 12, 0));
+            session.tasks = vec![task];
+            session
+        };
+        let first = days(&[session(4, "acme", "dev"), session(5, "acme", "dev")]);
+        let second = days(&[session(11, "acme", "dev"), session(12, "globex", "ops")]);
+        let lines = diff_lines(
+            ("2024-W10", &first),
+            ("2024-W11", &second),
+            table::Style::Plain,
+            &ProjectColors::default(),
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "Projects:",
+                "Project\t2024-W10\t2024-W11\tChange\t%",
+                "acme\t8h 0m 0s\t4h 0m 0s\t-4h 0m 0s\t-50%",
+                "globex\t0h 0m 0s\t4h 0m 0s\t+4h 0m 0s\tnew",
+                "Total\t8h 0m 0s\t8h 0m 0s\t+0h 0m 0s\t+0%",
+                "Tags:",
+                "Tag\t2024-W10\t2024-W11\tChange\t%",
+                "dev\t8h 0m 0s\t4h 0m 0s\t-4h 0m 0s\t-50%",
+                "ops\t0h 0m 0s\t4h 0m 0s\t+4h 0m 0s\tnew",
+            ]
+        );
+
+        let weeks = Weeks::default();
+        let date = |day| chrono::NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+        assert_eq!(parse_period(&weeks, "2024-W11"), Some((date(11), date(17))));
+        assert_eq!(
+            parse_period(&weeks, "2024-03-04..2024-03-15"),
+            Some((date(4), date(15)))
+        );
+        assert_eq!(
+            parse_period(&weeks, "2024-03-12"),
+            Some((date(12), date(12)))
+        );
+        assert_eq!(parse_period(&weeks, "2024-03-15..2024-03-04"), None);
+    }
+
+    #[test]
+    fn test_week_summary() {
+        let entries = vec![
+            entry(1, (11, 8, 0), (11, 15, 30)),
+            entry(2, (12, 9, 0), (12, 17, 6)),
+        ];
+        let now = chrono::Local
+            .with_ymd_and_hms(2024, 3, 13, 12, 12, 0)
+            .unwrap();
+        let running = chrono::Local
+            .with_ymd_and_hms(2024, 3, 13, 9, 0, 0)
+            .unwrap();
+        assert_eq!(
+            week_summary(&entries, Some(running), now, Weeks::default()),
+            "This week: Mon 7.5h Tue 8.1h Wed (running) 3.2h"
+        );
+    }
+}
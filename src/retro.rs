@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    cli::Args,
+    config::Config,
+    daytype,
+    error::{self, ErrorCode},
+    format_duration, log,
+    report::{self, Day},
+    summary, target,
+    week::Weeks,
+    Task,
+};
+
+/// Whether `task` was known before the session it was done in: carried over
+/// from an earlier session, added from a recurring task or picked from
+/// Taskwarrior. Anything else came up during the session.
+fn planned(task: &Task) -> bool {
+    task.carried_over || task.recurring || task.taskwarrior_uuid.is_some()
+}
+
+fn percent(part: chrono::Duration, total: chrono::Duration) -> f64 {
+    if total.is_zero() {
+        return 0.0;
+    }
+    part.num_seconds() as f64 / total.num_seconds() as f64 * 100.0
+}
+
+/// A retrospective of `days` as Markdown: the three tasks that took the most
+/// time, the time on planned and unplanned tasks, the session goals and the
+/// weekly project `targets` with how they went, and questions to reflect on.
+fn retro_markdown(title: &str, days: &[Day], targets: &BTreeMap<String, f64>) -> String {
+    let mut text = format!("# Retrospective: {}\n\n", title);
+    let worked = days
+        .iter()
+        .fold(chrono::Duration::zero(), |sum, day| sum + day.worked());
+    let sessions = days.iter().map(|day| day.sessions.len()).sum::<usize>();
+    text.push_str(&format!(
+        "Worked {} in {} sessions on {} days.\n\n",
+        format_duration(worked),
+        sessions,
+        days.len()
+    ));
+
+    text.push_str("## Top time sinks\n\n");
+    let mut tasks = report::report_tasks(days, true);
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.duration));
+    let task_time = tasks
+        .iter()
+        .fold(chrono::Duration::zero(), |sum, task| sum + task.duration);
+    if tasks.is_empty() {
+        text.push_str("No tasks were logged.\n");
+    }
+    for (number, task) in tasks.iter().take(3).enumerate() {
+        let project = task
+            .project
+            .as_ref()
+            .map(|project| format!(" ({})", project))
+            .unwrap_or_default();
+        text.push_str(&format!(
+            "{}. **{}**{} - {} ({:.0}%)\n",
+            number + 1,
+            task.name,
+            project,
+            format_duration(task.duration),
+            percent(task.duration, task_time)
+        ));
+    }
+
+    text.push_str("\n## Planned vs unplanned\n\n");
+    let mut planned_time = chrono::Duration::zero();
+    for session in days.iter().flat_map(|day| &day.sessions) {
+        for (task, duration) in summary::task_durations(session.start_time, &session.tasks) {
+            if planned(&task) {
+                planned_time += duration;
+            }
+        }
+    }
+    let unplanned_time = task_time - planned_time;
+    text.push_str(&format!(
+        "- Planned: {} ({:.0}%)\n- Unplanned: {} ({:.0}%)\n\n",
+        format_duration(planned_time),
+        percent(planned_time, task_time),
+        format_duration(unplanned_time),
+        percent(unplanned_time, task_time)
+    ));
+    text.push_str(
+        "Planned tasks were carried over from an earlier session, recurring or from Taskwarrior.\n",
+    );
+
+    text.push_str("\n## Goals\n\n");
+    let mut goals = 0;
+    for day in days {
+        for session in &day.sessions {
+            let Some(goal) = &session.goal else {
+                continue;
+            };
+            goals += 1;
+            text.push_str(&format!(
+                "- [{}] {} ({}){}\n",
+                if session.goal_met == Some(true) {
+                    "x"
+                } else {
+                    " "
+                },
+                goal,
+                day.date.format("%a %d-%m-%Y"),
+                if session.goal_met.is_none() {
+                    ", not marked"
+                } else {
+                    ""
+                }
+            ));
+        }
+    }
+    let totals = report::project_totals(days);
+    for (project, target) in targets {
+        let done = totals
+            .iter()
+            .find(|(name, _)| name.as_deref() == Some(project.as_str()))
+            .map(|(_, duration)| duration.num_seconds() as f64 / 3600.0)
+            .unwrap_or(0.0);
+        goals += 1;
+        text.push_str(&format!(
+            "- [{}] {}: {:.1}h of the {:.1}h weekly target\n",
+            if done >= *target { "x" } else { " " },
+            project,
+            done,
+            target
+        ));
+    }
+    if goals == 0 {
+        text.push_str("No session goals or weekly targets were set.\n");
+    }
+
+    text.push_str("\n## Prompts\n\n");
+    for prompt in [
+        "What took longer than expected, and why?",
+        "Which unplanned work could have been planned, or turned down?",
+        "What got in the way of the goals that were not met?",
+        "What will you do differently next week?",
+    ] {
+        text.push_str(&format!("- {}\n", prompt));
+    }
+    text
+}
+
+/// `retro --week [<number>] [--year <year>]`: prints a retrospective of the
+/// current week, or of the given week, as Markdown.
+pub fn retro(args: &mut Args) {
+    let Some(number) = args.optional_number("--week") else {
+        error::print(
+            ErrorCode::MissingArgument,
+            "Usage: retro --week [<number>] [--year <year>]",
+        );
+        return;
+    };
+    let year = args.value("--year");
+    let config = Config::current();
+    let weeks = Weeks::from_config(&config);
+    let Ok((first, last)) = report::week_period(&weeks, number, year.as_deref()) else {
+        return;
+    };
+    let days = report::days(&log::load_range(Some(first), Some(last)))
+        .into_iter()
+        .filter(|day| day.date >= first && day.date <= last)
+        .collect::<Vec<Day>>();
+    let title = format!(
+        "{} ({} - {})",
+        weeks.label(first),
+        first.format("%d-%m-%Y"),
+        last.format("%d-%m-%Y")
+    );
+    let available = daytype::available_share(
+        &daytype::load(),
+        first,
+        last,
+        &daytype::working_days(&config),
+    );
+    let mut targets = target::load();
+    for target in targets.values_mut() {
+        *target *= available;
+    }
+    print!("{}", retro_markdown(&title, &days, &targets));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::LogEntry;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_retro_markdown() {
+        let at = |day, hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, day, hour, 0, 0)
+                .unwrap()
+        };
+        let mut carried = Task::new("Fix the parser", at(3, 12));
+        carried.carried_over = true;
+        carried.project = Some("acme".to_string());
+        let days = report::days(&[
+            LogEntry {
+                start_time: at(3, 8),
+                end_time: at(3, 16),
+                tasks: vec![
+                    carried,
+                    Task::new("Incident", at(3, 13)),
+                    Task::new("Review", at(3, 16)),
+                ],
+                goal: Some("Ship 1.2".to_string()),
+                goal_met: Some(true),
+                ..Default::default()
+            },
+            LogEntry {
+                start_time: at(4, 8),
+                end_time: at(4, 10),
+                tasks: vec![Task::new("Incident", at(4, 10))],
+                goal: Some("Write docs".to_string()),
+                ..Default::default()
+            },
+        ]);
+        let targets = BTreeMap::from([("acme".to_string(), 10.0)]);
+        let text = retro_markdown("Week 23", &days, &targets);
+        assert!(text.starts_with(
+            "# Retrospective: Week 23\n\nWorked 10h 0m 0s in 2 sessions on 2 days.\n\n\
+             ## Top time sinks\n\n\
+             1. **Fix the parser** (acme) - 4h 0m 0s (40%)\n\
+             2. **Incident** - 3h 0m 0s (30%)\n\
+             3. **Review** - 3h 0m 0s (30%)\n"
+        ));
+        assert!(text.contains("- Planned: 4h 0m 0s (40%)\n- Unplanned: 6h 0m 0s (60%)\n"));
+        assert!(text.contains(
+            "- [x] Ship 1.2 (Mon 03-06-2024)\n\
+             - [ ] Write docs (Tue 04-06-2024), not marked\n\
+             - [ ] acme: 4.0h of the 10.0h weekly target\n"
+        ));
+    }
+}
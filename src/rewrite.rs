@@ -0,0 +1,163 @@
+use crate::{
+    cli::{self, Args},
+    error::{self, ErrorCode},
+    log,
+    regex::Regex,
+    Task,
+};
+
+/// A task name changed by a rewrite.
+struct Change {
+    created_at: chrono::DateTime<chrono::Local>,
+    project: Option<String>,
+    old: String,
+    new: String,
+}
+
+/// Replaces every match of `regex` in the names of `tasks` with
+/// `replacement`, leaving out tasks not in `project` when one is given.
+/// Names that would end up empty are kept as they are.
+fn rewrite_tasks<'a>(
+    tasks: impl Iterator<Item = &'a mut Task>,
+    regex: &Regex,
+    replacement: &str,
+    project: Option<&str>,
+) -> Vec<Change> {
+    let mut changes = vec![];
+    for task in tasks {
+        if project.is_some() && task.project.as_deref() != project {
+            continue;
+        }
+        let name = regex
+            .replace_all(&task.name, replacement)
+            .trim()
+            .to_string();
+        if name == task.name || name.is_empty() {
+            continue;
+        }
+        changes.push(Change {
+            created_at: task.created_at,
+            project: task.project.clone(),
+            old: std::mem::replace(&mut task.name, name.clone()),
+            new: name,
+        });
+    }
+    changes
+}
+
+fn print_change(change: &Change) {
+    let project = change
+        .project
+        .as_ref()
+        .map(|project| format!(" [{}]", project))
+        .unwrap_or_default();
+    println!(
+        "{}{}",
+        change.created_at.format("%d-%m-%Y %H:%M:%S"),
+        project
+    );
+    println!("  - {}", change.old);
+    println!("  + {}", change.new);
+}
+
+/// `rewrite --match <regex> --replace <text> [--project <name>] [--dry-run]`:
+/// replaces text in the names of logged tasks and of the tasks of the
+/// running session, after showing every change. Returns whether the
+/// session changed.
+pub fn rewrite(args: &mut Args, session_tasks: &mut [Task]) -> bool {
+    let usage = "Usage: rewrite --match <regex> --replace <text> [--project <name>] [--dry-run]";
+    let dry_run = args.flag("--dry-run");
+    let project = args.value("--project");
+    let (Some(pattern), Some(replacement)) = (args.value("--match"), args.value("--replace"))
+    else {
+        error::print(ErrorCode::MissingArgument, usage);
+        return false;
+    };
+    let regex = match Regex::new(&pattern) {
+        Ok(regex) => regex,
+        Err(e) => {
+            error::print(
+                ErrorCode::InvalidArgument,
+                &format!("Invalid pattern: {} ({})", pattern, e),
+            );
+            return false;
+        }
+    };
+
+    let mut entries = log::load();
+    let mut changes = rewrite_tasks(
+        entries.iter_mut().flat_map(|entry| &mut entry.tasks),
+        &regex,
+        &replacement,
+        project.as_deref(),
+    );
+    let logged = changes.len();
+    let mut running_tasks = session_tasks.to_vec();
+    changes.extend(rewrite_tasks(
+        running_tasks.iter_mut(),
+        &regex,
+        &replacement,
+        project.as_deref(),
+    ));
+    if changes.is_empty() {
+        println!("No task names match {}", pattern);
+        return false;
+    }
+    for change in &changes {
+        print_change(change);
+    }
+    if dry_run {
+        println!(
+            "{} tasks would be renamed (dry run, nothing written)",
+            changes.len()
+        );
+        return false;
+    }
+    if !cli::confirm(&format!("Rename {} tasks?", changes.len())) {
+        return false;
+    }
+    if logged > 0 {
+        if let Err(e) = log::save(&entries) {
+            error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+            return false;
+        }
+    }
+    cli::info(&format!("Renamed {} tasks", changes.len()));
+    if changes.len() == logged {
+        return false;
+    }
+    session_tasks.clone_from_slice(&running_tasks);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_tasks() {
+        let task = |name: &str, project: Option<&str>| {
+            let mut task = Task::new(name, chrono::Local::now());
+            task.project = project.map(|project| project.to_string());
+            task
+        };
+        let mut tasks = [
+            task("Fix paser crash", Some("acme")),
+            task("Paser docs", Some("globex")),
+            task("Review", Some("acme")),
+            task("paser", Some("acme")),
+        ];
+        let regex = Regex::new("(?i)paser").unwrap();
+        let changes = rewrite_tasks(tasks.iter_mut(), &regex, "parser", Some("acme"));
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].old, "Fix paser crash");
+        assert_eq!(changes[0].new, "Fix parser crash");
+        assert_eq!(tasks[0].name, "Fix parser crash");
+        assert_eq!(tasks[1].name, "Paser docs");
+        assert_eq!(tasks[3].name, "parser");
+
+        let changes = rewrite_tasks(tasks.iter_mut(), &regex, "", None);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(tasks[1].name, "docs");
+    }
+}
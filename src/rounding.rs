@@ -0,0 +1,138 @@
+use crate::{format_duration, log::LogEntry, report::Day, summary};
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    Up,
+    Down,
+    #[default]
+    Nearest,
+}
+
+/// Rounding of billed time, applied to the time of each project in each
+/// session, such as up to the next 15 minutes.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct Rounding {
+    pub minutes: i64,
+    pub mode: RoundingMode,
+}
+
+impl Default for Rounding {
+    fn default() -> Rounding {
+        Rounding {
+            minutes: 15,
+            mode: RoundingMode::Nearest,
+        }
+    }
+}
+
+impl Rounding {
+    pub fn round(&self, duration: chrono::Duration) -> chrono::Duration {
+        let step = self.minutes.max(1) * 60;
+        let seconds = duration.num_seconds();
+        let steps = match self.mode {
+            RoundingMode::Up => (seconds + step - 1).div_euclid(step),
+            RoundingMode::Down => seconds.div_euclid(step),
+            RoundingMode::Nearest => (seconds + step / 2).div_euclid(step),
+        };
+        chrono::Duration::try_seconds(steps * step).unwrap()
+    }
+}
+
+/// Raw and rounded time of a project in one session, as stored in exports.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+pub struct RoundedTime {
+    pub project: Option<String>,
+    pub raw_seconds: i64,
+    pub rounded_seconds: i64,
+}
+
+/// The raw and rounded time of each project in `entry`.
+pub fn entry(rounding: &Rounding, entry: &LogEntry) -> Vec<RoundedTime> {
+    summary::group_by_project(entry.start_time, &entry.tasks)
+        .into_iter()
+        .map(|group| RoundedTime {
+            project: group.name,
+            raw_seconds: group.duration.num_seconds(),
+            rounded_seconds: rounding.round(group.duration).num_seconds(),
+        })
+        .collect()
+}
+
+/// Rounded time per project over `days`, with projects without a name last.
+pub fn project_totals(rounding: &Rounding, days: &[Day]) -> Vec<RoundedTime> {
+    let mut totals: Vec<RoundedTime> = vec![];
+    for time in days
+        .iter()
+        .flat_map(|day| &day.sessions)
+        .flat_map(|session| entry(rounding, session))
+    {
+        match totals
+            .iter_mut()
+            .find(|total| total.project == time.project)
+        {
+            Some(total) => {
+                total.raw_seconds += time.raw_seconds;
+                total.rounded_seconds += time.rounded_seconds;
+            }
+            None => totals.push(time),
+        }
+    }
+    totals.sort_by_key(|total| (total.project.is_none(), total.project.clone()));
+    totals
+}
+
+/// A duration with its sign, such as "+0h 15m 0s".
+pub fn signed_duration(seconds: i64) -> String {
+    let sign = if seconds < 0 { "-" } else { "+" };
+    format!(
+        "{}{}",
+        sign,
+        format_duration(chrono::Duration::try_seconds(seconds.abs()).unwrap())
+    )
+}
+
+/// Prints how much time rounding added or removed per project over `days`.
+pub fn print_rounding_diff(rounding: Option<&Rounding>, days: &[Day]) {
+    println!("Rounding:");
+    let Some(rounding) = rounding else {
+        println!("  No rounding configured");
+        return;
+    };
+    let mut difference = 0;
+    for total in project_totals(rounding, days) {
+        difference += total.rounded_seconds - total.raw_seconds;
+        println!(
+            "  {} - {} raw, {} rounded ({})",
+            total.project.as_deref().unwrap_or("No project"),
+            format_duration(chrono::Duration::try_seconds(total.raw_seconds).unwrap()),
+            format_duration(chrono::Duration::try_seconds(total.rounded_seconds).unwrap()),
+            signed_duration(total.rounded_seconds - total.raw_seconds)
+        );
+    }
+    println!("  Total rounding: {}", signed_duration(difference));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round() {
+        let minutes = |minutes| chrono::Duration::try_minutes(minutes).unwrap();
+        let rounding = |mode| Rounding { minutes: 15, mode };
+        assert_eq!(rounding(RoundingMode::Up).round(minutes(31)), minutes(45));
+        assert_eq!(rounding(RoundingMode::Down).round(minutes(44)), minutes(30));
+        assert_eq!(
+            rounding(RoundingMode::Nearest).round(minutes(37)),
+            minutes(30)
+        );
+        assert_eq!(
+            rounding(RoundingMode::Nearest).round(minutes(38)),
+            minutes(45)
+        );
+        assert_eq!(rounding(RoundingMode::Up).round(minutes(30)), minutes(30));
+        assert_eq!(signed_duration(-90), "-0h 1m 30s");
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{config, git, regex::Regex, Task};
+
+/// Categorization rule: when `pattern` matches a task name, the task is
+/// assigned the rule's project, tags and billable flag.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Rule {
+    pub pattern: String,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub billable: Option<bool>,
+}
+
+/// Path rule: files below `path` belong to `project`.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct PathRule {
+    pub path: String,
+    pub project: String,
+}
+
+/// The project of the most specific path rule containing `path`.
+pub fn project_for_path(rules: &[PathRule], path: &std::path::Path) -> Option<String> {
+    rules
+        .iter()
+        .map(|rule| (config::expand_home(&rule.path), rule))
+        .filter(|(prefix, _)| path.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.components().count())
+        .map(|(_, rule)| rule.project.clone())
+}
+
+/// Marker file naming the project of the folder it is in and those below it.
+const MARKER: &str = ".jobclock";
+
+/// Detects the project of `folder`: from the nearest `.jobclock` marker file,
+/// then the path rules, then the name of the git `origin` remote.
+pub fn detect_project(rules: &[PathRule], folder: &std::path::Path) -> Option<String> {
+    let marker = folder.ancestors().find_map(|folder| {
+        let name = std::fs::read_to_string(folder.join(MARKER)).ok()?;
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    });
+    marker
+        .or_else(|| project_for_path(rules, folder))
+        .or_else(|| git::remote_name(folder))
+}
+
+/// Applies `rules` in order to `task`. Tags from every matching rule are
+/// added, while the first matching rule that sets a project or billable flag
+/// decides it. A project already on the task is kept.
+pub fn categorize(rules: &[Rule], task: &mut Task) {
+    let mut billable_decided = false;
+
+    for rule in rules {
+        let regex = match Regex::new(&rule.pattern) {
+            Ok(regex) => regex,
+            Err(e) => {
+                eprintln!("Invalid rule pattern '{}': {}", rule.pattern, e);
+                continue;
+            }
+        };
+        if !regex.is_match(&task.name) {
+            continue;
+        }
+
+        if task.project.is_none() {
+            task.project = rule.project.clone();
+        }
+        for tag in &rule.tags {
+            if !task.tags.contains(tag) {
+                task.tags.push(tag.clone());
+            }
+        }
+        if let (false, Some(billable)) = (billable_decided, rule.billable) {
+            task.billable = billable;
+            billable_decided = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_categorize() {
+        let rules = vec![
+            Rule {
+                pattern: r"JIRA-\d+".to_string(),
+                project: Some("client-a".to_string()),
+                tags: vec!["client-a".to_string()],
+                billable: None,
+            },
+            Rule {
+                pattern: "(?i)meeting".to_string(),
+                project: Some("internal".to_string()),
+                tags: vec!["meeting".to_string()],
+                billable: Some(false),
+            },
+        ];
+
+        let mut task = Task::new("Fix JIRA-12 after meeting", chrono::Local::now());
+        categorize(&rules, &mut task);
+        assert_eq!(task.project.as_deref(), Some("client-a"));
+        assert_eq!(task.tags, vec!["client-a", "meeting"]);
+        assert!(!task.billable);
+
+        let mut task = Task::new("Write docs", chrono::Local::now());
+        categorize(&rules, &mut task);
+        assert_eq!(task.project, None);
+        assert!(task.tags.is_empty());
+        assert!(task.billable);
+    }
+
+    #[test]
+    fn test_project_for_path() {
+        let rules = vec![
+            PathRule {
+                path: "/work".to_string(),
+                project: "work".to_string(),
+            },
+            PathRule {
+                path: "/work/client-a".to_string(),
+                project: "client-a".to_string(),
+            },
+        ];
+        let project = |path: &str| project_for_path(&rules, std::path::Path::new(path));
+        assert_eq!(
+            project("/work/client-a/src/main.rs").as_deref(),
+            Some("client-a")
+        );
+        assert_eq!(project("/work/tools/build.sh").as_deref(), Some("work"));
+        assert_eq!(project("/work/client-ab/main.rs").as_deref(), Some("work"));
+        assert_eq!(project("/home/notes.md"), None);
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{
+    error::{self, ErrorCode},
+    format_duration, log,
+};
+
+/// Scores how well `query` fuzzy-matches `text`. Every word of the query must
+/// appear in `text` as an in-order (not necessarily contiguous) sequence of
+/// characters, ignoring case. Consecutive characters and characters at the
+/// start of a word score higher. Returns `None` if any word does not match.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text = text.to_lowercase().chars().collect::<Vec<char>>();
+    let mut total = 0;
+
+    for word in query.to_lowercase().split_whitespace() {
+        let mut score = 0;
+        let mut position = 0;
+        let mut previous: Option<usize> = None;
+
+        for c in word.chars() {
+            let index = position + text[position..].iter().position(|&t| t == c)?;
+            score += 1;
+            if previous.is_some_and(|previous| previous + 1 == index) {
+                score += 5;
+            }
+            if index == 0 || !text[index - 1].is_alphanumeric() {
+                score += 3;
+            }
+            previous = Some(index);
+            position = index + 1;
+        }
+
+        total += score;
+    }
+
+    Some(total)
+}
+
+pub fn search(query: &str) {
+    if query.trim().is_empty() {
+        error::print(ErrorCode::MissingArgument, "Search query is required");
+        return;
+    }
+
+    let mut entries = log::load();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.end_time));
+
+    let mut found = false;
+    for entry in entries {
+        let mut matches = entry
+            .tasks
+            .iter()
+            .filter_map(|task| fuzzy_score(query, &task.name).map(|score| (score, task)))
+            .collect::<Vec<_>>();
+        if matches.is_empty() {
+            continue;
+        }
+        matches.sort_by_key(|(score, task)| (std::cmp::Reverse(*score), task.created_at));
+
+        found = true;
+        println!(
+            "Session {}: {} - {} ({})",
+            entry.id,
+            entry.start_time.format("%d-%m-%Y %H:%M:%S"),
+            entry.end_time.format("%d-%m-%Y %H:%M:%S"),
+            format_duration(entry.duration())
+        );
+        for (_, task) in matches {
+            println!(
+                "  {} - {}",
+                task.created_at.format("%d-%m-%Y %H:%M:%S"),
+                task.name
+            );
+        }
+    }
+
+    if !found {
+        println!("No sessions matching '{}'", query);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("parser", "Fix the parser").is_some());
+        assert!(fuzzy_score("prsr", "Fix the parser").is_some());
+        assert!(fuzzy_score("PARSER bug", "parser: fix bug").is_some());
+        assert!(fuzzy_score("lexer", "Fix the parser").is_none());
+        assert!(
+            fuzzy_score("parser", "Fix the parser").unwrap()
+                > fuzzy_score("parser", "prepare a serializer").unwrap_or(0)
+        );
+    }
+}
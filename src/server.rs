@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+
+use crate::{
+    cli::Args,
+    config::Config,
+    error::{self, ErrorCode},
+    log::LogEntry,
+    persistent_folder, report, storage,
+    team::{MemberHours, ServerConfig},
+    valid_profile_name,
+};
+
+/// Largest request body accepted, enough for many years of sessions.
+const MAX_BODY: usize = 8 * 1024 * 1024;
+/// Largest request line and headers accepted together.
+const MAX_HEAD: usize = 16 * 1024;
+/// Connections served at the same time; more are turned away with 503.
+const MAX_CONNECTIONS: usize = 32;
+/// Pushes received so far, to give every push a temporary file of its own,
+/// so pushes running at the same time never write into each other's.
+static PUSHES: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+struct Request {
+    method: String,
+    path: String,
+    query: BTreeMap<String, String>,
+    token: Option<String>,
+    body: String,
+}
+
+#[derive(Debug, PartialEq)]
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: serde_json::Value) -> Response {
+        Response {
+            status,
+            body: body.to_string(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response::json(status, serde_json::json!({ "error": message }))
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Reads a line of the request head into `line`, taking it from the
+/// `remaining` bytes the head may still use.
+fn read_head_line(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    remaining: &mut usize,
+) -> Result<(), String> {
+    line.clear();
+    let read = reader
+        .take(*remaining as u64)
+        .read_line(line)
+        .map_err(|e| e.to_string())?;
+    *remaining -= read;
+    if !line.ends_with('\n') && *remaining == 0 {
+        return Err("The request headers are too large".to_string());
+    }
+    Ok(())
+}
+
+/// Reads a request. The body is only read for a request by one of the users
+/// in `config`, so a request without a valid token cannot make the server
+/// take in a large body.
+fn read_request(stream: impl Read, config: &ServerConfig) -> Result<Request, String> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    let mut remaining = MAX_HEAD;
+    read_head_line(&mut reader, &mut line, &mut remaining)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err("Invalid request line".to_string());
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query: query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        token: None,
+        body: String::new(),
+    };
+
+    let mut length = 0;
+    loop {
+        read_head_line(&mut reader, &mut line, &mut remaining)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(format!("Invalid header: {}", header));
+        };
+        let value = value.trim();
+        match name.to_lowercase().as_str() {
+            "content-length" => {
+                length = value
+                    .parse()
+                    .map_err(|_| format!("Invalid Content-Length: {}", value))?
+            }
+            "authorization" => request.token = value.strip_prefix("Bearer ").map(str::to_string),
+            _ => {}
+        }
+    }
+    if user(config, &request).is_none() {
+        return Ok(request);
+    }
+    if length > MAX_BODY {
+        return Err("The request body is too large".to_string());
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|e| e.to_string())?;
+    request.body = String::from_utf8(body).map_err(|e| e.to_string())?;
+    Ok(request)
+}
+
+/// Compares tokens in time independent of where they differ.
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// The user a request is made by, from its token.
+fn user<'a>(config: &'a ServerConfig, request: &Request) -> Option<&'a str> {
+    let token = request.token.as_deref()?;
+    config
+        .users
+        .iter()
+        .find(|(_, user_token)| !user_token.is_empty() && same_token(user_token, token))
+        .map(|(user, _)| user.as_str())
+}
+
+fn log_file(folder: &std::path::Path, user: &str) -> std::path::PathBuf {
+    folder.join(format!("{}.json", user))
+}
+
+fn read_log(folder: &std::path::Path, user: &str) -> Vec<LogEntry> {
+    std::fs::read_to_string(log_file(folder, user))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// The hours of every user with a stored log, counting sessions that start
+/// within the `from` and `to` query parameters.
+fn team_hours(
+    config: &ServerConfig,
+    folder: &std::path::Path,
+    query: &BTreeMap<String, String>,
+) -> Result<Vec<MemberHours>, String> {
+    let date = |name: &str| match query.get(name) {
+        Some(date) => report::parse_date(date)
+            .map(Some)
+            .ok_or_else(|| format!("Invalid date for {}: {}", name, date)),
+        None => Ok(None),
+    };
+    let (from, to) = (date("from")?, date("to")?);
+    Ok(config
+        .users
+        .keys()
+        .filter(|user| log_file(folder, user).exists())
+        .map(|user| {
+            let entries = read_log(folder, user)
+                .into_iter()
+                .filter(|entry| {
+                    let date = entry.start_time.date_naive();
+                    from.is_none_or(|from| date >= from) && to.is_none_or(|to| date <= to)
+                })
+                .collect::<Vec<LogEntry>>();
+            MemberHours {
+                user: user.clone(),
+                sessions: entries.len(),
+                hours: entries
+                    .iter()
+                    .map(|entry| entry.duration().num_seconds() as f64 / 3600.0)
+                    .sum(),
+            }
+        })
+        .collect())
+}
+
+fn handle(config: &ServerConfig, folder: &std::path::Path, request: &Request) -> Response {
+    let Some(user) = user(config, request) else {
+        return Response::error(401, "Missing or unknown token");
+    };
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/log") => Response {
+            status: 200,
+            body: serde_json::to_string(&read_log(folder, user)).unwrap(),
+        },
+        ("PUT", "/log") => {
+            let entries = match serde_json::from_str::<Vec<LogEntry>>(&request.body) {
+                Ok(entries) => entries,
+                Err(e) => return Response::error(400, &format!("Invalid log: {}", e)),
+            };
+            let file = log_file(folder, user);
+            let push = PUSHES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            let temporary =
+                file.with_extension(format!("json.{}-{}.tmp", std::process::id(), push));
+            let written = storage::create_folder(folder)
+                .and_then(|_| storage::write(&temporary, &request.body))
+                .and_then(|_| std::fs::rename(&temporary, &file));
+            match written {
+                Ok(()) => Response::json(200, serde_json::json!({ "sessions": entries.len() })),
+                Err(e) => Response::error(500, &format!("Cannot store the log: {}", e)),
+            }
+        }
+        ("GET", "/team") => match team_hours(config, folder, &request.query) {
+            Ok(members) => Response {
+                status: 200,
+                body: serde_json::to_string(&members).unwrap(),
+            },
+            Err(e) => Response::error(400, &e),
+        },
+        (_, "/log" | "/team") => Response::error(405, "Method not allowed"),
+        _ => Response::error(404, "Not found"),
+    }
+}
+
+fn respond(mut stream: std::net::TcpStream, config: &ServerConfig, folder: &std::path::Path) {
+    stream
+        .set_read_timeout(Some(std::time::Duration::from_secs(30)))
+        .ok();
+    let (line, response) = match read_request(&stream, config) {
+        Ok(request) => {
+            let response = handle(config, folder, &request);
+            let line = format!(
+                "{} {} {} {}",
+                request.method,
+                request.path,
+                response.status,
+                user(config, &request).unwrap_or("-")
+            );
+            (line, response)
+        }
+        Err(e) => (format!("Bad request: {}", e), Response::error(400, &e)),
+    };
+    println!("{}", line);
+    write_response(&mut stream, &response);
+}
+
+fn write_response(stream: &mut std::net::TcpStream, response: &Response) {
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.body.len()
+    );
+    stream
+        .write_all(head.as_bytes())
+        .and_then(|_| stream.write_all(response.body.as_bytes()))
+        .ok();
+}
+
+/// `server [--bind <address>] [--port <port>]`: serves the logs pushed by
+/// the users in the `server` config, each authenticated by their token.
+pub fn serve(args: &mut Args) {
+    let config = Config::current().server.unwrap_or_default();
+    if config.users.is_empty() {
+        error::print(
+            ErrorCode::CommandFailed,
+            "No users configured, add server.users to the config",
+        );
+        return;
+    }
+    if let Some(user) = config.users.keys().find(|user| !valid_profile_name(user)) {
+        error::print(
+            ErrorCode::InvalidArgument,
+            &format!("ERROR: Invalid user name: {}", user),
+        );
+        return;
+    }
+    let bind = args.value("--bind").unwrap_or("127.0.0.1".to_string());
+    let port = args.value("--port").unwrap_or("8420".to_string());
+    let listener = match std::net::TcpListener::bind(format!("{}:{}", bind, port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error::print(
+                ErrorCode::CommandFailed,
+                &format!("ERROR: Cannot listen on {}:{}: {}", bind, port, e),
+            );
+            return;
+        }
+    };
+    let folder = persistent_folder().join("server");
+    println!(
+        "Serving team logs from {} on http://{}:{}",
+        folder.display(),
+        bind,
+        port
+    );
+    let connections = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    for mut stream in listener.incoming().flatten() {
+        use std::sync::atomic::Ordering;
+        if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            connections.fetch_sub(1, Ordering::SeqCst);
+            stream
+                .set_write_timeout(Some(std::time::Duration::from_secs(1)))
+                .ok();
+            write_response(&mut stream, &Response::error(503, "Too many connections"));
+            continue;
+        }
+        let (config, folder, connections) = (config.clone(), folder.clone(), connections.clone());
+        std::thread::spawn(move || {
+            respond(stream, &config, &folder);
+            connections.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle() {
+        let config = ServerConfig {
+            users: BTreeMap::from([("alice".to_string(), "s3cret".to_string())]),
+        };
+        let folder = std::env::temp_dir().join(format!("jobclock-server-{}", std::process::id()));
+        let request = |raw: &str| read_request(raw.as_bytes(), &config).unwrap();
+
+        let body = r#"[{"id":1,"start_time":"2024-03-13T09:00:00+01:00","end_time":"2024-03-13T11:30:00+01:00","tasks":[]}]"#;
+        let put = request(&format!(
+            "PUT /log HTTP/1.1\r\nAuthorization: Bearer s3cret\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        assert_eq!(
+            handle(&config, &folder, &put),
+            Response::json(200, serde_json::json!({ "sessions": 1 }))
+        );
+
+        let get = request("GET /log HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n");
+        assert!(handle(&config, &folder, &get).body.contains("\"id\":1"));
+        let team =
+            request("GET /team?from=2024-03-01 HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n");
+        assert_eq!(
+            serde_json::from_str::<Vec<MemberHours>>(&handle(&config, &folder, &team).body)
+                .unwrap(),
+            vec![MemberHours {
+                user: "alice".to_string(),
+                sessions: 1,
+                hours: 2.5
+            }]
+        );
+
+        let wrong = request("GET /log HTTP/1.1\r\nAuthorization: Bearer s3cre\r\n\r\n");
+        assert_eq!(handle(&config, &folder, &wrong).status, 401);
+        let bad = request("PUT /log HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n");
+        assert_eq!(handle(&config, &folder, &bad).status, 400);
+        std::fs::remove_dir_all(&folder).ok();
+
+        // Without a valid token the body is not read, however large it claims to be.
+        let unknown = request("PUT /log HTTP/1.1\r\nContent-Length: 999999999999\r\n\r\n");
+        assert_eq!(handle(&config, &folder, &unknown).status, 401);
+        let large = read_request(
+            "PUT /log HTTP/1.1\r\nAuthorization: Bearer s3cret\r\nContent-Length: 999999999999\r\n\r\n"
+                .as_bytes(),
+            &config,
+        );
+        assert!(large.is_err());
+        let head = format!(
+            "GET /log HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+            "a".repeat(MAX_HEAD)
+        );
+        assert!(read_request(head.as_bytes(), &config).is_err());
+    }
+}
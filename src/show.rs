@@ -0,0 +1,139 @@
+use crate::{
+    cli::Args,
+    config::Config,
+    currency::{self, Converter},
+    edit,
+    error::{self, ErrorCode},
+    format_duration, log,
+    log::LogEntry,
+    render, summary,
+};
+
+/// The logged sessions `selector` picks: a session id, `last` for the most
+/// recently ended session or `today` for the sessions started on `today`.
+fn select<'a>(
+    entries: &'a [LogEntry],
+    selector: &str,
+    today: chrono::NaiveDate,
+) -> Result<Vec<&'a LogEntry>, String> {
+    match selector {
+        "last" => entries
+            .iter()
+            .max_by_key(|entry| entry.end_time)
+            .map(|entry| vec![entry])
+            .ok_or("No logged job sessions".to_string()),
+        "today" => {
+            let mut today = entries
+                .iter()
+                .filter(|entry| entry.start_time.date_naive() == today)
+                .collect::<Vec<&LogEntry>>();
+            today.sort_by_key(|entry| entry.start_time);
+            if today.is_empty() {
+                return Err("No job sessions logged today".to_string());
+            }
+            Ok(today)
+        }
+        id => edit::find(entries, id).map(|index| vec![&entries[index]]),
+    }
+}
+
+/// The earnings of `entry` in the home currency, if rates are configured.
+fn earnings(config: &Config, entry: &LogEntry) -> Result<Option<f64>, String> {
+    let Some(rates) = &config.rates else {
+        return Ok(None);
+    };
+    let converter = Converter::new(config);
+    let mut earned = 0.0;
+    for (project, amount) in rates.session(entry) {
+        let currency = currency::project_currency(config, project.as_deref());
+        earned += converter.to_home(amount, currency.as_deref())?;
+    }
+    Ok(Some(earned))
+}
+
+/// `show <id|last|today>...`: prints the full detail of logged sessions,
+/// like the summary printed when a session ends, with the time per task and
+/// the earnings.
+pub fn show(args: &mut Args) {
+    let selectors = args.text();
+    if selectors.is_empty() {
+        error::print(
+            ErrorCode::MissingArgument,
+            "Usage: show <session-id|last|today>...",
+        );
+        return;
+    }
+    let entries = log::load();
+    let today = chrono::Local::now().date_naive();
+    let mut shown = vec![];
+    for selector in selectors.split_whitespace() {
+        match select(&entries, selector, today) {
+            Ok(selected) => shown.extend(selected),
+            Err(e) => {
+                error::print(ErrorCode::InvalidArgument, &format!("ERROR: {}", e));
+                return;
+            }
+        }
+    }
+
+    let config = Config::current();
+    for (index, entry) in shown.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        println!("Job session {}", entry.id);
+        print!("{}", render::session(entry, &config.summary));
+        let durations = summary::task_durations(entry.start_time, &entry.tasks);
+        if !durations.is_empty() {
+            println!("Time per task:");
+            for (task, duration) in durations {
+                println!(
+                    "  {} - {}{}",
+                    format_duration(duration),
+                    task.name,
+                    task.labels()
+                );
+            }
+        }
+        match earnings(&config, entry) {
+            Ok(Some(earned)) => println!("Earnings: {}", Converter::new(&config).format(earned)),
+            Ok(None) => {}
+            Err(e) => error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_select() {
+        let at = |day, hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, day, hour, 0, 0)
+                .unwrap()
+        };
+        let entry = |id, day, start, end| LogEntry {
+            id,
+            start_time: at(day, start),
+            end_time: at(day, end),
+            ..Default::default()
+        };
+        let entries = [
+            entry(1, 11, 9, 12),
+            entry(3, 12, 13, 15),
+            entry(2, 12, 8, 11),
+        ];
+        let today = chrono::NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+        let ids = |selector| {
+            select(&entries, selector, today)
+                .map(|selected| selected.iter().map(|entry| entry.id).collect::<Vec<u64>>())
+        };
+        assert_eq!(ids("last"), Ok(vec![3]));
+        assert_eq!(ids("today"), Ok(vec![2, 3]));
+        assert_eq!(ids("#1"), Ok(vec![1]));
+        assert!(ids("4").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{
+    cli::{self, Args},
+    clipboard,
+    error::{self, ErrorCode},
+    log,
+    log::LogEntry,
+    Task, TaskState,
+};
+
+/// Task names in order, each once.
+fn task_names<'a>(tasks: impl Iterator<Item = &'a Task>) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for task in tasks {
+        let name = task.name.trim().to_string();
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// The standup update: the tasks of `last` as yesterday's work, `goal` or
+/// the tasks still in progress as today's plan, and `blockers`. Missing
+/// parts are shown as a dash, so the update can be pasted as it is.
+fn standup_text(
+    last: Option<&LogEntry>,
+    goal: Option<&str>,
+    tasks: &[Task],
+    blockers: Option<&str>,
+) -> String {
+    let or_dash = |text: String| {
+        if text.is_empty() {
+            "—".to_string()
+        } else {
+            text
+        }
+    };
+
+    let mut done = last.map(|last| last.tasks.clone()).unwrap_or_default();
+    done.sort_by_key(|task| task.created_at);
+    let yesterday = match task_names(done.iter()) {
+        names if !names.is_empty() => names.join(", "),
+        _ => last.and_then(|last| last.goal.clone()).unwrap_or_default(),
+    };
+    let today = match goal {
+        Some(goal) => goal.to_string(),
+        None => task_names(
+            tasks
+                .iter()
+                .filter(|task| task.state == TaskState::InProgress),
+        )
+        .join(", "),
+    };
+    format!(
+        "Yesterday: {}\nToday: {}\nBlockers: {}",
+        or_dash(yesterday),
+        or_dash(today),
+        or_dash(blockers.unwrap_or_default().trim().to_string())
+    )
+}
+
+/// `standup [--blockers <text>] [--copy]`: prints a standup update from the
+/// last ended session and the goal or open tasks of the current one, ready
+/// to paste into a chat.
+pub fn standup(args: &mut Args, goal: Option<&str>, tasks: &[Task]) {
+    let blockers = args.value("--blockers");
+    let copy = args.flag("--copy");
+    let text = standup_text(log::last().as_ref(), goal, tasks, blockers.as_deref());
+    println!("{}", text);
+    if copy {
+        match clipboard::copy(&text) {
+            Ok(()) => cli::info("Standup copied to the clipboard"),
+            Err(e) => error::print(
+                ErrorCode::CommandFailed,
+                &format!("ERROR: Cannot copy the standup: {}", e),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_standup_text() {
+        let at = |hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, 4, hour, 0, 0)
+                .unwrap()
+        };
+        let last = LogEntry {
+            start_time: at(8),
+            end_time: at(16),
+            tasks: vec![
+                Task::new("Review", at(11)),
+                Task::new("Fix the parser", at(9)),
+                Task::new("Review", at(14)),
+            ],
+            ..Default::default()
+        };
+        let mut open = Task::new("Write docs", at(15));
+        open.state = TaskState::InProgress;
+        let tasks = [Task::new("Done already", at(15)), open];
+
+        assert_eq!(
+            standup_text(Some(&last), None, &tasks, None),
+            "Yesterday: Fix the parser, Review\nToday: Write docs\nBlockers: —"
+        );
+        assert_eq!(
+            standup_text(None, Some("Ship 1.2"), &[], Some("Waiting on review")),
+            "Yesterday: —\nToday: Ship 1.2\nBlockers: Waiting on review"
+        );
+    }
+}
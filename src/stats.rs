@@ -0,0 +1,282 @@
+use chrono::Timelike;
+
+use crate::{
+    cli::Args,
+    config::Config,
+    format_duration, log,
+    log::LogEntry,
+    report::{self, Day},
+    summary,
+    table::{self, Table},
+    week::Weeks,
+};
+
+/// Stretches on one task at least this long count as focused time.
+const FOCUS_MINUTES: i64 = 25;
+
+/// How fragmented the work of a day was.
+#[derive(PartialEq, Debug)]
+pub struct Focus {
+    pub worked: chrono::Duration,
+    /// Changes from one task to another within a session.
+    pub switches: usize,
+    /// The longest time on one task without switching.
+    pub longest: chrono::Duration,
+    /// Time in stretches of at least `FOCUS_MINUTES`.
+    pub focused: chrono::Duration,
+}
+
+impl Focus {
+    pub fn switches_per_hour(&self) -> f64 {
+        let hours = self.worked.num_seconds() as f64 / 3600.0;
+        if hours == 0.0 {
+            return 0.0;
+        }
+        self.switches as f64 / hours
+    }
+
+    /// The share of the time worked spent in focused stretches, in percent.
+    pub fn score(&self) -> f64 {
+        if self.worked.num_seconds() == 0 {
+            return 0.0;
+        }
+        self.focused.num_seconds() as f64 / self.worked.num_seconds() as f64 * 100.0
+    }
+}
+
+/// The stretches of `entry` spent on one task. A task covers the time since
+/// the task before it, or from its start to its end when it was timed, and
+/// consecutive tasks with the same name are one stretch. Time no task covers
+/// is counted as more of the stretch before it.
+fn stretches(entry: &LogEntry) -> Vec<chrono::Duration> {
+    let mut stretches: Vec<(Option<String>, chrono::Duration)> = vec![];
+    let mut previous = entry.start_time;
+    for (task, start, end) in summary::task_spans(entry.start_time, entry.end_time, &entry.tasks) {
+        let gap = (start - previous).max(chrono::Duration::zero());
+        match stretches.last_mut() {
+            Some((_, duration)) => *duration += gap,
+            None if !gap.is_zero() => stretches.push((None, gap)),
+            None => {}
+        }
+        let name = task.name.trim().to_lowercase();
+        match stretches.last_mut() {
+            Some((Some(last), duration)) if *last == name => *duration += end - start,
+            _ => stretches.push((Some(name), end - start)),
+        }
+        previous = previous.max(end);
+    }
+    match stretches.last_mut() {
+        Some((_, duration)) => *duration += entry.end_time - previous,
+        None => stretches.push((None, entry.duration())),
+    }
+    stretches
+        .into_iter()
+        .map(|(_, duration)| duration)
+        .collect()
+}
+
+/// The focus metrics of the sessions of `day`.
+pub fn focus(day: &Day) -> Focus {
+    let minimum = chrono::Duration::try_minutes(FOCUS_MINUTES).unwrap();
+    let mut focus = Focus {
+        worked: day.worked(),
+        switches: 0,
+        longest: chrono::Duration::zero(),
+        focused: chrono::Duration::zero(),
+    };
+    for session in &day.sessions {
+        let stretches = stretches(session);
+        focus.switches += stretches.len().saturating_sub(1);
+        for stretch in stretches {
+            focus.longest = focus.longest.max(stretch);
+            if stretch >= minimum {
+                focus.focused += stretch;
+            }
+        }
+    }
+    focus
+}
+
+/// The time tracked and the tasks added in each hour of the day over the
+/// sessions of `days`, indexed by hour.
+pub fn by_hour(days: &[Day]) -> [(chrono::Duration, usize); 24] {
+    let mut hours = [(chrono::Duration::zero(), 0); 24];
+    for session in days.iter().flat_map(|day| &day.sessions) {
+        let mut time = session.start_time;
+        while time < session.end_time {
+            let hour_start = time
+                .with_minute(0)
+                .and_then(|time| time.with_second(0))
+                .and_then(|time| time.with_nanosecond(0))
+                .unwrap();
+            let next = (hour_start + chrono::Duration::try_hours(1).unwrap()).min(session.end_time);
+            hours[time.hour() as usize].0 += next - time;
+            time = next;
+        }
+        for task in &session.tasks {
+            hours[task.created_at.hour() as usize].1 += 1;
+        }
+    }
+    hours
+}
+
+/// Prints the time tracked and the tasks added per hour of the day, with a
+/// bar for the time, from the first to the last hour with any.
+fn print_by_hour(days: &[Day], style: table::Style) {
+    let hours = by_hour(days);
+    let active = |hour: &usize| !hours[*hour].0.is_zero() || hours[*hour].1 > 0;
+    let (Some(first), Some(last)) = ((0..24).find(active), (0..24).rev().find(active)) else {
+        return;
+    };
+    let most = hours.iter().map(|(time, _)| *time).max().unwrap();
+    let mut table = Table::new(&["Hour", "Time", "Tasks", ""]).right(1).right(2);
+    for (hour, (time, tasks)) in hours.iter().enumerate().take(last + 1).skip(first) {
+        let width = (time.num_seconds() as f64 / most.num_seconds().max(1) as f64 * 30.0).round();
+        table.row(vec![
+            format!("{:02}:00", hour),
+            format_duration(*time),
+            tasks.to_string(),
+            "#".repeat(width as usize),
+        ]);
+    }
+    for line in table.render(style) {
+        println!("{}", line);
+    }
+}
+
+/// `stats [--from YYYY-MM-DD] [--to YYYY-MM-DD]`: shows the task switches
+/// per hour, the longest stretch on one task and the focus score of each
+/// day, this week by default. With `--by-hour`, shows the time tracked and
+/// the tasks added in each hour of the day instead.
+pub fn stats(args: &mut Args) {
+    let style = table::Style::from_args(args);
+    let hourly = args.flag("--by-hour");
+    let Ok(from) = report::parse_date_option(args, "--from") else {
+        return;
+    };
+    let Ok(to) = report::parse_date_option(args, "--to") else {
+        return;
+    };
+    let today = chrono::Local::now().date_naive();
+    let from = match (from, to) {
+        (None, None) => Some(Weeks::from_config(&Config::current()).first_day(today)),
+        (from, _) => from,
+    };
+    let to = to.unwrap_or(today);
+    let days = report::days(&log::load_range(from, Some(to)))
+        .into_iter()
+        .filter(|day| from.is_none_or(|from| day.date >= from) && day.date <= to)
+        .collect::<Vec<Day>>();
+    if days.is_empty() {
+        println!("No job sessions logged in this period");
+        return;
+    }
+
+    let period = format!(
+        "{} - {}",
+        days[0].date.format("%d-%m-%Y"),
+        days[days.len() - 1].date.format("%d-%m-%Y")
+    );
+    if hourly {
+        println!("Time by hour of the day for {}", period);
+        print_by_hour(&days, style);
+        return;
+    }
+
+    println!("Focus for {}", period);
+    let mut table = Table::new(&[
+        "Day",
+        "Time",
+        "Switches",
+        "Per hour",
+        "Longest stretch",
+        "Focus",
+    ])
+    .right(1)
+    .right(2)
+    .right(3)
+    .right(4)
+    .right(5);
+    for day in &days {
+        let focus = focus(day);
+        table.row(vec![
+            day.date.format("%a %d-%m-%Y").to_string(),
+            format_duration(focus.worked),
+            focus.switches.to_string(),
+            format!("{:.1}", focus.switches_per_hour()),
+            format_duration(focus.longest),
+            format!("{:.0}%", focus.score()),
+        ]);
+    }
+    for line in table.render(style) {
+        println!("{}", line);
+    }
+    println!(
+        "Focus is the share of the time spent in stretches of {} minutes or more on one task",
+        FOCUS_MINUTES
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Task;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_focus() {
+        let at = |hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, 3, hour, minute, 0)
+                .unwrap()
+        };
+        let minutes = |minutes| chrono::Duration::try_minutes(minutes).unwrap();
+        // 60m parser, 10m mail, 20m review, then the parser until the end.
+        let morning = LogEntry {
+            start_time: at(8, 0),
+            end_time: at(10, 0),
+            tasks: vec![
+                Task::new("Fix parser", at(8, 30)),
+                Task::new("fix parser", at(9, 0)),
+                Task::new("Mail", at(9, 10)),
+                Task::new("Review", at(9, 30)),
+                Task::new("Fix parser", at(9, 40)),
+            ],
+            ..Default::default()
+        };
+        let afternoon = LogEntry {
+            start_time: at(13, 0),
+            end_time: at(13, 20),
+            ..Default::default()
+        };
+        assert_eq!(
+            stretches(&morning),
+            vec![minutes(60), minutes(10), minutes(20), minutes(30)]
+        );
+
+        let days = report::days(&[morning, afternoon]);
+        let focus = focus(&days[0]);
+        assert_eq!(
+            focus,
+            Focus {
+                worked: minutes(140),
+                switches: 3,
+                longest: minutes(60),
+                focused: minutes(90),
+            }
+        );
+        assert_eq!(format!("{:.1}", focus.switches_per_hour()), "1.3");
+        assert_eq!(format!("{:.0}", focus.score()), "64");
+        let short = Focus {
+            worked: chrono::Duration::try_milliseconds(500).unwrap(),
+            ..focus
+        };
+        assert_eq!(short.score(), 0.0);
+
+        let hours = by_hour(&days);
+        assert_eq!(hours[8], (minutes(60), 1));
+        assert_eq!(hours[9], (minutes(60), 4));
+        assert_eq!(hours[13], (minutes(20), 0));
+        assert_eq!(hours[12], (minutes(0), 0));
+    }
+}
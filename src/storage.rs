@@ -0,0 +1,336 @@
+use crate::log::{month_of, LogEntry};
+
+/// Where the log of job sessions and the running session are kept. Rules on
+/// the log, such as closed months, are applied by the `log` functions before
+/// a storage is written, so a storage only reads and writes entries.
+pub trait Storage {
+    /// Every entry in the log, ordered by month.
+    fn load(&mut self) -> Vec<LogEntry>;
+
+    /// Appends `entry` with the next free id and returns the id.
+    fn append(&mut self, entry: LogEntry) -> Result<u64, String>;
+
+    /// Replaces the whole log with `entries`.
+    fn save(&mut self, entries: &[LogEntry]) -> Result<(), String>;
+
+    /// The entries that started in `month` (`YYYY-MM`).
+    fn load_month(&mut self, month: &str) -> Vec<LogEntry> {
+        self.load()
+            .into_iter()
+            .filter(|entry| month_of(&entry.start_time) == month)
+            .collect()
+    }
+
+    /// The entries of every month overlapping `from..=to`.
+    fn load_range(
+        &mut self,
+        from: Option<chrono::NaiveDate>,
+        to: Option<chrono::NaiveDate>,
+    ) -> Vec<LogEntry> {
+        let from = from.map(|date| date.format("%Y-%m").to_string());
+        let to = to.map(|date| date.format("%Y-%m").to_string());
+        self.load()
+            .into_iter()
+            .filter(|entry| {
+                let month = month_of(&entry.start_time);
+                from.as_ref().is_none_or(|from| &month >= from)
+                    && to.as_ref().is_none_or(|to| &month <= to)
+            })
+            .collect()
+    }
+
+    /// The session that ended last.
+    fn last(&mut self) -> Option<LogEntry> {
+        self.load().into_iter().max_by_key(|entry| entry.end_time)
+    }
+
+    /// The running session as it was saved, as JSON, if one was saved.
+    fn load_session(&mut self) -> Option<String>;
+
+    /// Replaces the saved running session with `data`.
+    fn save_session(&mut self, data: &str) -> Result<(), String>;
+
+    /// The journal of changes to the running session since it was saved,
+    /// one line per change.
+    fn load_journal(&mut self) -> String;
+
+    /// Appends `line` to the journal.
+    fn append_journal(&mut self, line: &str) -> Result<(), String>;
+
+    fn clear_journal(&mut self);
+}
+
+/// How the data folder is written.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageMode {
+    /// Only jobclock changes the files.
+    #[default]
+    Local,
+    /// The folder is kept in sync between machines by a service such as
+    /// Dropbox or iCloud Drive, which can replace files at any time and
+    /// leaves conflicted copies when two machines change a file.
+    Synced,
+}
+
+/// Merges two versions of a list that were changed apart from each other
+/// since `base`, matching items by `key`. Items added on either side are
+/// kept and items removed on either side are dropped. An item changed on
+/// both sides is taken from `ours`.
+pub fn merge<T, K>(base: &[T], ours: &[T], theirs: &[T], key: impl Fn(&T) -> K) -> Vec<T>
+where
+    T: serde::Serialize + Clone,
+    K: Ord,
+{
+    let same = |a: &T, b: &T| serde_json::to_value(a).ok() == serde_json::to_value(b).ok();
+    let by_key = |items: &[T]| {
+        items
+            .iter()
+            .map(|item| (key(item), item.clone()))
+            .collect::<std::collections::BTreeMap<K, T>>()
+    };
+    let (base_items, their_items) = (by_key(base), by_key(theirs));
+    let mut merged = vec![];
+    for item in ours {
+        let key = key(item);
+        match (base_items.get(&key), their_items.get(&key)) {
+            (Some(base), None) if same(base, item) => {}
+            (Some(base), Some(theirs)) if same(base, item) => merged.push(theirs.clone()),
+            _ => merged.push(item.clone()),
+        }
+    }
+    let our_items = by_key(ours);
+    for item in theirs {
+        let key = key(item);
+        if !base_items.contains_key(&key) && !our_items.contains_key(&key) {
+            merged.push(item.clone());
+        }
+    }
+    merged
+}
+
+/// Options for opening a data file for writing, creating it readable and
+/// writable by the user only, since time data can be private.
+pub fn private_file() -> std::fs::OpenOptions {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+}
+
+/// Writes `data` to the data file `file`, creating it like `private_file`.
+pub fn write(file: &std::path::Path, data: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let mut file = private_file().truncate(true).open(file)?;
+    std::io::Write::write_all(&mut file, data.as_ref())
+}
+
+/// Creates the data folder `folder` and the folders above it that are
+/// missing, accessible by the user only.
+pub fn create_folder(folder: &std::path::Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(folder)
+}
+
+/// A log and session kept in memory only, used by the unit tests.
+#[cfg(test)]
+#[derive(Default)]
+pub struct Memory {
+    entries: Vec<LogEntry>,
+    session: Option<String>,
+    journal: String,
+}
+
+#[cfg(test)]
+impl Storage for Memory {
+    fn load(&mut self) -> Vec<LogEntry> {
+        let mut entries = self.entries.clone();
+        entries.sort_by_key(|entry| month_of(&entry.start_time));
+        entries
+    }
+
+    fn append(&mut self, mut entry: LogEntry) -> Result<u64, String> {
+        entry.id = self.entries.iter().map(|entry| entry.id).max().unwrap_or(0) + 1;
+        let id = entry.id;
+        self.entries.push(entry);
+        Ok(id)
+    }
+
+    fn save(&mut self, entries: &[LogEntry]) -> Result<(), String> {
+        self.entries = entries.to_vec();
+        Ok(())
+    }
+
+    fn load_session(&mut self) -> Option<String> {
+        self.session.clone()
+    }
+
+    fn save_session(&mut self, data: &str) -> Result<(), String> {
+        self.session = Some(data.to_string());
+        Ok(())
+    }
+
+    fn load_journal(&mut self) -> String {
+        self.journal.clone()
+    }
+
+    fn append_journal(&mut self, line: &str) -> Result<(), String> {
+        self.journal.push_str(line);
+        self.journal.push('\n');
+        Ok(())
+    }
+
+    fn clear_journal(&mut self) {
+        self.journal.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::{JsonFiles, SyncedFiles};
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_storages() {
+        let entry = |month, day| LogEntry {
+            start_time: chrono::Local
+                .with_ymd_and_hms(2024, month, day, 9, 0, 0)
+                .unwrap(),
+            end_time: chrono::Local
+                .with_ymd_and_hms(2024, month, day, 12, 0, 0)
+                .unwrap(),
+            ..Default::default()
+        };
+        let folder = std::env::temp_dir().join(format!("jobclock-storage-{}", std::process::id()));
+        let storages: Vec<Box<dyn Storage>> = vec![
+            Box::new(Memory::default()),
+            Box::new(JsonFiles::new(folder.clone())),
+            Box::new(SyncedFiles::new(folder.join("synced"))),
+        ];
+        for mut storage in storages {
+            assert_eq!(storage.append(entry(3, 12)), Ok(1));
+            assert_eq!(storage.append(entry(2, 5)), Ok(2));
+            assert_eq!(storage.append(entry(3, 1)), Ok(3));
+            let ids = |entries: Vec<LogEntry>| entries.iter().map(|e| e.id).collect::<Vec<u64>>();
+            assert_eq!(ids(storage.load()), vec![2, 1, 3]);
+            assert_eq!(ids(storage.load_month("2024-03")), vec![1, 3]);
+            let march = chrono::NaiveDate::from_ymd_opt(2024, 3, 20);
+            assert_eq!(ids(storage.load_range(march, None)), vec![1, 3]);
+            assert_eq!(storage.last().map(|e| e.id), Some(1));
+
+            let mut entries = storage.load();
+            entries.retain(|entry| entry.id != 1);
+            storage.save(&entries).unwrap();
+            assert_eq!(ids(storage.load()), vec![2, 3]);
+            assert_eq!(storage.last().map(|e| e.id), Some(3));
+
+            assert_eq!(storage.load_session(), None);
+            storage.save_session("{}").unwrap();
+            assert_eq!(storage.load_session(), Some("{}".to_string()));
+            storage.append_journal("a").unwrap();
+            storage.append_journal("b").unwrap();
+            assert_eq!(storage.load_journal(), "a\nb\n");
+            storage.clear_journal();
+            assert_eq!(storage.load_journal(), "");
+        }
+        std::fs::remove_dir_all(folder).ok();
+    }
+
+    #[test]
+    fn test_synced_files() {
+        let entry = |day, id| LogEntry {
+            id,
+            start_time: chrono::Local
+                .with_ymd_and_hms(2024, 3, day, 9, 0, 0)
+                .unwrap(),
+            end_time: chrono::Local
+                .with_ymd_and_hms(2024, 3, day, 12, 0, 0)
+                .unwrap(),
+            ..Default::default()
+        };
+        let folder = std::env::temp_dir().join(format!("jobclock-synced-{}", std::process::id()));
+        let mut synced = SyncedFiles::new(folder.clone());
+        synced.append(entry(1, 0)).unwrap();
+        synced.append(entry(4, 0)).unwrap();
+        let ids = |entries: Vec<LogEntry>| {
+            entries
+                .iter()
+                .map(|entry| (entry.id, entry.start_time.format("%d").to_string()))
+                .collect::<Vec<(u64, String)>>()
+        };
+
+        // Another machine adds a session while we remove one.
+        let mut entries = synced.load();
+        JsonFiles::new(folder.clone()).append(entry(8, 0)).unwrap();
+        entries.retain(|entry| entry.id != 1);
+        synced.save(&entries).unwrap();
+        assert_eq!(ids(synced.load()), vec![(2, "04".into()), (3, "08".into())]);
+
+        // Both machines appended session 3 while apart.
+        let copy = folder.join("log").join("2024-03 (conflicted copy).json");
+        std::fs::write(
+            &copy,
+            serde_json::to_string(&[entry(4, 2), entry(9, 3)]).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            ids(synced.load()),
+            vec![(2, "04".into()), (3, "08".into()), (4, "09".into())]
+        );
+        assert!(!copy.exists());
+        std::fs::remove_dir_all(folder).ok();
+    }
+
+    #[test]
+    fn test_json_files_save() {
+        let entry = |month| LogEntry {
+            start_time: chrono::Local
+                .with_ymd_and_hms(2024, month, 1, 9, 0, 0)
+                .unwrap(),
+            end_time: chrono::Local
+                .with_ymd_and_hms(2024, month, 1, 12, 0, 0)
+                .unwrap(),
+            ..Default::default()
+        };
+        let folder = std::env::temp_dir().join(format!("jobclock-json-{}", std::process::id()));
+        let mut files = JsonFiles::new(folder.clone());
+        files.append(entry(2)).unwrap();
+        files.append(entry(3)).unwrap();
+        let mut entries = files.load();
+        entries.retain(|entry| entry.id != 1);
+        files.save(&entries).unwrap();
+
+        let mut names = std::fs::read_dir(folder.join("log"))
+            .unwrap()
+            .map(|file| file.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<String>>();
+        names.sort();
+        assert_eq!(names, vec!["2024-03.json", "index.json"]);
+
+        // A month that cannot be read is left out, and the log is not
+        // written over it.
+        files.append(entry(4)).unwrap();
+        std::fs::write(folder.join("log").join("2024-03.json"), "[{").unwrap();
+        assert_eq!(files.load().len(), 1);
+        assert!(files.save(&[]).is_err());
+        assert!(files.append(entry(3)).is_err());
+        assert_eq!(files.last().map(|e| e.id), Some(3));
+        std::fs::remove_dir_all(folder).ok();
+    }
+
+    #[test]
+    fn test_merge() {
+        let base = [(1, "a"), (2, "b"), (3, "c"), (4, "d")];
+        // We changed 2 and removed 3, they changed 1 and 2, removed 4 and added 5.
+        let ours = [(1, "a"), (2, "ours"), (4, "d"), (6, "f")];
+        let theirs = [(1, "theirs"), (2, "theirs"), (3, "c"), (5, "e")];
+        assert_eq!(
+            merge(&base, &ours, &theirs, |(id, _)| *id),
+            vec![(1, "theirs"), (2, "ours"), (6, "f"), (5, "e")]
+        );
+    }
+}
@@ -0,0 +1,508 @@
+use crate::{format_duration, tags, Task};
+
+/// Pairs each task with the time spent on it, counted from the previous task
+/// (or the start of the session for the first one). A timed task begun with
+/// `task start` is measured from its start to its end instead, or to now
+/// while it runs. Tasks are returned in chronological order.
+pub fn task_durations(
+    start_time: chrono::DateTime<chrono::Local>,
+    tasks: &[Task],
+) -> Vec<(Task, chrono::Duration)> {
+    let mut tasks = tasks.to_vec();
+    tasks.sort_by_key(|task| task.created_at);
+
+    let mut previous = start_time;
+    let mut durations = vec![];
+    for task in tasks {
+        let duration = match task.started_at {
+            Some(started_at) => {
+                let ended_at = task.ended_at.unwrap_or_else(chrono::Local::now);
+                previous = previous.max(ended_at);
+                ended_at - started_at
+            }
+            None => {
+                let duration = task.created_at - previous;
+                previous = previous.max(task.created_at);
+                duration
+            }
+        };
+        durations.push((task, duration.max(chrono::Duration::zero())));
+    }
+    durations
+}
+
+/// Pairs each task of a session from `start_time` to `end_time` with the
+/// time it covers, counted like `task_durations`: from the previous task, or
+/// from its start to its end for a timed task, kept within the session.
+/// Tasks are returned in chronological order.
+pub fn task_spans(
+    start_time: chrono::DateTime<chrono::Local>,
+    end_time: chrono::DateTime<chrono::Local>,
+    tasks: &[Task],
+) -> Vec<(
+    Task,
+    chrono::DateTime<chrono::Local>,
+    chrono::DateTime<chrono::Local>,
+)> {
+    let mut tasks = tasks.to_vec();
+    tasks.sort_by_key(|task| task.created_at);
+
+    let within = |time: chrono::DateTime<chrono::Local>| time.max(start_time).min(end_time);
+    let mut previous = start_time;
+    let mut spans = vec![];
+    for task in tasks {
+        let (start, end) = match task.started_at {
+            Some(started_at) => (
+                within(started_at),
+                within(task.ended_at.unwrap_or(end_time)),
+            ),
+            None => (previous, within(task.created_at).max(previous)),
+        };
+        previous = previous.max(end);
+        spans.push((task, start, end.max(start)));
+    }
+    spans
+}
+
+/// How the end summary is written: as a list of tasks per project, or as a
+/// paragraph of prose.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryStyle {
+    #[default]
+    Bullets,
+    /// One sentence listing the tasks.
+    Concise,
+    /// The total time, then a sentence per project with its time and tasks.
+    Detailed,
+}
+
+impl std::str::FromStr for SummaryStyle {
+    type Err = String;
+
+    fn from_str(style: &str) -> Result<SummaryStyle, String> {
+        match style {
+            "bullets" => Ok(SummaryStyle::Bullets),
+            "concise" => Ok(SummaryStyle::Concise),
+            "detailed" => Ok(SummaryStyle::Detailed),
+            _ => Err(format!(
+                "Unknown summary style: {} (expected bullets, concise or detailed)",
+                style
+            )),
+        }
+    }
+}
+
+/// The words a summary paragraph is written with.
+pub struct Language {
+    pub code: &'static str,
+    worked_on: &'static str,
+    and: &'static str,
+    other: &'static str,
+    /// Sentence with the total time and number of tasks.
+    total: fn(&str, usize) -> String,
+}
+
+pub const LANGUAGES: [Language; 4] = [
+    Language {
+        code: "en",
+        worked_on: "Worked on",
+        and: "and",
+        other: "Other",
+        total: |duration, tasks| format!("Worked {} on {} tasks.", duration, tasks),
+    },
+    Language {
+        code: "nb",
+        worked_on: "Jobbet med",
+        and: "og",
+        other: "Annet",
+        total: |duration, tasks| format!("Jobbet {} med {} oppgaver.", duration, tasks),
+    },
+    Language {
+        code: "de",
+        worked_on: "Gearbeitet an",
+        and: "und",
+        other: "Sonstiges",
+        total: |duration, tasks| format!("{} an {} Aufgaben gearbeitet.", duration, tasks),
+    },
+    Language {
+        code: "es",
+        worked_on: "Trabajé en",
+        and: "y",
+        other: "Otros",
+        total: |duration, tasks| format!("Trabajé {} en {} tareas.", duration, tasks),
+    },
+];
+
+pub fn language(code: &str) -> Result<&'static Language, String> {
+    LANGUAGES
+        .iter()
+        .find(|language| language.code == code)
+        .ok_or_else(|| {
+            format!(
+                "Unknown summary language: {} (available: {})",
+                code,
+                LANGUAGES.map(|language| language.code).join(", ")
+            )
+        })
+}
+
+/// Joins names into a list such as "A, B and C".
+fn join_list(names: &[&str], and: &str) -> String {
+    match names {
+        [] => String::new(),
+        [name] => name.to_string(),
+        [rest @ .., last] => format!("{} {} {}", rest.join(", "), and, last),
+    }
+}
+
+/// How task names are listed in the end summary. By default every task is a
+/// bullet; with a `separator` the names are joined into one line instead.
+/// The concise and detailed styles write a paragraph in `language`.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct SummaryFormat {
+    pub separator: Option<String>,
+    pub bullet: String,
+    /// List identical task names only once.
+    pub dedupe: bool,
+    pub style: SummaryStyle,
+    pub language: String,
+}
+
+impl Default for SummaryFormat {
+    fn default() -> SummaryFormat {
+        SummaryFormat {
+            separator: None,
+            bullet: "- ".to_string(),
+            dedupe: false,
+            style: SummaryStyle::Bullets,
+            language: "en".to_string(),
+        }
+    }
+}
+
+impl SummaryFormat {
+    /// The task names, trimmed and without identical names when deduping.
+    fn names<'a>(&self, tasks: &'a [String]) -> Vec<&'a str> {
+        let mut names: Vec<&str> = vec![];
+        for task in tasks {
+            let name = task.trim();
+            if !self.dedupe || !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Writes the tasks as a paragraph in the concise or detailed style.
+    pub fn paragraph(
+        &self,
+        language: &Language,
+        start_time: chrono::DateTime<chrono::Local>,
+        tasks: &[Task],
+    ) -> String {
+        let sentence = |lead: String, names: &[String]| {
+            let names = self.names(names);
+            let names = names
+                .iter()
+                .map(|name| name.trim_end_matches('.'))
+                .collect::<Vec<&str>>();
+            format!("{} {}.", lead, join_list(&names, language.and))
+        };
+        let names = |tasks: &[Task]| {
+            let mut tasks = tasks.to_vec();
+            tasks.sort_by_key(|task| task.created_at);
+            tasks
+                .into_iter()
+                .map(|task| task.name)
+                .collect::<Vec<String>>()
+        };
+
+        match self.style {
+            SummaryStyle::Detailed => {
+                let total = task_durations(start_time, tasks)
+                    .iter()
+                    .fold(chrono::Duration::zero(), |total, (_, duration)| {
+                        total + *duration
+                    });
+                let mut sentences = vec![(language.total)(&format_duration(total), tasks.len())];
+                for group in group_by_project(start_time, tasks) {
+                    sentences.push(sentence(
+                        format!(
+                            "{} ({}):",
+                            group.name.as_deref().unwrap_or(language.other),
+                            format_duration(group.duration)
+                        ),
+                        &group.tasks,
+                    ));
+                }
+                sentences.join(" ")
+            }
+            _ => sentence(language.worked_on.to_string(), &names(tasks)),
+        }
+    }
+
+    /// Formats `tasks` as summary lines. When joining, a name that already
+    /// ends in punctuation is followed only by the whitespace of the
+    /// separator, so "Fix bug." does not become "Fix bug..".
+    pub fn lines(&self, tasks: &[String]) -> Vec<String> {
+        let names = self.names(tasks);
+
+        match &self.separator {
+            None => names
+                .iter()
+                .map(|name| format!("{}{}", self.bullet, name))
+                .collect(),
+            Some(separator) => {
+                let spacing = &separator[separator.trim_end().len()..];
+                let mut line = String::new();
+                for (index, name) in names.iter().enumerate() {
+                    if index > 0 {
+                        let previous = names[index - 1];
+                        if previous.ends_with(|c: char| c.is_ascii_punctuation()) {
+                            line.push_str(spacing);
+                        } else {
+                            line.push_str(separator);
+                        }
+                    }
+                    line.push_str(name);
+                }
+                vec![line]
+            }
+        }
+    }
+}
+
+/// Tasks sharing a project, or a tag when grouping by tag.
+pub struct Group {
+    pub name: Option<String>,
+    pub duration: chrono::Duration,
+    pub tasks: Vec<String>,
+}
+
+fn add_to_group(
+    groups: &mut Vec<Group>,
+    name: Option<String>,
+    task: &Task,
+    duration: chrono::Duration,
+) {
+    let group = match groups.iter().position(|group| group.name == name) {
+        Some(index) => &mut groups[index],
+        None => {
+            groups.push(Group {
+                name,
+                duration: chrono::Duration::zero(),
+                tasks: vec![],
+            });
+            groups.last_mut().unwrap()
+        }
+    };
+    group.duration += duration;
+    group.tasks.push(task.name.clone());
+}
+
+/// Groups tasks by project, with tasks without a project last.
+pub fn group_by_project(start_time: chrono::DateTime<chrono::Local>, tasks: &[Task]) -> Vec<Group> {
+    let mut groups = vec![];
+    for (task, duration) in task_durations(start_time, tasks) {
+        add_to_group(&mut groups, task.project.clone(), &task, duration);
+    }
+    groups.sort_by_key(|group| group.name.is_none());
+    groups
+}
+
+/// Groups tasks by tag. A task with several tags is counted in each of them,
+/// and tasks without tags are left out.
+pub fn group_by_tag(start_time: chrono::DateTime<chrono::Local>, tasks: &[Task]) -> Vec<Group> {
+    let mut groups = vec![];
+    for (task, duration) in task_durations(start_time, tasks) {
+        // Aliases count towards their tag, once per task.
+        let mut names: Vec<String> = vec![];
+        for tag in &task.tags {
+            let tag = tags::canonical(tag);
+            if !names.contains(&tag) {
+                names.push(tag);
+            }
+        }
+        for tag in names {
+            add_to_group(&mut groups, Some(tag), &task, duration);
+        }
+    }
+    groups
+}
+
+/// The summary of `tasks` as lines of text, in the configured format.
+pub fn summary_lines(
+    start_time: chrono::DateTime<chrono::Local>,
+    tasks: &[Task],
+    format: &SummaryFormat,
+) -> Vec<String> {
+    if format.style != SummaryStyle::Bullets {
+        let language = language(&format.language).unwrap_or_else(|e| {
+            println!("WARNING: {}", e);
+            &LANGUAGES[0]
+        });
+        return vec![format.paragraph(language, start_time, tasks)];
+    }
+    let mut lines = vec![];
+    let groups = group_by_project(start_time, tasks);
+    if groups.len() == 1 && groups[0].name.is_none() {
+        lines.extend(format.lines(&groups[0].tasks));
+    } else {
+        for group in groups {
+            lines.push(format!(
+                "{} ({})",
+                group.name.as_deref().unwrap_or("No project"),
+                format_duration(group.duration)
+            ));
+            for line in format.lines(&group.tasks) {
+                lines.push(format!("  {}", line));
+            }
+        }
+    }
+
+    let tags = group_by_tag(start_time, tasks);
+    if !tags.is_empty() {
+        lines.push("Tags:".to_string());
+        for tag in tags {
+            lines.push(format!(
+                "  #{} - {}",
+                tag.name.unwrap(),
+                format_duration(tag.duration)
+            ));
+        }
+    }
+    lines
+}
+
+/// The summary shown when a session ends, after a blank line.
+pub fn render_summary(
+    start_time: chrono::DateTime<chrono::Local>,
+    tasks: &[Task],
+    format: &SummaryFormat,
+) -> String {
+    if tasks.is_empty() {
+        return "No tasks added\n".to_string();
+    }
+
+    let mut text = "\nSummary:\n".to_string();
+    for line in summary_lines(start_time, tasks, format) {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_group_by_project() {
+        let at = |hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 13, hour, minute, 0)
+                .unwrap()
+        };
+        let task = |name, created_at, project: Option<&str>, tags: &[&str]| {
+            let mut task = Task::new(name, created_at);
+            task.project = project.map(|project| project.to_string());
+            task.tags = tags.iter().map(|tag| tag.to_string()).collect();
+            task
+        };
+        let tasks = vec![
+            task("Write docs", at(11, 0), None, &[]),
+            task("Fix parser", at(9, 30), Some("acme"), &["client"]),
+            task("Review", at(10, 0), Some("acme"), &["client", "review"]),
+        ];
+
+        let groups = group_by_project(at(9, 0), &tasks);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name.as_deref(), Some("acme"));
+        assert_eq!(groups[0].tasks, vec!["Fix parser", "Review"]);
+        assert_eq!(groups[0].duration, chrono::Duration::try_hours(1).unwrap());
+        assert_eq!(groups[1].name, None);
+        assert_eq!(groups[1].duration, chrono::Duration::try_hours(1).unwrap());
+
+        let tags = group_by_tag(at(9, 0), &tasks);
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].duration, chrono::Duration::try_hours(1).unwrap());
+        assert_eq!(tags[1].tasks, vec!["Review"]);
+    }
+
+    #[test]
+    fn test_timed_task_durations() {
+        let at = |hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 13, hour, minute, 0)
+                .unwrap()
+        };
+        // Tasks saved before timed tasks load as instant tasks.
+        let review: Task = serde_json::from_str(
+            r#"{"name": "Review", "created_at": "2024-03-13T11:00:00+00:00"}"#,
+        )
+        .unwrap();
+        assert_eq!(review.started_at, None);
+
+        let mut fix = Task::new("Fix parser", at(9, 15));
+        fix.started_at = Some(at(9, 15));
+        fix.ended_at = Some(at(10, 0));
+        let tasks = vec![fix, Task::new("Deploy", at(10, 30))];
+        let minutes = |durations: Vec<(Task, chrono::Duration)>| {
+            durations
+                .iter()
+                .map(|(_, duration)| duration.num_minutes())
+                .collect::<Vec<i64>>()
+        };
+        // The instant task after a timed one counts from where it ended.
+        assert_eq!(minutes(task_durations(at(9, 0), &tasks)), vec![45, 30]);
+    }
+
+    #[test]
+    fn test_summary_format() {
+        let format = SummaryFormat {
+            separator: Some(". ".to_string()),
+            dedupe: true,
+            ..Default::default()
+        };
+        let names = ["Fix bug.", "Review", "v1.2 release", "Review"].map(String::from);
+        assert_eq!(format.lines(&names), vec!["Fix bug. Review. v1.2 release"]);
+        assert_eq!(
+            SummaryFormat::default().lines(&names[..2]),
+            vec!["- Fix bug.", "- Review"]
+        );
+    }
+
+    #[test]
+    fn test_paragraph() {
+        let at = |hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 13, hour, minute, 0)
+                .unwrap()
+        };
+        let mut review = Task::new("Review", at(10, 0));
+        review.project = Some("acme".to_string());
+        let tasks = vec![
+            Task::new("Fix bug.", at(9, 30)),
+            review,
+            Task::new("Write docs", at(11, 0)),
+        ];
+        let format = |style| SummaryFormat {
+            style,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            format(SummaryStyle::Concise).paragraph(language("nb").unwrap(), at(9, 0), &tasks),
+            "Jobbet med Fix bug, Review og Write docs."
+        );
+        assert_eq!(
+            format(SummaryStyle::Detailed).paragraph(&LANGUAGES[0], at(9, 0), &tasks),
+            "Worked 2h 0m 0s on 3 tasks. acme (0h 30m 0s): Review. \
+             Other (1h 30m 0s): Fix bug and Write docs."
+        );
+        assert!(language("xx").is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    cli::{self, Args},
+    error::{self, ErrorCode},
+    git, persistent_folder, process, queue, storage,
+};
+
+/// Whether the data folder is kept in a git repository by `sync git`.
+fn enabled() -> bool {
+    persistent_folder().join(".git").exists()
+}
+
+/// Whether the data folder tracks a branch on the remote, which it does
+/// once `sync git` has finished.
+fn tracking() -> bool {
+    enabled() && git(&["rev-parse", "--abbrev-ref", "@{upstream}"]).is_ok()
+}
+
+/// Runs git in the data folder, returning its output.
+fn git(args: &[&str]) -> Result<String, String> {
+    match process::output(
+        git::git_command()
+            .arg("-C")
+            .arg(persistent_folder())
+            .args(args),
+    ) {
+        Ok(output) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        }
+        Ok(output) => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        Err(e) => Err(format!("Failed to execute git: {}", e)),
+    }
+}
+
+/// The default branch named in the output of `git ls-remote --symref
+/// <remote> HEAD`, such as `main` for `ref: refs/heads/main\tHEAD`.
+fn remote_branch(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let target = line.strip_prefix("ref: ")?.split('\t').next()?;
+        target.strip_prefix("refs/heads/").map(str::to_string)
+    })
+}
+
+/// Rebases the data folder onto the remote, so a command sees the changes
+/// made on other machines. Failures are only warned about, as the data
+/// can still be used offline.
+pub fn pull() {
+    if !tracking() {
+        return;
+    }
+    if let Err(e) = git(&["pull", "--rebase", "--autostash", "--quiet"]) {
+        println!("WARNING: Cannot pull the data folder: {}", e);
+    }
+}
+
+/// Commits whatever `subcommand` changed in the data folder, and pushes
+/// the commits the remote does not have yet, including ones left over from
+/// working offline.
+pub fn commit(subcommand: &str) {
+    if !tracking() {
+        return;
+    }
+    let message = format!("jobclock {}", subcommand);
+    let result = git(&["status", "--porcelain"])
+        .and_then(|status| {
+            if status.is_empty() {
+                return Ok(());
+            }
+            git(&["add", "--all"])?;
+            git(&["commit", "--quiet", "--message", &message]).map(|_| ())
+        })
+        .and_then(|_| git(&["rev-list", "--count", "@{upstream}..HEAD"]))
+        .and_then(|ahead| match ahead.as_str() {
+            "0" => Ok(()),
+            _ => git(&["push", "--quiet"]).map(|_| ()),
+        });
+    if let Err(e) = result {
+        println!("WARNING: Cannot sync the data folder: {}", e);
+    }
+}
+
+/// Makes the data folder a git repository with `remote` as its origin. When
+/// the remote already has data, from another machine, that data replaces
+/// the local data files.
+fn setup(remote: &str) -> Result<(), String> {
+    if !enabled() {
+        storage::create_folder(&persistent_folder()).map_err(|e| e.to_string())?;
+        git(&["init", "--quiet"])?;
+    }
+    if git(&["remote", "get-url", "origin"]).is_ok() {
+        git(&["remote", "set-url", "origin", remote])?;
+    } else {
+        git(&["remote", "add", "origin", remote])?;
+    }
+
+    match remote_branch(&git(&["ls-remote", "--symref", "origin", "HEAD"])?) {
+        Some(branch) => {
+            if !cli::confirm(&format!(
+                "{} already has data. Replace the local data with it?",
+                remote
+            )) {
+                return Err("The remote was added but nothing was synced".to_string());
+            }
+            git(&["fetch", "--quiet", "origin"])?;
+            let upstream = format!("origin/{}", branch);
+            git(&["checkout", "--quiet", "--force", "-B", &branch, &upstream])?;
+            git(&["branch", "--quiet", "--set-upstream-to", &upstream])?;
+            // Files the remote does not have, such as a new month, are kept.
+            commit("sync");
+            Ok(())
+        }
+        None => {
+            git(&["add", "--all"])?;
+            git(&[
+                "commit",
+                "--quiet",
+                "--allow-empty",
+                "--message",
+                "jobclock sync",
+            ])?;
+            git(&["push", "--quiet", "--set-upstream", "origin", "HEAD"])?;
+            Ok(())
+        }
+    }
+}
+
+/// `sync git <remote>` keeps the data folder in a git repository, pulling
+/// before and pushing after every command. `sync` alone syncs now, and
+/// `sync status` lists the queued actions.
+pub fn sync(args: &mut Args) {
+    match args.shift().as_deref() {
+        Some("git") => {
+            let remote = args.text();
+            if remote.is_empty() {
+                error::print(ErrorCode::MissingArgument, "Usage: sync git <remote>");
+                return;
+            }
+            match setup(&remote) {
+                Ok(()) => cli::info(&format!("Data folder synced with {}", remote)),
+                Err(e) => error::print(
+                    ErrorCode::CommandFailed,
+                    &format!("ERROR: Cannot sync with {}: {}", remote, e),
+                ),
+            }
+        }
+        Some("status") => queue::status(),
+        Some(_) => error::print(
+            ErrorCode::InvalidArgument,
+            "Usage: sync [git <remote> | status]",
+        ),
+        // The data folder was pulled before the command and is pushed after it.
+        None if tracking() => cli::info("Data folder synced"),
+        None => println!("Sync is not set up, use sync git <remote>"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_branch() {
+        assert_eq!(
+            remote_branch("ref: refs/heads/main\tHEAD\n4f2a9c1\tHEAD\n").as_deref(),
+            Some("main")
+        );
+        assert_eq!(remote_branch(""), None);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::{cli::Args, color::visible_width};
+
+/// How a table is printed.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Style {
+    /// Columns padded to line up, indented like other report lines.
+    Aligned,
+    /// Aligned columns with lines drawn around the cells.
+    Borders,
+    /// Tab separated values without padding or truncation, for scripts.
+    Plain,
+}
+
+impl Style {
+    /// The style picked with `--plain` or `--borders`, aligned by default.
+    pub fn from_args(args: &mut Args) -> Style {
+        let plain = args.flag("--plain");
+        let borders = args.flag("--borders");
+        if plain {
+            Style::Plain
+        } else if borders {
+            Style::Borders
+        } else {
+            Style::Aligned
+        }
+    }
+}
+
+struct Column {
+    header: String,
+    right: bool,
+    max_width: Option<usize>,
+}
+
+/// Rows of text cells printed in columns.
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<String>>,
+}
+
+/// Shortens `text` to `width` characters, ending it with `…` when cut.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut text = text
+        .chars()
+        .take(width.saturating_sub(1))
+        .collect::<String>();
+    text.push('…');
+    text
+}
+
+fn pad(text: &str, width: usize, right: bool) -> String {
+    let fill = " ".repeat(width.saturating_sub(visible_width(text)));
+    if right {
+        format!("{}{}", fill, text)
+    } else {
+        format!("{}{}", text, fill)
+    }
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Table {
+        Table {
+            columns: headers
+                .iter()
+                .map(|header| Column {
+                    header: header.to_string(),
+                    right: false,
+                    max_width: None,
+                })
+                .collect(),
+            rows: vec![],
+        }
+    }
+
+    /// Aligns the cells of `column` to the right, as for numbers.
+    pub fn right(mut self, column: usize) -> Table {
+        self.columns[column].right = true;
+        self
+    }
+
+    /// Cuts the cells of `column` to `width` characters, except in plain
+    /// output.
+    pub fn truncate(mut self, column: usize, width: usize) -> Table {
+        self.columns[column].max_width = Some(width);
+        self
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The lines of the table in `style`, with a header line first.
+    pub fn render(&self, style: Style) -> Vec<String> {
+        let headers = self
+            .columns
+            .iter()
+            .map(|column| column.header.clone())
+            .collect::<Vec<String>>();
+        if style == Style::Plain {
+            return std::iter::once(&headers)
+                .chain(&self.rows)
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| cell.replace(['\t', '\n'], " "))
+                        .collect::<Vec<String>>()
+                        .join("\t")
+                })
+                .collect();
+        }
+
+        let rows = std::iter::once(headers)
+            .chain(self.rows.iter().map(|row| {
+                row.iter()
+                    .zip(&self.columns)
+                    .map(|(cell, column)| match column.max_width {
+                        Some(width) => truncate(cell, width),
+                        None => cell.clone(),
+                    })
+                    .collect()
+            }))
+            .collect::<Vec<Vec<String>>>();
+        let widths = (0..self.columns.len())
+            .map(|index| {
+                rows.iter()
+                    .map(|row| row.get(index).map_or(0, |cell| visible_width(cell)))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<usize>>();
+        let line = |row: &Vec<String>| {
+            self.columns
+                .iter()
+                .enumerate()
+                .map(|(index, column)| {
+                    pad(
+                        row.get(index).map_or("", |cell| cell.as_str()),
+                        widths[index],
+                        column.right,
+                    )
+                })
+                .collect::<Vec<String>>()
+        };
+
+        match style {
+            Style::Borders => {
+                let rule = format!(
+                    "+{}+",
+                    widths
+                        .iter()
+                        .map(|width| "-".repeat(width + 2))
+                        .collect::<Vec<String>>()
+                        .join("+")
+                );
+                let mut lines = vec![rule.clone()];
+                for (index, row) in rows.iter().enumerate() {
+                    lines.push(format!("| {} |", line(row).join(" | ")));
+                    if index == 0 {
+                        lines.push(rule.clone());
+                    }
+                }
+                lines.push(rule);
+                lines
+            }
+            _ => rows
+                .iter()
+                .map(|row| format!("  {}", line(row).join("  ").trim_end()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut table = Table::new(&["Task", "Time"]).right(1).truncate(0, 8);
+        table.row(vec!["Review".to_string(), "1h 0m 0s".to_string()]);
+        table.row(vec!["Fix the parser".to_string(), "30m".to_string()]);
+
+        assert_eq!(
+            table.render(Style::Aligned),
+            vec![
+                "  Task          Time",
+                "  Review    1h 0m 0s",
+                "  Fix the…       30m",
+            ]
+        );
+        assert_eq!(
+            table.render(Style::Borders),
+            vec![
+                "+----------+----------+",
+                "| Task     |     Time |",
+                "+----------+----------+",
+                "| Review   | 1h 0m 0s |",
+                "| Fix the… |      30m |",
+                "+----------+----------+",
+            ]
+        );
+        assert_eq!(
+            table.render(Style::Plain),
+            vec!["Task\tTime", "Review\t1h 0m 0s", "Fix the parser\t30m"]
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    cli::{self, Args},
+    config::Config,
+    error::{self, ErrorCode},
+    log, Task,
+};
+
+/// The configured tag aliases, read once per run.
+fn aliases() -> &'static BTreeMap<String, String> {
+    static ALIASES: std::sync::OnceLock<BTreeMap<String, String>> = std::sync::OnceLock::new();
+    ALIASES.get_or_init(|| Config::current().tag_aliases)
+}
+
+fn resolve(aliases: &BTreeMap<String, String>, tag: &str) -> String {
+    aliases.get(tag).cloned().unwrap_or_else(|| tag.to_string())
+}
+
+/// The name `tag` is reported under: the tag it is an alias of, or itself.
+pub fn canonical(tag: &str) -> String {
+    resolve(aliases(), tag)
+}
+
+/// Renames the tag `old` to `new` on `tasks`, without giving a task the same
+/// tag twice. Returns how many tasks were changed.
+fn rename<'a>(tasks: impl Iterator<Item = &'a mut Task>, old: &str, new: &str) -> usize {
+    let mut renamed = 0;
+    for task in tasks {
+        if !task.tags.iter().any(|tag| tag == old) {
+            continue;
+        }
+        let mut tags: Vec<String> = vec![];
+        for tag in task.tags.drain(..) {
+            let tag = if tag == old { new.to_string() } else { tag };
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        task.tags = tags;
+        renamed += 1;
+    }
+    renamed
+}
+
+/// `tag rename <old> <new>`: renames a tag on every logged task and on the
+/// tasks of the running session, returning whether the session changed.
+pub fn tag(args: &mut Args, session_tasks: &mut [Task]) -> bool {
+    if args.shift().as_deref() != Some("rename") {
+        error::print(ErrorCode::MissingArgument, "Usage: tag rename <old> <new>");
+        return false;
+    }
+    let (Some(old), Some(new)) = (args.shift(), args.shift()) else {
+        error::print(ErrorCode::MissingArgument, "Usage: tag rename <old> <new>");
+        return false;
+    };
+    let (old, new) = (old.trim_start_matches('#'), new.trim_start_matches('#'));
+
+    let mut entries = log::load();
+    let logged = rename(
+        entries.iter_mut().flat_map(|entry| &mut entry.tasks),
+        old,
+        new,
+    );
+    if logged > 0 {
+        if let Err(e) = log::save(&entries) {
+            error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+            return false;
+        }
+    }
+    let running = rename(session_tasks.iter_mut(), old, new);
+    if logged + running == 0 {
+        println!("No tasks are tagged {}", old);
+    } else {
+        cli::info(&format!(
+            "Renamed tag {} to {} on {} tasks",
+            old,
+            new,
+            logged + running
+        ));
+    }
+    running > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename() {
+        let aliases = BTreeMap::from([("mtg".to_string(), "meeting".to_string())]);
+        assert_eq!(resolve(&aliases, "mtg"), "meeting");
+        assert_eq!(resolve(&aliases, "review"), "review");
+
+        let task = |tags: &[&str]| {
+            let mut task = Task::new("Sync", chrono::Local::now());
+            task.tags = tags.iter().map(|tag| tag.to_string()).collect();
+            task
+        };
+        let mut tasks = [task(&["mtg", "acme"]), task(&["mtg", "meeting"]), task(&[])];
+        assert_eq!(rename(tasks.iter_mut(), "mtg", "meeting"), 2);
+        assert_eq!(tasks[0].tags, vec!["meeting", "acme"]);
+        assert_eq!(tasks[1].tags, vec!["meeting"]);
+    }
+}
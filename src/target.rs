@@ -0,0 +1,163 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    cli::Args,
+    error::{self, ErrorCode},
+    persistent_folder,
+    report::Day,
+    storage,
+};
+
+/// Weekly hour targets per project, set with `goal set`.
+fn targets_file() -> std::path::PathBuf {
+    let mut path = persistent_folder();
+    path.push("targets.json");
+    path
+}
+
+pub fn load() -> BTreeMap<String, f64> {
+    if !targets_file().exists() {
+        return BTreeMap::new();
+    }
+    let data = std::fs::read_to_string(targets_file()).unwrap();
+    serde_json::from_str(&data).unwrap()
+}
+
+fn save(targets: &BTreeMap<String, f64>) {
+    if !persistent_folder().exists() {
+        storage::create_folder(&persistent_folder()).unwrap();
+    }
+    let data = serde_json::to_string(targets).unwrap();
+    storage::write(&targets_file(), data).unwrap();
+}
+
+/// Parses hours per week such as `10h/week`, `10h`, `7.5` or `90m/week`.
+pub fn parse_weekly_hours(text: &str) -> Option<f64> {
+    let text = text.trim().to_lowercase();
+    parse_hours(text.strip_suffix("/week").unwrap_or(&text))
+}
+
+/// Parses a positive number of hours such as `4h`, `7.5` or `90m`.
+pub fn parse_hours(text: &str) -> Option<f64> {
+    let text = text.trim().to_lowercase();
+    let text = text.as_str();
+    let hours = if let Some(minutes) = text.strip_suffix('m') {
+        minutes.parse::<f64>().ok()? / 60.0
+    } else {
+        text.strip_suffix('h').unwrap_or(text).parse::<f64>().ok()?
+    };
+    if hours.is_finite() && hours > 0.0 {
+        Some(hours)
+    } else {
+        None
+    }
+}
+
+/// A bar of `width` characters filled by the share of `target` that is
+/// `done`, capped at full.
+pub fn progress_bar(done: f64, target: f64, width: usize) -> String {
+    let filled = ((done / target).clamp(0.0, 1.0) * width as f64).round() as usize;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(width - filled))
+}
+
+/// Prints the progress of every project with a weekly target over `days`,
+/// except those in `hidden`. The targets are cut to the `available` share of
+/// the working days, so holidays and sick days do not count as missed hours.
+pub fn print_progress(days: &[Day], hidden: &BTreeSet<String>, available: f64) {
+    let mut targets = load();
+    targets.retain(|project, _| !hidden.contains(project));
+    if targets.is_empty() {
+        return;
+    }
+    let totals = crate::report::project_totals(days);
+    let width = targets.keys().map(|project| project.len()).max().unwrap();
+
+    if available < 1.0 {
+        println!(
+            "Weekly targets, cut to {:.0}% for days off:",
+            available * 100.0
+        );
+    } else {
+        println!("Weekly targets:");
+    }
+    for (project, target) in &targets {
+        let target = &(target * available);
+        let done = totals
+            .iter()
+            .find(|(name, _)| name.as_deref() == Some(project.as_str()))
+            .map(|(_, duration)| duration.num_seconds() as f64 / 3600.0)
+            .unwrap_or(0.0);
+        println!(
+            "  {:<width$} {} {:.1}h / {:.1}h ({:.0}%)",
+            project,
+            progress_bar(done, *target, 20),
+            done,
+            target,
+            done / target * 100.0,
+            width = width
+        );
+    }
+}
+
+/// `goal set --project <name> <hours>/week`, `goal clear --project <name>`
+/// and `goal list`.
+pub fn goal(args: &mut Args) {
+    let action = args.shift().unwrap_or_default();
+    let project = args.value("--project");
+    let mut targets = load();
+
+    match (action.as_str(), project) {
+        ("set", Some(project)) => {
+            let text = args.text();
+            let Some(hours) = parse_weekly_hours(&text) else {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("Invalid weekly target: {} (expected e.g. 10h/week)", text),
+                );
+                return;
+            };
+            targets.insert(project.clone(), hours);
+            save(&targets);
+            println!("Weekly target for {} set to {:.1}h", project, hours);
+        }
+        ("clear", Some(project)) => {
+            if targets.remove(&project).is_some() {
+                save(&targets);
+                println!("Weekly target for {} cleared", project);
+            } else {
+                println!("No weekly target for {}", project);
+            }
+        }
+        ("set" | "clear", None) => error::print(
+            ErrorCode::MissingArgument,
+            "Project is required (--project <name>)",
+        ),
+        ("list" | "", _) => {
+            if targets.is_empty() {
+                println!("No weekly targets set");
+            }
+            for (project, hours) in targets {
+                println!("  {} - {:.1}h/week", project, hours);
+            }
+        }
+        _ => error::print(
+            ErrorCode::InvalidArgument,
+            &format!("Unknown goal action: {}", action),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weekly_targets() {
+        assert_eq!(parse_weekly_hours("10h/week"), Some(10.0));
+        assert_eq!(parse_weekly_hours("7.5"), Some(7.5));
+        assert_eq!(parse_weekly_hours("90m/week"), Some(1.5));
+        assert_eq!(parse_weekly_hours("lots"), None);
+        assert_eq!(progress_bar(5.0, 10.0, 10), "[#####-----]");
+        assert_eq!(progress_bar(12.0, 10.0, 4), "[####]");
+    }
+}
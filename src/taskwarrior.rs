@@ -0,0 +1,153 @@
+use std::process::Command;
+
+use crate::{
+    cli,
+    error::{self, ErrorCode},
+    format_duration, log, process, summary, Task, TaskState,
+};
+
+/// A pending task from `task export`.
+#[derive(serde::Deserialize, Clone)]
+pub struct PendingTask {
+    #[serde(default)]
+    pub id: u64,
+    pub uuid: String,
+    pub description: String,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl PendingTask {
+    /// A jobclock task linked to this Taskwarrior task by its UUID.
+    pub fn to_task(&self, now: chrono::DateTime<chrono::Local>) -> Task {
+        let mut task = Task::new(&self.description, now);
+        task.project = self.project.clone();
+        task.tags = self.tags.clone();
+        task.state = TaskState::InProgress;
+        task.taskwarrior_uuid = Some(self.uuid.clone());
+        task
+    }
+}
+
+fn taskwarrior(args: &[&str]) -> Result<String, String> {
+    match process::output(
+        Command::new("task")
+            .args(["rc.confirmation=off", "rc.verbose=nothing"])
+            .args(args),
+    ) {
+        Ok(output) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        }
+        Ok(output) => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        Err(e) => Err(format!("Failed to execute task: {}", e)),
+    }
+}
+
+fn parse_export(json: &str) -> Result<Vec<PendingTask>, String> {
+    let mut tasks: Vec<PendingTask> =
+        serde_json::from_str(json.trim()).map_err(|e| format!("Invalid task export: {}", e))?;
+    tasks.sort_by_key(|task| task.id);
+    Ok(tasks)
+}
+
+/// The pending Taskwarrior tasks, ordered by their id.
+pub fn pending() -> Result<Vec<PendingTask>, String> {
+    parse_export(&taskwarrior(&["status:pending", "export"])?)
+}
+
+/// `tw pull`: lists the pending tasks that can be added with `tw add <id>`.
+pub fn print_pending() {
+    let tasks = match pending() {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+            return;
+        }
+    };
+    if tasks.is_empty() {
+        println!("No pending Taskwarrior tasks");
+    }
+    for task in tasks {
+        println!(
+            "  {}. {}{}",
+            task.id,
+            task.description,
+            task.project
+                .map(|project| format!(" [{}]", project))
+                .unwrap_or_default()
+        );
+    }
+}
+
+/// `tw push`: annotates the Taskwarrior tasks linked to logged tasks with
+/// the time spent on them, once per logged task.
+pub fn push() {
+    let mut entries = log::load();
+    let mut annotated = 0;
+    for entry in &mut entries {
+        let durations = summary::task_durations(entry.start_time, &entry.tasks);
+        for task in &mut entry.tasks {
+            let Some(uuid) = task.taskwarrior_uuid.clone() else {
+                continue;
+            };
+            if task.taskwarrior_annotated {
+                continue;
+            }
+            let duration = durations
+                .iter()
+                .find(|(timed, _)| timed.name == task.name && timed.created_at == task.created_at)
+                .map(|(_, duration)| *duration)
+                .unwrap_or_else(chrono::Duration::zero);
+            let annotation = format!(
+                "jobclock: {} on {}",
+                format_duration(duration),
+                entry.start_time.format("%d-%m-%Y")
+            );
+            if let Err(e) = taskwarrior(&[&uuid, "annotate", &annotation]) {
+                error::print(
+                    ErrorCode::CommandFailed,
+                    &format!("ERROR: Cannot annotate {}: {}", uuid, e),
+                );
+                continue;
+            }
+            task.taskwarrior_annotated = true;
+            annotated += 1;
+        }
+    }
+    if annotated == 0 {
+        println!("No new time to push to Taskwarrior");
+        return;
+    }
+    match log::save(&entries) {
+        Ok(()) => cli::info(&format!(
+            "Annotated {} Taskwarrior tasks with their time",
+            annotated
+        )),
+        Err(e) => error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_export() {
+        let json = r#"[
+            {"id":2,"uuid":"b1","description":"Write docs","status":"pending","entry":"20240313T090000Z"},
+            {"id":1,"uuid":"a1","description":"Fix parser","project":"jobclock","tags":["bug"]}
+        ]"#;
+        let tasks = parse_export(json).unwrap();
+        assert_eq!(tasks[0].uuid, "a1");
+        assert_eq!(tasks[1].project, None);
+
+        let task = tasks[0].to_task(chrono::Local::now());
+        assert_eq!(task.name, "Fix parser");
+        assert_eq!(task.project.as_deref(), Some("jobclock"));
+        assert_eq!(task.tags, vec!["bug"]);
+        assert_eq!(task.taskwarrior_uuid.as_deref(), Some("a1"));
+        assert!(parse_export("not json").is_err());
+    }
+}
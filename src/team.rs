@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    cli::{self, Args},
+    config::Config,
+    error::{self, ErrorCode},
+    http, log,
+    log::LogEntry,
+    queue, report,
+};
+
+/// The team server that `push`, `pull` and `team` talk to.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct TeamServer {
+    pub url: String,
+    /// Environment variable holding the access token, so it is not stored in
+    /// the config file.
+    pub token_env: String,
+}
+
+impl Default for TeamServer {
+    fn default() -> TeamServer {
+        TeamServer {
+            url: String::new(),
+            token_env: "JOBCLOCK_TEAM_TOKEN".to_string(),
+        }
+    }
+}
+
+/// Settings for `server`: the users that may push and their tokens.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Access token per user name.
+    pub users: BTreeMap<String, String>,
+}
+
+/// One member's hours, as listed by the server's `/team` endpoint.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+pub struct MemberHours {
+    pub user: String,
+    pub sessions: usize,
+    pub hours: f64,
+}
+
+/// The URL of the configured team server and the access token for it.
+fn server() -> Result<(String, String), String> {
+    let server = Config::current()
+        .team
+        .filter(|server| !server.url.is_empty())
+        .ok_or("No team server configured, add team.url to the config")?;
+    let token = std::env::var(&server.token_env).map_err(|_| {
+        format!(
+            "No team token in the environment variable {}",
+            server.token_env
+        )
+    })?;
+    Ok((server.url, token))
+}
+
+/// Sends a request to the configured team server, returning the body of a
+/// successful response.
+fn request(method: &str, path: &str, body: Option<&str>) -> Result<String, String> {
+    let (url, token) = server()?;
+    let url = format!("{}{}", url.trim_end_matches('/'), path);
+    let (status, body) = http::request(
+        method,
+        &url,
+        &[format!("Authorization: Bearer {}", token)],
+        body,
+    )?;
+    if !(200..300).contains(&status) {
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|body| body["error"].as_str().map(str::to_string))
+            .unwrap_or(body);
+        return Err(format!("The team server answered {}: {}", status, message));
+    }
+    Ok(body)
+}
+
+/// Uploads the whole log to the team server, replacing the copy stored
+/// there, and returns the number of sessions pushed.
+pub fn push_log() -> Result<usize, String> {
+    let entries = log::load();
+    let body = serde_json::to_string(&entries).unwrap();
+    request("PUT", "/log", Some(&body)).map(|_| entries.len())
+}
+
+/// `push`: uploads the whole log to the team server. When the server cannot
+/// be reached, the push is queued and tried again by a later command.
+pub fn push() {
+    if let Err(e) = server() {
+        error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+        return;
+    }
+    match push_log() {
+        Ok(count) => cli::info(&format!("Pushed {} job sessions to the team server", count)),
+        Err(e) => {
+            queue::add(queue::Action::Push, &e, chrono::Local::now());
+            println!(
+                "WARNING: Cannot push to the team server, it is queued and tried again later: {}",
+                e
+            );
+        }
+    }
+}
+
+/// `pull`: replaces the log with the copy pushed to the team server, for
+/// setting up another machine.
+pub fn pull() {
+    let entries = match request("GET", "/log", None).and_then(|body| {
+        serde_json::from_str::<Vec<LogEntry>>(&body)
+            .map_err(|e| format!("Invalid log from the team server: {}", e))
+    }) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+            return;
+        }
+    };
+    let local = log::load().len();
+    if local > 0
+        && !cli::confirm(&format!(
+            "Replace the {} local job sessions with the {} on the team server?",
+            local,
+            entries.len()
+        ))
+    {
+        return;
+    }
+    match log::save(&entries) {
+        Ok(()) => cli::info(&format!(
+            "Pulled {} job sessions from the team server",
+            entries.len()
+        )),
+        Err(e) => error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e)),
+    }
+}
+
+/// `team [--from YYYY-MM-DD] [--to YYYY-MM-DD]`: lists the hours each member
+/// has pushed to the team server.
+pub fn team(args: &mut Args) {
+    let mut query = vec![];
+    for name in ["--from", "--to"] {
+        match report::parse_date_option(args, name) {
+            Ok(Some(date)) => query.push(format!("{}={}", &name[2..], date.format("%Y-%m-%d"))),
+            Ok(None) => {}
+            Err(()) => return,
+        }
+    }
+    let path = if query.is_empty() {
+        "/team".to_string()
+    } else {
+        format!("/team?{}", query.join("&"))
+    };
+    let members = match request("GET", &path, None).and_then(|body| {
+        serde_json::from_str::<Vec<MemberHours>>(&body)
+            .map_err(|e| format!("Invalid answer from the team server: {}", e))
+    }) {
+        Ok(members) => members,
+        Err(e) => {
+            error::print(ErrorCode::CommandFailed, &format!("ERROR: {}", e));
+            return;
+        }
+    };
+    if members.is_empty() {
+        println!("Nobody has pushed to the team server yet");
+        return;
+    }
+    let width = members
+        .iter()
+        .map(|member| member.user.len())
+        .max()
+        .unwrap();
+    for member in &members {
+        println!(
+            "  {:<width$}  {:>7.2}h  ({} sessions)",
+            member.user,
+            member.hours,
+            member.sessions,
+            width = width
+        );
+    }
+    println!(
+        "Total: {:.2}h",
+        members.iter().map(|member| member.hours).sum::<f64>()
+    );
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    cli::{self, Args},
+    config::Config,
+    daytype::{self, DayType},
+    error::{self, ErrorCode},
+    log, persistent_folder,
+    report::{self, Day},
+    storage, target,
+};
+
+/// A flexitime agreement: the hours expected per working day. Time worked
+/// beyond them is saved in the time bank, and time off is taken from it.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct Timebank {
+    pub hours_per_day: f64,
+    /// `daily`, `weekdays`, `weekends` or a list of days such as `mon,thu`.
+    pub days: String,
+    /// First day counted, `YYYY-MM-DD`, such as the start of the agreement.
+    pub since: Option<String>,
+}
+
+impl Default for Timebank {
+    fn default() -> Timebank {
+        Timebank {
+            hours_per_day: 7.5,
+            days: "weekdays".to_string(),
+            since: None,
+        }
+    }
+}
+
+/// Time off taken from the time bank with `timebank take`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+pub struct Withdrawal {
+    pub date: chrono::NaiveDate,
+    pub hours: f64,
+    pub note: String,
+}
+
+fn withdrawals_file() -> std::path::PathBuf {
+    let mut path = persistent_folder();
+    path.push("timebank.json");
+    path
+}
+
+pub fn load() -> Vec<Withdrawal> {
+    if !withdrawals_file().exists() {
+        return vec![];
+    }
+    let data = std::fs::read_to_string(withdrawals_file()).unwrap();
+    serde_json::from_str(&data).unwrap()
+}
+
+fn save(withdrawals: &[Withdrawal]) {
+    if !persistent_folder().exists() {
+        storage::create_folder(&persistent_folder()).unwrap();
+    }
+    let data = serde_json::to_string(withdrawals).unwrap();
+    storage::write(&withdrawals_file(), data).unwrap();
+}
+
+/// The balance in hours at the end of `until`. A day with logged time adds
+/// the hours worked beyond the hours expected, or takes the hours short of
+/// them, so a short day is already taken from the balance. No hours are
+/// expected on days marked as holidays or sick days in `day_types`. Time off
+/// taken on a day without logged time is taken from it as well. Other days
+/// without logged time, such as holidays, are not counted.
+pub fn balance(
+    timebank: &Timebank,
+    days: &[Day],
+    withdrawals: &[Withdrawal],
+    day_types: &BTreeMap<chrono::NaiveDate, DayType>,
+    until: chrono::NaiveDate,
+) -> f64 {
+    let since = timebank.since.as_deref().and_then(report::parse_date);
+    let counted =
+        |date: chrono::NaiveDate| since.is_none_or(|since| date >= since) && date <= until;
+
+    let mut balance = 0.0;
+    for day in days.iter().filter(|day| counted(day.date)) {
+        balance += day.worked().num_seconds() as f64 / 3600.0;
+        if daytype::is_working_day(day_types, day.date, &timebank.days) {
+            balance -= timebank.hours_per_day;
+        }
+    }
+    for withdrawal in withdrawals {
+        if counted(withdrawal.date) && !days.iter().any(|day| day.date == withdrawal.date) {
+            balance -= withdrawal.hours;
+        }
+    }
+    balance
+}
+
+/// Hours such as `+5.5h` or `-2.0h`.
+pub fn format_hours(hours: f64) -> String {
+    format!("{:+.1}h", hours)
+}
+
+/// `timebank`: shows the balance. `timebank take <hours> [YYYY-MM-DD]
+/// [note]` records time off taken from it, and `timebank list` lists the
+/// time off taken.
+pub fn timebank(args: &mut Args) {
+    let Some(timebank) = Config::current().timebank else {
+        error::print(
+            ErrorCode::CommandFailed,
+            "No time bank configured, add timebank.hours_per_day to the config",
+        );
+        return;
+    };
+    let mut withdrawals = load();
+    match args.shift().as_deref() {
+        Some("take") => {
+            let hours = args.shift().unwrap_or_default();
+            let Some(hours) = target::parse_hours(&hours) else {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    "Usage: timebank take <hours> [YYYY-MM-DD] [note]",
+                );
+                return;
+            };
+            let rest = args.text();
+            let (date, note) = match rest.split_once(' ').unwrap_or((&rest, "")) {
+                (date, note) if report::parse_date(date).is_some() => {
+                    (report::parse_date(date).unwrap(), note.to_string())
+                }
+                _ => (chrono::Local::now().date_naive(), rest.clone()),
+            };
+            withdrawals.push(Withdrawal { date, hours, note });
+            withdrawals.sort_by_key(|withdrawal| withdrawal.date);
+            save(&withdrawals);
+            cli::info(&format!(
+                "Took {:.1}h from the time bank on {}",
+                hours,
+                date.format("%d-%m-%Y")
+            ));
+        }
+        Some("list") => {
+            if withdrawals.is_empty() {
+                println!("No time off taken from the time bank");
+            }
+            for withdrawal in &withdrawals {
+                println!(
+                    "  {} - {:.1}h{}",
+                    withdrawal.date.format("%a %d-%m-%Y"),
+                    withdrawal.hours,
+                    if withdrawal.note.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" - {}", withdrawal.note)
+                    }
+                );
+            }
+        }
+        Some(_) => error::print(
+            ErrorCode::InvalidArgument,
+            "Usage: timebank [take <hours> [YYYY-MM-DD] [note]] [list]",
+        ),
+        None => {
+            let today = chrono::Local::now().date_naive();
+            let days = report::days(&log::load());
+            let day_types = daytype::load();
+            println!(
+                "Time bank: {}",
+                format_hours(balance(&timebank, &days, &withdrawals, &day_types, today))
+            );
+            let planned = withdrawals
+                .iter()
+                .filter(|withdrawal| withdrawal.date > today)
+                .map(|withdrawal| withdrawal.hours)
+                .sum::<f64>();
+            if planned > 0.0 {
+                println!("Time off planned: {:.1}h", planned);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::LogEntry;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_balance() {
+        let at = |day, hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 6, day, hour, 0, 0)
+                .unwrap()
+        };
+        let entry = |day, from, to| LogEntry {
+            start_time: at(day, from),
+            end_time: at(day, to),
+            ..Default::default()
+        };
+        // Mon 9h, Tue 8h, Wed 7h and 2h on Saturday.
+        let days = report::days(&[
+            entry(3, 8, 17),
+            entry(4, 8, 16),
+            entry(5, 8, 15),
+            entry(8, 10, 12),
+        ]);
+        let timebank = Timebank::default();
+        let date = |day| chrono::NaiveDate::from_ymd_opt(2024, 6, day).unwrap();
+        let types = BTreeMap::new();
+        assert_eq!(
+            balance(&timebank, &days, &[], &types, date(8)),
+            1.5 + 0.5 - 0.5 + 2.0
+        );
+        // Going home sick after 7h on Wednesday does not take from it.
+        let sick = BTreeMap::from([(date(5), DayType::Sick)]);
+        assert_eq!(
+            balance(&timebank, &days, &[], &sick, date(8)),
+            1.5 + 0.5 + 7.0 + 2.0
+        );
+
+        let withdrawals = [Withdrawal {
+            date: date(7),
+            hours: 4.0,
+            note: "short Friday".to_string(),
+        }];
+        assert_eq!(
+            balance(&timebank, &days, &withdrawals, &types, date(8)),
+            3.5 - 4.0
+        );
+        assert_eq!(
+            balance(&timebank, &days, &withdrawals, &types, date(4)),
+            2.0
+        );
+        // Time off on a day with logged time is already counted by the log.
+        let withdrawals = [Withdrawal {
+            date: date(5),
+            ..withdrawals[0].clone()
+        }];
+        assert_eq!(
+            balance(&timebank, &days, &withdrawals, &types, date(8)),
+            3.5
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    error::{self, ErrorCode},
+    log,
+    log::LogEntry,
+    report,
+};
+
+/// Minutes per character in the timeline.
+const SLOT_MINUTES: i64 = 15;
+const SLOTS_PER_HOUR: usize = 4;
+
+/// Renders an hour-by-hour timeline of the sessions in `entries` that started
+/// on `date`. Each session gets a row where `█` marks time worked and `|` a
+/// task, followed by rows marking pauses between sessions and overlapping
+/// sessions. A session with id 0 is shown as the running session.
+pub fn render(date: chrono::NaiveDate, entries: &[LogEntry]) -> String {
+    let mut sessions = entries
+        .iter()
+        .filter(|entry| entry.start_time.date_naive() == date)
+        .collect::<Vec<&LogEntry>>();
+    sessions.sort_by_key(|entry| entry.start_time);
+
+    let mut lines = vec![format!("Timeline for {}", date.format("%a %d-%m-%Y"))];
+    if sessions.is_empty() {
+        lines.push("No job sessions on this day".to_string());
+        return lines.join("\n");
+    }
+
+    let local = |time: chrono::NaiveDateTime| time.and_local_timezone(chrono::Local).earliest();
+    let midnight = local(date.and_hms_opt(0, 0, 0).unwrap()).unwrap();
+    let minutes =
+        |time: chrono::DateTime<chrono::Local>| (time - midnight).num_minutes().clamp(0, 24 * 60);
+
+    let first_hour = sessions
+        .iter()
+        .map(|entry| minutes(entry.start_time) / 60)
+        .min()
+        .unwrap();
+    let last_hour = sessions
+        .iter()
+        .map(|entry| (minutes(entry.end_time) + 59) / 60)
+        .max()
+        .unwrap()
+        .max(first_hour + 1);
+    let slots = (last_hour - first_hour) as usize * SLOTS_PER_HOUR;
+    let slot_start = |slot: usize| first_hour * 60 + slot as i64 * SLOT_MINUTES;
+    let covers = |entry: &LogEntry, slot: usize| {
+        minutes(entry.start_time) < slot_start(slot) + SLOT_MINUTES
+            && minutes(entry.end_time) > slot_start(slot)
+    };
+
+    let mut axis = " ".repeat(10);
+    for hour in first_hour..last_hour {
+        axis.push_str(&format!("{:<4}", format!("{:02}", hour)));
+    }
+    lines.push(axis.trim_end().to_string());
+
+    for entry in &sessions {
+        let mut row = (0..slots)
+            .map(|slot| if covers(entry, slot) { '█' } else { ' ' })
+            .collect::<Vec<char>>();
+        for task in &entry.tasks {
+            if task.created_at.date_naive() == date {
+                let slot = ((minutes(task.created_at) - first_hour * 60) / SLOT_MINUTES) as usize;
+                if slot < slots {
+                    row[slot] = '|';
+                }
+            }
+        }
+        let label = if entry.id == 0 {
+            "now".to_string()
+        } else {
+            format!("#{}", entry.id)
+        };
+        lines.push(
+            format!("  {:<8}{}", label, row.iter().collect::<String>())
+                .trim_end()
+                .to_string(),
+        );
+    }
+
+    let first_start = minutes(sessions[0].start_time);
+    let last_end = sessions
+        .iter()
+        .map(|entry| minutes(entry.end_time))
+        .max()
+        .unwrap();
+    // Time covered by more than one session, as minute ranges.
+    let mut overlapping = vec![];
+    for (index, a) in sessions.iter().enumerate() {
+        for b in &sessions[index + 1..] {
+            let start = minutes(a.start_time).max(minutes(b.start_time));
+            let end = minutes(a.end_time).min(minutes(b.end_time));
+            if start < end {
+                overlapping.push((start, end));
+            }
+        }
+    }
+    let mut pauses = String::new();
+    let mut overlaps = String::new();
+    for slot in 0..slots {
+        let (start, end) = (slot_start(slot), slot_start(slot) + SLOT_MINUTES);
+        let covered = sessions.iter().any(|entry| covers(entry, slot));
+        let inside = start >= first_start && end <= last_end;
+        pauses.push(if !covered && inside { '·' } else { ' ' });
+        let overlap = overlapping
+            .iter()
+            .any(|range| range.0 < end && range.1 > start);
+        overlaps.push(if overlap { '!' } else { ' ' });
+    }
+    if pauses.contains('·') {
+        lines.push(format!("  {:<8}{}", "pause", pauses).trim_end().to_string());
+    }
+    if overlaps.contains('!') {
+        lines.push(
+            format!("  {:<8}{}", "overlap", overlaps)
+                .trim_end()
+                .to_string(),
+        );
+    }
+
+    lines.push(String::new());
+    for entry in &sessions {
+        for task in &entry.tasks {
+            lines.push(format!(
+                "  {} {}{}",
+                task.created_at.format("%H:%M"),
+                task.name,
+                task.labels()
+            ));
+        }
+    }
+    lines.join("\n").trim_end().to_string()
+}
+
+/// Prints the timeline of `date` (`YYYY-MM-DD`, today by default), including
+/// the running session.
+pub fn timeline(date: &str, running: Option<LogEntry>) {
+    let date = if date.is_empty() {
+        chrono::Local::now().date_naive()
+    } else {
+        match report::parse_date(date) {
+            Some(date) => date,
+            None => {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("Invalid date: {} (expected YYYY-MM-DD)", date),
+                );
+                return;
+            }
+        }
+    };
+
+    let mut entries = log::load_range(Some(date), Some(date));
+    entries.extend(running);
+    println!("{}", render(date, &entries));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Task;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_render() {
+        let at = |hour, minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 12, hour, minute, 0)
+                .unwrap()
+        };
+        let entries = vec![
+            LogEntry {
+                id: 1,
+                start_time: at(8, 0),
+                end_time: at(9, 0),
+                tasks: vec![Task::new("Fix parser", at(8, 30))],
+                ..Default::default()
+            },
+            LogEntry {
+                id: 2,
+                start_time: at(9, 30),
+                end_time: at(10, 30),
+                ..Default::default()
+            },
+            LogEntry {
+                id: 3,
+                start_time: at(10, 0),
+                end_time: at(10, 15),
+                ..Default::default()
+            },
+        ];
+        let timeline = render(at(0, 0).date_naive(), &entries);
+        assert_eq!(
+            timeline.lines().collect::<Vec<&str>>(),
+            vec![
+                "Timeline for Tue 12-03-2024",
+                "          08  09  10",
+                "  #1      ██|█",
+                "  #2            ████",
+                "  #3              █",
+                "  pause       ··",
+                "  overlap         !",
+                "",
+                "  08:30 Fix parser",
+            ]
+        );
+    }
+}
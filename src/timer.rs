@@ -0,0 +1,120 @@
+use crate::{
+    cli,
+    error::{self, ErrorCode},
+    format_duration,
+};
+
+/// A named stopwatch within a session. Time accumulates over every
+/// start/stop pair.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Timer {
+    pub name: String,
+    /// Seconds accumulated by earlier start/stop pairs.
+    pub seconds: i64,
+    /// When the timer was started, if it is running.
+    pub started_at: Option<chrono::DateTime<chrono::Local>>,
+}
+
+impl Timer {
+    pub fn elapsed(&self, now: chrono::DateTime<chrono::Local>) -> chrono::Duration {
+        let running = match self.started_at {
+            Some(started_at) => (now - started_at).max(chrono::Duration::zero()),
+            None => chrono::Duration::zero(),
+        };
+        chrono::Duration::try_seconds(self.seconds).unwrap() + running
+    }
+
+    pub fn stop(&mut self, now: chrono::DateTime<chrono::Local>) {
+        self.seconds = self.elapsed(now).num_seconds();
+        self.started_at = None;
+    }
+}
+
+/// Starts the timer called `name`, creating it if needed.
+pub fn start(timers: &mut Vec<Timer>, name: &str, now: chrono::DateTime<chrono::Local>) {
+    match timers.iter_mut().find(|timer| timer.name == name) {
+        Some(timer) if timer.started_at.is_some() => {
+            error::print(
+                ErrorCode::CommandFailed,
+                &format!("Timer '{}' is already running", name),
+            );
+            return;
+        }
+        Some(timer) => timer.started_at = Some(now),
+        None => timers.push(Timer {
+            name: name.to_string(),
+            seconds: 0,
+            started_at: Some(now),
+        }),
+    }
+    cli::info(&format!("Timer '{}' started", name));
+}
+
+pub fn stop(timers: &mut [Timer], name: &str, now: chrono::DateTime<chrono::Local>) {
+    match timers
+        .iter_mut()
+        .find(|timer| timer.name == name && timer.started_at.is_some())
+    {
+        Some(timer) => {
+            timer.stop(now);
+            cli::info(&format!(
+                "Timer '{}' stopped at {}",
+                name,
+                format_duration(timer.elapsed(now))
+            ));
+        }
+        None => error::print(
+            ErrorCode::CommandFailed,
+            &format!("Timer '{}' is not running", name),
+        ),
+    }
+}
+
+/// The timers of a session with their time at `now`, if it has any.
+pub fn render_timers(timers: &[Timer], now: chrono::DateTime<chrono::Local>) -> String {
+    let mut text = String::new();
+    if timers.is_empty() {
+        return text;
+    }
+    text.push_str("Timers:\n");
+    for timer in timers {
+        text.push_str(&format!(
+            "  {} - {}{}\n",
+            timer.name,
+            format_duration(timer.elapsed(now)),
+            if timer.started_at.is_some() {
+                " (running)"
+            } else {
+                ""
+            }
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_timer_accumulates() {
+        let at = |minute| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 12, 9, minute, 0)
+                .unwrap()
+        };
+        let mut timers = vec![];
+        start(&mut timers, "build", at(0));
+        stop(&mut timers, "build", at(5));
+        start(&mut timers, "build", at(20));
+        assert_eq!(
+            timers[0].elapsed(at(30)),
+            chrono::Duration::try_minutes(15).unwrap()
+        );
+        timers[0].stop(at(22));
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].seconds, 7 * 60);
+        assert!(timers[0].started_at.is_none());
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{
+    cli::{self, Args},
+    error::{self, ErrorCode},
+    format_duration, log,
+    log::{month_of, LogEntry},
+    persistent_folder, sha256, storage,
+};
+
+/// A month whose log entries have been signed off. Its checksum is taken
+/// over the entries at the time of closing, so any later change is detected.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct ClosedMonth {
+    pub month: String,
+    pub closed_at: chrono::DateTime<chrono::Local>,
+    pub checksum: String,
+}
+
+fn closed_months_file() -> std::path::PathBuf {
+    let mut path = persistent_folder();
+    path.push("closed_months.json");
+    path
+}
+
+pub fn load() -> Vec<ClosedMonth> {
+    if !closed_months_file().exists() {
+        return vec![];
+    }
+    let data = std::fs::read_to_string(closed_months_file()).unwrap();
+    serde_json::from_str(&data).unwrap()
+}
+
+fn save(months: &[ClosedMonth]) {
+    if !persistent_folder().exists() {
+        storage::create_folder(&persistent_folder()).unwrap();
+    }
+    let data = serde_json::to_string(months).unwrap();
+    storage::write(&closed_months_file(), data).unwrap();
+}
+
+/// Checksum over the entries that started in `month`.
+pub fn checksum(entries: &[LogEntry], month: &str) -> String {
+    digest(
+        entries
+            .iter()
+            .filter(|entry| month_of(&entry.start_time) == month)
+            .collect(),
+    )
+}
+
+/// Checksum over `entries`. Times are taken in UTC and only the timesheet
+/// contents are included, so the checksum survives time zone changes and
+/// new fields in the log format.
+pub fn digest(mut entries: Vec<&LogEntry>) -> String {
+    entries.sort_by_key(|entry| entry.id);
+
+    let mut data = String::new();
+    for entry in entries {
+        data.push_str(&format!(
+            "{}|{}|{}\n",
+            entry.id,
+            entry.start_time.naive_utc(),
+            entry.end_time.naive_utc()
+        ));
+        for task in &entry.tasks {
+            data.push_str(&format!(
+                "  {}|{}|{}\n",
+                task.created_at.naive_utc(),
+                task.project.as_deref().unwrap_or(""),
+                task.name
+            ));
+        }
+    }
+    sha256::hex_digest(data.as_bytes())
+}
+
+/// Fails if the entries of the closed month in `entries` differ from the
+/// ones it was closed with.
+pub fn check_unchanged(closed: &ClosedMonth, entries: &[LogEntry]) -> Result<(), String> {
+    if checksum(entries, &closed.month) == closed.checksum {
+        Ok(())
+    } else {
+        Err(format!(
+            "Month {} is closed, reopen it with 'close-month {} --reopen' to change it",
+            closed.month, closed.month
+        ))
+    }
+}
+
+/// Returns the closed months whose entries in the log no longer match the
+/// checksum recorded when they were closed.
+pub fn changed_months() -> Vec<String> {
+    load()
+        .into_iter()
+        .filter(|closed| {
+            checksum(&log::load_month(&closed.month), &closed.month) != closed.checksum
+        })
+        .map(|closed| closed.month)
+        .collect()
+}
+
+pub fn parse_month(month: &str) -> Option<String> {
+    chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .ok()
+        .map(|date| date.format("%Y-%m").to_string())
+}
+
+pub fn close_month(args: &mut Args) {
+    let reopen = args.flag("--reopen");
+    let month = args.text();
+    let mut closed_months = load();
+
+    if month.is_empty() {
+        if closed_months.is_empty() {
+            println!("No closed months");
+            return;
+        }
+        let changed = changed_months();
+        println!("Closed months:");
+        for closed in closed_months {
+            println!(
+                "  {} - closed {}{}",
+                closed.month,
+                closed.closed_at.format("%d-%m-%Y %H:%M:%S"),
+                if changed.contains(&closed.month) {
+                    " (CHANGED since closing)"
+                } else {
+                    ""
+                }
+            );
+        }
+        return;
+    }
+
+    let Some(month) = parse_month(&month) else {
+        error::print(
+            ErrorCode::InvalidArgument,
+            &format!("Invalid month: {} (expected YYYY-MM)", month),
+        );
+        return;
+    };
+    let index = closed_months
+        .iter()
+        .position(|closed| closed.month == month);
+
+    if reopen {
+        match index {
+            Some(_) if !cli::confirm(&format!("Reopen {} for changes?", month)) => {
+                println!("Month {} is still closed", month);
+            }
+            Some(index) => {
+                closed_months.remove(index);
+                save(&closed_months);
+                cli::info(&format!("Month {} reopened", month));
+            }
+            None => error::print(
+                ErrorCode::CommandFailed,
+                &format!("Month {} is not closed", month),
+            ),
+        }
+        return;
+    }
+
+    if index.is_some() {
+        error::print(
+            ErrorCode::CommandFailed,
+            &format!("Month {} is already closed", month),
+        );
+        return;
+    }
+
+    let entries = log::load_month(&month);
+    let sessions = entries
+        .iter()
+        .filter(|entry| month_of(&entry.start_time) == month)
+        .collect::<Vec<&LogEntry>>();
+    let total = sessions
+        .iter()
+        .fold(chrono::Duration::zero(), |total, entry| {
+            total + entry.duration()
+        });
+    let checksum = checksum(&entries, &month);
+
+    closed_months.push(ClosedMonth {
+        month: month.clone(),
+        closed_at: chrono::Local::now(),
+        checksum: checksum.clone(),
+    });
+    save(&closed_months);
+
+    println!(
+        "Month {} closed with {} sessions, {}",
+        month,
+        sessions.len(),
+        format_duration(total)
+    );
+    println!("Checksum: {}", checksum);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_checksum() {
+        let at = |day, hour| {
+            chrono::Local
+                .with_ymd_and_hms(2024, 4, day, hour, 0, 0)
+                .unwrap()
+        };
+        let mut entries = vec![LogEntry {
+            id: 1,
+            start_time: at(2, 8),
+            end_time: at(2, 16),
+            tasks: vec![crate::Task::new("Fix parser", at(2, 12))],
+            ..Default::default()
+        }];
+        let april = checksum(&entries, "2024-04");
+        assert_eq!(april, checksum(&entries, "2024-04"));
+        assert_ne!(april, checksum(&entries, "2024-05"));
+
+        entries[0].tasks[0].name = "Fix lexer".to_string();
+        assert_ne!(april, checksum(&entries, "2024-04"));
+    }
+}
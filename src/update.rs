@@ -0,0 +1,241 @@
+use std::process::Command;
+
+use crate::{
+    cli::{self, Args},
+    error::{self, ErrorCode},
+    http, process, sha256,
+};
+
+const RELEASES: &str = "https://api.github.com/repos/johansolbakken/jobclock/releases/latest";
+
+/// Checksums of the release assets, one `<sha256>  <name>` per line.
+const CHECKSUMS: &str = "SHA256SUMS";
+
+/// GPG signature of the checksums, checked when the release has one.
+const SIGNATURE: &str = "SHA256SUMS.asc";
+
+#[derive(serde::Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+impl Release {
+    fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    fn asset(&self, name: &str) -> Option<&Asset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+fn download(url: &str) -> Result<Vec<u8>, String> {
+    match process::output(
+        Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--location"])
+            .args(["--header", "User-Agent: jobclock", url]),
+    ) {
+        Ok(output) if output.status.success() => Ok(output.stdout),
+        Ok(output) => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+        Err(e) => Err(format!("Failed to execute curl: {}", e)),
+    }
+}
+
+/// Whether `version` is newer than `current`, comparing dot-separated
+/// numbers such as `1.10.0` and `1.9.2`.
+fn newer(version: &str, current: &str) -> bool {
+    let numbers = |version: &str| -> Vec<u64> {
+        version
+            .split(['.', '-'])
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    numbers(version) > numbers(current)
+}
+
+/// The name of the release binary for `os` and `arch`, such as
+/// `jobclock-linux-x86_64` or `jobclock-windows-x86_64.exe`.
+fn asset_name(os: &str, arch: &str) -> String {
+    let extension = if os == "windows" { ".exe" } else { "" };
+    format!("jobclock-{}-{}{}", os, arch, extension)
+}
+
+/// The checksum listed for `name` in a `SHA256SUMS` file.
+fn checksum<'a>(sums: &'a str, name: &str) -> Option<&'a str> {
+    sums.lines().find_map(|line| {
+        let (sum, file) = line.split_once(char::is_whitespace)?;
+        (file.trim().trim_start_matches('*') == name).then_some(sum)
+    })
+}
+
+fn parse_release(json: &str) -> Result<Release, String> {
+    serde_json::from_str(json).map_err(|e| format!("Invalid release: {}", e))
+}
+
+/// Verifies the signature of `sums` with GPG, which must already trust the
+/// key it was made with.
+fn verify_signature(sums: &[u8], signature: &[u8]) -> Result<(), String> {
+    let folder = std::env::temp_dir().join(format!("jobclock-update-{}", std::process::id()));
+    std::fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+    let (sums_path, signature_path) = (folder.join(CHECKSUMS), folder.join(SIGNATURE));
+    let result = std::fs::write(&sums_path, sums)
+        .and_then(|_| std::fs::write(&signature_path, signature))
+        .map_err(|e| e.to_string())
+        .and_then(|_| {
+            match process::output(
+                Command::new("gpg")
+                    .arg("--verify")
+                    .args([&signature_path, &sums_path]),
+            ) {
+                Ok(output) if output.status.success() => Ok(()),
+                Ok(output) => Err(format!(
+                    "Invalid signature: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )),
+                Err(e) => Err(format!("Failed to execute gpg: {}", e)),
+            }
+        });
+    std::fs::remove_dir_all(&folder).ok();
+    result
+}
+
+/// The verified binary of `release` for this platform.
+fn fetch_binary(release: &Release) -> Result<Vec<u8>, String> {
+    let name = asset_name(std::env::consts::OS, std::env::consts::ARCH);
+    let binary = release
+        .asset(&name)
+        .ok_or_else(|| format!("Release {} has no {}", release.tag_name, name))?;
+    let sums = release
+        .asset(CHECKSUMS)
+        .ok_or_else(|| format!("Release {} has no {}", release.tag_name, CHECKSUMS))?;
+
+    let sums = download(&sums.browser_download_url)?;
+    if let Some(signature) = release.asset(SIGNATURE) {
+        verify_signature(&sums, &download(&signature.browser_download_url)?)?;
+    }
+    let sums = String::from_utf8_lossy(&sums);
+    let expected = checksum(&sums, &name)
+        .ok_or_else(|| format!("{} has no checksum for {}", CHECKSUMS, name))?;
+
+    let data = download(&binary.browser_download_url)?;
+    if sha256::hex_digest(&data) != expected.to_lowercase() {
+        return Err(format!("The checksum of {} does not match", name));
+    }
+    Ok(data)
+}
+
+/// Puts `data` in place of the executable at `exe`. The new binary is
+/// written next to it first, so a failed write leaves the old one working.
+fn replace(exe: &std::path::Path, data: &[u8]) -> Result<(), String> {
+    let new = exe.with_extension("new");
+    std::fs::write(&new, data).map_err(|e| format!("Cannot write {}: {}", new.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| e.to_string())?;
+    }
+    if cfg!(windows) {
+        // A running executable cannot be overwritten on Windows, but it can be renamed.
+        let old = exe.with_extension("old");
+        std::fs::remove_file(&old).ok();
+        std::fs::rename(exe, &old).map_err(|e| e.to_string())?;
+    }
+    std::fs::rename(&new, exe).map_err(|e| format!("Cannot replace {}: {}", exe.display(), e))
+}
+
+/// `self-update [--check]`: replaces the running binary with the latest
+/// GitHub release, after checking it against the release's checksums and,
+/// when the release is signed, their signature.
+pub fn self_update(args: &mut Args) {
+    let check = args.flag("--check");
+    // Cached, as GitHub allows few requests without a token and does not
+    // count the ones answered with "not modified".
+    let headers = [
+        "User-Agent: jobclock".to_string(),
+        "Accept: application/vnd.github+json".to_string(),
+    ];
+    let release = match http::get_cached(RELEASES, &headers).and_then(|json| parse_release(&json)) {
+        Ok(release) => release,
+        Err(e) => {
+            error::print(
+                ErrorCode::CommandFailed,
+                &format!("ERROR: Cannot check for updates: {}", e),
+            );
+            return;
+        }
+    };
+    let current = env!("CARGO_PKG_VERSION");
+    if !newer(release.version(), current) {
+        println!("Jobclock v{} is the latest version", current);
+        return;
+    }
+    if check {
+        println!(
+            "Jobclock v{} is available (installed: v{})",
+            release.version(),
+            current
+        );
+        return;
+    }
+    if !cli::confirm(&format!(
+        "Update jobclock from v{} to v{}?",
+        current,
+        release.version()
+    )) {
+        return;
+    }
+
+    let result = fetch_binary(&release).and_then(|data| {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        replace(&exe, &data)
+    });
+    match result {
+        Ok(()) => cli::info(&format!("Updated jobclock to v{}", release.version())),
+        Err(e) => error::print(
+            ErrorCode::CommandFailed,
+            &format!("ERROR: Cannot update: {}", e),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release() {
+        let release = parse_release(
+            r#"{"tag_name":"v1.10.0","assets":[
+                {"name":"jobclock-linux-x86_64","browser_download_url":"https://example.com/a"},
+                {"name":"SHA256SUMS","browser_download_url":"https://example.com/s"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(release.version(), "1.10.0");
+        assert!(newer(release.version(), "1.9.2"));
+        assert!(!newer(release.version(), "1.10.0"));
+        assert!(!newer("1.2.0", "1.10.0"));
+
+        let name = asset_name("linux", "x86_64");
+        assert_eq!(
+            release.asset(&name).unwrap().browser_download_url,
+            "https://example.com/a"
+        );
+        assert_eq!(
+            asset_name("windows", "x86_64"),
+            "jobclock-windows-x86_64.exe"
+        );
+        let sums = "0a1b  jobclock-macos-aarch64\n2c3d *jobclock-linux-x86_64\n";
+        assert_eq!(checksum(sums, &name), Some("2c3d"));
+        assert_eq!(checksum(sums, "jobclock-linux-arm"), None);
+    }
+}
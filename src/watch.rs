@@ -0,0 +1,177 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use chrono::TimeZone;
+
+use crate::{
+    cli::{self, Args},
+    config::Config,
+    error::{self, ErrorCode},
+    git, rules, Session, Task,
+};
+
+/// A repository whose reflog is watched, and how much of it has been read.
+struct Watched {
+    repository: std::path::PathBuf,
+    reflog: std::path::PathBuf,
+    read: u64,
+}
+
+impl Watched {
+    /// The complete lines appended to the reflog since it was last read. A
+    /// reflog that shrank was rewritten, for example by `git reflog expire`,
+    /// and is read again from its new end.
+    fn new_lines(&mut self) -> String {
+        let Ok(mut file) = std::fs::File::open(&self.reflog) else {
+            return String::new();
+        };
+        let length = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        if length < self.read {
+            self.read = length;
+        }
+        if length == self.read || file.seek(SeekFrom::Start(self.read)).is_err() {
+            return String::new();
+        }
+        let mut text = String::new();
+        if file.read_to_string(&mut text).is_err() {
+            return String::new();
+        }
+        let Some(end) = text.rfind('\n') else {
+            return String::new();
+        };
+        text.truncate(end + 1);
+        self.read += text.len() as u64;
+        text
+    }
+}
+
+/// The commits in reflog lines, as their time and title. Other reflog
+/// entries, such as checkouts, resets and rebases, are skipped.
+fn reflog_commits(text: &str) -> Vec<(chrono::DateTime<chrono::Local>, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let (identity, message) = line.split_once('\t')?;
+            let (action, title) = message.split_once(": ")?;
+            if action != "commit" && !action.starts_with("commit (") {
+                return None;
+            }
+            // `<old> <new> <name> <email> <seconds> <timezone>`
+            let seconds = identity.rsplit(' ').nth(1)?.parse().ok()?;
+            let date = chrono::Local.timestamp_opt(seconds, 0).single()?;
+            Some((date, title.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Adds `commits` made in `repository` to the running session as tasks,
+/// like `git` does, skipping titles it already has. Returns how many were
+/// added.
+fn add_commits(
+    session: &mut Session,
+    config: &Config,
+    repository: &std::path::Path,
+    commits: Vec<(chrono::DateTime<chrono::Local>, String)>,
+) -> usize {
+    let mut count = 0;
+    for (date, title) in commits {
+        if date < session.start_time || session.tasks.iter().any(|task| task.name == title) {
+            continue;
+        }
+        let mut task = Task::new(&title, date);
+        rules::categorize(&config.rules, &mut task);
+        if task.project.is_none() {
+            task.project = rules::detect_project(&config.paths, repository);
+        }
+        cli::info(&format!("Task '{}' added to job session", title));
+        session.add_task(task);
+        count += 1;
+    }
+    count
+}
+
+/// `watch [--interval <seconds>]`: watches the reflog of the configured
+/// repositories, or of the one in the current directory, and adds every
+/// commit made while a job session is running as a task right away. Runs
+/// until interrupted.
+pub fn watch(args: &mut Args) {
+    let interval = match args.value("--interval") {
+        Some(interval) => match interval.parse::<u64>() {
+            Ok(interval) if interval > 0 => interval,
+            _ => {
+                error::print(
+                    ErrorCode::InvalidArgument,
+                    &format!("Invalid interval: {} (expected seconds)", interval),
+                );
+                return;
+            }
+        },
+        None => 2,
+    };
+
+    let config = Config::current();
+    let mut watched: Vec<Watched> = git::repositories(&config)
+        .into_iter()
+        .filter_map(|repository| {
+            let Some(reflog) = git::reflog_file(&repository) else {
+                println!("Not a git repository: {}", repository.display());
+                return None;
+            };
+            let read = std::fs::metadata(&reflog)
+                .map(|metadata| metadata.len())
+                .unwrap_or(0);
+            Some(Watched {
+                repository,
+                reflog,
+                read,
+            })
+        })
+        .collect();
+    if watched.is_empty() {
+        error::print(ErrorCode::CommandFailed, "ERROR: No repository to watch");
+        return;
+    }
+    cli::info(&format!(
+        "Watching {} repositories for commits, press Ctrl+C to stop",
+        watched.len()
+    ));
+
+    loop {
+        std::thread::sleep(std::time::Duration::from_secs(interval));
+        for watched in &mut watched {
+            let commits = reflog_commits(&watched.new_lines());
+            if commits.is_empty() || !Session::exists() {
+                continue;
+            }
+            // Reloaded every time, since other commands change the session
+            // while this one is waiting.
+            let mut session = Session::load();
+            if !session.working {
+                cli::info("Commit not added as a task, no job session started");
+                continue;
+            }
+            if add_commits(&mut session, &config, &watched.repository, commits) > 0 {
+                session.save();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reflog_commits() {
+        let old = "0".repeat(40);
+        let new = "a".repeat(40);
+        let text = format!(
+            "{old} {new} Ada Lovelace <ada@example.com> 1717405200 +0200\tcommit (initial): Start the parser\n\
+             {new} {new} Ada Lovelace <ada@example.com> 1717405800 +0200\tcheckout: moving from main to fix\n\
+             {new} {new} Ada Lovelace <ada@example.com> 1717406400 +0200\tcommit: Fix: handle empty input\n"
+        );
+        let commits = reflog_commits(&text);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].0.timestamp(), 1717405200);
+        assert_eq!(commits[0].1, "Start the parser");
+        assert_eq!(commits[1].1, "Fix: handle empty input");
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::Datelike;
+
+use crate::config::Config;
+
+/// The day weeks start on.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+/// How weeks are numbered within a year.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekNumbering {
+    /// Week 1 is the week with 4 January in it, as in ISO 8601.
+    #[default]
+    Iso,
+    /// Week 1 is the week with 1 January in it, as in the United States.
+    Us,
+}
+
+/// The configured week convention, used for `--week` periods, week numbers
+/// and weekly totals.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Weeks {
+    pub start: WeekStart,
+    pub numbering: WeekNumbering,
+}
+
+impl Default for Weeks {
+    fn default() -> Weeks {
+        Weeks {
+            start: WeekStart::Monday,
+            numbering: WeekNumbering::Iso,
+        }
+    }
+}
+
+impl Weeks {
+    pub fn from_config(config: &Config) -> Weeks {
+        Weeks {
+            start: config.week_start,
+            numbering: config.week_numbering,
+        }
+    }
+
+    pub fn start_day(&self) -> chrono::Weekday {
+        match self.start {
+            WeekStart::Monday => chrono::Weekday::Mon,
+            WeekStart::Sunday => chrono::Weekday::Sun,
+        }
+    }
+
+    /// The first day of the week `date` is in.
+    pub fn first_day(&self, date: chrono::NaiveDate) -> chrono::NaiveDate {
+        date.week(self.start_day()).first_day()
+    }
+
+    /// The first day of week 1 of `year`.
+    fn week_one(&self, year: i32) -> chrono::NaiveDate {
+        let day = match self.numbering {
+            WeekNumbering::Iso => 4,
+            WeekNumbering::Us => 1,
+        };
+        self.first_day(chrono::NaiveDate::from_ymd_opt(year, 1, day).unwrap())
+    }
+
+    /// The year and number of the week `date` is in. Days at the turn of the
+    /// year can belong to a week of the year before or after.
+    pub fn number(&self, date: chrono::NaiveDate) -> (i32, u32) {
+        let mut year = date.year();
+        if date >= self.week_one(year + 1) {
+            year += 1;
+        } else if date < self.week_one(year) {
+            year -= 1;
+        }
+        let days = (self.first_day(date) - self.week_one(year)).num_days();
+        (year, days as u32 / 7 + 1)
+    }
+
+    /// The first day of week `week` of `year`, if the year has that week.
+    pub fn nth(&self, year: i32, week: u32) -> Option<chrono::NaiveDate> {
+        if week == 0 {
+            return None;
+        }
+        let first = self
+            .week_one(year)
+            .checked_add_days(chrono::Days::new(7 * (week as u64 - 1)))?;
+        (self.number(first) == (year, week)).then_some(first)
+    }
+
+    /// The week of `date` written like `2024-W11`.
+    pub fn label(&self, date: chrono::NaiveDate) -> String {
+        let (year, week) = self.number(date);
+        format!("{}-W{:02}", year, week)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number() {
+        let date = |y, m, d| chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let iso = Weeks::default();
+        for day in [date(2024, 6, 5), date(2021, 1, 3), date(2026, 12, 31)] {
+            assert_eq!(
+                iso.number(day),
+                (day.iso_week().year(), day.iso_week().week())
+            );
+        }
+        assert_eq!(iso.nth(2024, 23), Some(date(2024, 6, 3)));
+        assert_eq!(iso.nth(2024, 53), None);
+        assert_eq!(iso.label(date(2024, 3, 13)), "2024-W11");
+
+        let us = Weeks {
+            start: WeekStart::Sunday,
+            numbering: WeekNumbering::Us,
+        };
+        assert_eq!(us.first_day(date(2024, 6, 5)), date(2024, 6, 2));
+        assert_eq!(us.number(date(2024, 1, 1)), (2024, 1));
+        assert_eq!(us.number(date(2023, 12, 31)), (2024, 1));
+        assert_eq!(us.number(date(2024, 6, 5)), (2024, 23));
+        assert_eq!(us.nth(2024, 23), Some(date(2024, 6, 2)));
+    }
+}
@@ -0,0 +1,40 @@
+use std::process::Command;
+
+/// Runs jobclock with its data and config in a folder of its own.
+fn jobclock(folder: &std::path::Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_jobclock"))
+        .args(args)
+        .env("JOBCLOCK_DATA_DIR", folder.join("data"))
+        .env("XDG_CONFIG_HOME", folder.join("config"))
+        .env_remove("JOBCLOCK_PROFILE")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_exit_status() {
+    let folder = std::env::temp_dir().join(format!("jobclock-exit-{}", std::process::id()));
+
+    assert_eq!(jobclock(&folder, &["status"]).status.code(), Some(0));
+    let end = jobclock(&folder, &["--json", "end"]);
+    assert_eq!(end.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&end.stdout).contains("NO_ACTIVE_SESSION"));
+    assert_eq!(jobclock(&folder, &["frobnicate"]).status.code(), Some(2));
+    assert_eq!(
+        jobclock(&folder, &["--json", "report", "--from", "March"])
+            .status
+            .code(),
+        Some(2)
+    );
+    assert_eq!(jobclock(&folder, &["show"]).status.code(), Some(2));
+    let week_start = Command::new(env!("CARGO_BIN_EXE_jobclock"))
+        .arg("status")
+        .env("JOBCLOCK_DATA_DIR", folder.join("data"))
+        .env("XDG_CONFIG_HOME", folder.join("config"))
+        .env("JOBCLOCK_WEEK_START", "someday")
+        .output()
+        .unwrap();
+    assert_eq!(week_start.status.code(), Some(2));
+
+    std::fs::remove_dir_all(&folder).ok();
+}